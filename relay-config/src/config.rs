@@ -1,3 +1,4 @@
+use std::collections::{BTreeMap, BTreeSet};
 use std::env;
 use std::fmt;
 use std::fs;
@@ -564,6 +565,83 @@ impl Default for Cache {
     }
 }
 
+/// Request metadata that can be added to events by enrichment.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RequestField {
+    /// The SDK client identifier from the authentication header.
+    Client,
+    /// The value of the `User-Agent` header.
+    UserAgent,
+    /// The value of the `Origin` or `Referer` header.
+    Origin,
+}
+
+/// The source of a value that is added to events by enrichment.
+#[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum EnrichmentValue {
+    /// A fixed value, such as the region of a Relay.
+    Static(String),
+    /// The value of a request header, written as `{"header": "X-Region"}`.
+    Header {
+        /// The case-insensitive name of the header.
+        header: String,
+    },
+    /// Metadata of the request, written as `{"request": "user_agent"}`.
+    Request {
+        /// The field of the request metadata.
+        request: RequestField,
+    },
+}
+
+/// Tags and context fields added to events from static values and request metadata.
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct EnrichmentConfig {
+    /// Tags added to events, keyed by tag key.
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    pub tags: BTreeMap<String, EnrichmentValue>,
+    /// Fields added to custom contexts of events, keyed by context name and field name.
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    pub contexts: BTreeMap<String, BTreeMap<String, EnrichmentValue>>,
+    /// Replace tags and context fields sent by the SDK. Defaults to `false`, which only adds
+    /// values that are missing in the event.
+    pub overwrite: bool,
+}
+
+impl EnrichmentConfig {
+    /// Returns `true` if this config does not add any values.
+    pub fn is_empty(&self) -> bool {
+        self.tags.is_empty() && self.contexts.is_empty()
+    }
+
+    /// Returns the names of all request headers referenced by this config.
+    pub fn headers(&self) -> impl Iterator<Item = &str> {
+        self.tags
+            .values()
+            .chain(self.contexts.values().flat_map(BTreeMap::values))
+            .filter_map(|value| match value {
+                EnrichmentValue::Header { header } => Some(header.as_str()),
+                _ => None,
+            })
+    }
+}
+
+/// Controls enrichment of events with tags and contexts in Relay.
+#[derive(Serialize, Deserialize, Debug, Default)]
+#[serde(default)]
+struct Enrichment {
+    /// Additional request headers to capture for enrichment configured in project configs.
+    ///
+    /// Headers referenced in this section are captured automatically.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    capture_headers: Vec<String>,
+    /// Tags and context fields added to all events processed by this Relay.
+    #[serde(flatten)]
+    config: EnrichmentConfig,
+}
+
 /// Controls interal reporting to Sentry.
 #[derive(Serialize, Deserialize, Debug)]
 #[serde(default)]
@@ -583,6 +661,47 @@ impl Default for Sentry {
     }
 }
 
+/// Controls PII processing in Relay.
+#[derive(Serialize, Deserialize)]
+#[serde(default)]
+struct Pii {
+    /// Secret keys for the `pseudonymize` redaction, indexed by their key id.
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    pseudonymization_keys: BTreeMap<String, String>,
+    /// Path to a JSON file containing additional pseudonymization keys.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pseudonymization_keys_path: Option<PathBuf>,
+    /// The maximum size of text attachments that are scrubbed. Larger attachments are skipped.
+    max_attachment_size: ByteSize,
+}
+
+impl Default for Pii {
+    fn default() -> Self {
+        Pii {
+            pseudonymization_keys: BTreeMap::new(),
+            pseudonymization_keys_path: None,
+            max_attachment_size: ByteSize::from_megabytes(10),
+        }
+    }
+}
+
+impl fmt::Debug for Pii {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        // Never print secrets, only the ids of configured keys.
+        f.debug_struct("Pii")
+            .field(
+                "pseudonymization_keys",
+                &self.pseudonymization_keys.keys().collect::<Vec<_>>(),
+            )
+            .field(
+                "pseudonymization_keys_path",
+                &self.pseudonymization_keys_path,
+            )
+            .field("max_attachment_size", &self.max_attachment_size)
+            .finish()
+    }
+}
+
 /// Define the topics over which Relay communicates with Sentry.
 #[derive(Clone, Copy, Debug, Eq, PartialEq)]
 pub enum KafkaTopic {
@@ -663,6 +782,9 @@ pub struct Processing {
     /// GeoIp DB file source.
     #[serde(default)]
     pub geoip_path: Option<PathBuf>,
+    /// GeoIp ASN DB file source.
+    #[serde(default)]
+    pub geoip_asn_path: Option<PathBuf>,
     /// Maximum future timestamp of ingested events.
     #[serde(default = "default_max_secs_in_future")]
     pub max_secs_in_future: u32,
@@ -694,6 +816,7 @@ impl Default for Processing {
         Self {
             enabled: false,
             geoip_path: None,
+            geoip_asn_path: None,
             max_secs_in_future: 0,
             max_secs_in_past: 0,
             kafka_config: Vec::new(),
@@ -753,6 +876,10 @@ struct ConfigValues {
     sentry: Sentry,
     #[serde(default)]
     processing: Processing,
+    #[serde(default)]
+    pii: Pii,
+    #[serde(default)]
+    enrichment: Enrichment,
 }
 
 impl ConfigObject for ConfigValues {
@@ -768,6 +895,7 @@ impl ConfigObject for ConfigValues {
 pub struct Config {
     values: ConfigValues,
     credentials: Option<Credentials>,
+    pseudonymization_keys: BTreeMap<String, String>,
     path: PathBuf,
 }
 
@@ -787,12 +915,16 @@ impl Config {
             .map(|x| x.join(path.as_ref()))
             .unwrap_or_else(|_| path.as_ref().to_path_buf());
 
+        let values = ConfigValues::load(&path)?;
+        let pseudonymization_keys = load_pseudonymization_keys(&values.pii, &path)?;
+
         let config = Config {
-            values: ConfigValues::load(&path)?,
+            values,
             credentials: match fs::metadata(Credentials::path(&path)) {
                 Ok(_) => Some(Credentials::load(&path)?),
                 Err(_) => None,
             },
+            pseudonymization_keys,
             path: path.clone(),
         };
 
@@ -1248,6 +1380,11 @@ impl Config {
         self.values.processing.geoip_path.as_deref()
     }
 
+    /// The path to the optional GeoIp ASN database used to enrich events with network information.
+    pub fn geoip_asn_path(&self) -> Option<&Path> {
+        self.values.processing.geoip_asn_path.as_deref()
+    }
+
     /// Maximum future timestamp of ingested events.
     pub fn max_secs_in_future(&self) -> i64 {
         self.values.processing.max_secs_in_future.into()
@@ -1295,6 +1432,59 @@ impl Config {
     pub fn max_rate_limit(&self) -> Option<u64> {
         self.values.processing.max_rate_limit.map(u32::into)
     }
+
+    /// Secret keys for the `pseudonymize` PII redaction, indexed by their key id.
+    ///
+    /// This merges keys configured inline with those from `pii.pseudonymization_keys_path`.
+    pub fn pseudonymization_keys(&self) -> &BTreeMap<String, String> {
+        &self.pseudonymization_keys
+    }
+
+    /// Returns the maximum size of text attachments that are scrubbed for PII in bytes.
+    pub fn max_scrubbed_attachment_size(&self) -> usize {
+        self.values.pii.max_attachment_size.as_bytes() as usize
+    }
+
+    /// Returns the tags and context fields that this Relay adds to all events.
+    pub fn enrichment(&self) -> &EnrichmentConfig {
+        &self.values.enrichment.config
+    }
+
+    /// Returns the lowercase names of request headers captured for enrichment.
+    ///
+    /// This includes headers referenced by the enrichment config of this Relay as well as
+    /// `enrichment.capture_headers`.
+    pub fn enrichment_headers(&self) -> BTreeSet<String> {
+        let enrichment = &self.values.enrichment;
+        enrichment
+            .capture_headers
+            .iter()
+            .map(String::as_str)
+            .chain(enrichment.config.headers())
+            .map(str::to_lowercase)
+            .collect()
+    }
+}
+
+/// Merges inline pseudonymization keys with the contents of the secrets file, if configured.
+///
+/// Relative paths are resolved against the config directory. Keys from the file take precedence.
+fn load_pseudonymization_keys(
+    pii: &Pii,
+    base: &Path,
+) -> Result<BTreeMap<String, String>, ConfigError> {
+    let mut keys = pii.pseudonymization_keys.clone();
+
+    if let Some(ref keys_path) = pii.pseudonymization_keys_path {
+        let path = base.join(keys_path);
+        let f = fs::File::open(&path)
+            .map_err(|e| ConfigError::wrap(e, ConfigErrorKind::CouldNotOpenFile).file(&path))?;
+        let file_keys: BTreeMap<String, String> = serde_json::from_reader(io::BufReader::new(f))
+            .map_err(|e| ConfigError::wrap(e, ConfigErrorKind::BadJson).file(&path))?;
+        keys.extend(file_keys);
+    }
+
+    Ok(keys)
 }
 
 impl Default for Config {
@@ -1302,6 +1492,7 @@ impl Default for Config {
         Self {
             values: ConfigValues::default(),
             credentials: None,
+            pseudonymization_keys: BTreeMap::new(),
             path: PathBuf::new(),
         }
     }
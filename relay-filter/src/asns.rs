@@ -0,0 +1,75 @@
+//! Implements event filtering based on the autonomous system of the client.
+//!
+//! A project may be configured with blacklisted autonomous system numbers (ASNs), such as those
+//! of known scanners or cloud providers running crawlers. The ASN is read from the `network`
+//! context, which is populated during store normalization if an ASN database is configured. In
+//! that case, network contexts sent by clients are replaced.
+
+use relay_general::protocol::{Context, Event, NetworkContext};
+
+use crate::{AsnsFilterConfig, FilterStatKey};
+
+/// Returns the autonomous system number from the event's network context.
+fn get_asn(event: &Event) -> Option<u64> {
+    let contexts = event.contexts.value()?;
+    let context = contexts.get(NetworkContext::default_key())?.value()?;
+
+    match **context {
+        Context::Network(ref network) => network.asn.value().copied(),
+        _ => None,
+    }
+}
+
+/// Filters events from blacklisted autonomous systems.
+pub fn should_filter(event: &Event, config: &AsnsFilterConfig) -> Result<(), FilterStatKey> {
+    if config.blacklisted_asns.is_empty() {
+        return Ok(());
+    }
+
+    match get_asn(event) {
+        Some(asn) if config.blacklisted_asns.contains(&asn) => Err(FilterStatKey::Asn),
+        _ => Ok(()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use relay_general::protocol::Contexts;
+    use relay_general::types::Annotated;
+
+    fn get_event_with_asn(asn: u64) -> Event {
+        let mut contexts = Contexts::new();
+        contexts.add(Context::Network(Box::new(NetworkContext {
+            asn: Annotated::new(asn),
+            ..NetworkContext::default()
+        })));
+
+        Event {
+            contexts: Annotated::new(contexts),
+            ..Event::default()
+        }
+    }
+
+    #[test]
+    fn test_should_filter_blacklisted_asns() {
+        let config = AsnsFilterConfig {
+            blacklisted_asns: vec![14061, 16509],
+        };
+
+        let event = get_event_with_asn(16509);
+        assert_eq!(should_filter(&event, &config), Err(FilterStatKey::Asn));
+
+        let event = get_event_with_asn(15169);
+        assert_eq!(should_filter(&event, &config), Ok(()));
+
+        assert_eq!(should_filter(&Event::default(), &config), Ok(()));
+    }
+
+    #[test]
+    fn test_should_not_filter_without_config() {
+        let event = get_event_with_asn(16509);
+        assert_eq!(should_filter(&event, &AsnsFilterConfig::default()), Ok(()));
+    }
+}
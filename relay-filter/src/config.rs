@@ -104,6 +104,21 @@ impl ClientIpsFilterConfig {
     }
 }
 
+/// Configuration for the autonomous systems filter.
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AsnsFilterConfig {
+    /// Blacklisted autonomous system numbers.
+    pub blacklisted_asns: Vec<u64>,
+}
+
+impl AsnsFilterConfig {
+    /// Returns true if no configuration for this filter is given.
+    pub fn is_empty(&self) -> bool {
+        self.blacklisted_asns.is_empty()
+    }
+}
+
 /// Configuration for the CSP filter.
 #[derive(Clone, Debug, Default, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
@@ -177,6 +192,10 @@ pub struct FiltersConfig {
     #[serde(default, skip_serializing_if = "ClientIpsFilterConfig::is_empty")]
     pub client_ips: ClientIpsFilterConfig,
 
+    /// Configuration for the autonomous systems filter.
+    #[serde(default, skip_serializing_if = "AsnsFilterConfig::is_empty")]
+    pub asns: AsnsFilterConfig,
+
     /// Configuration for the Web Crawlers filter
     #[serde(default, skip_serializing_if = "FilterConfig::is_empty")]
     pub web_crawlers: FilterConfig,
@@ -207,6 +226,7 @@ impl FiltersConfig {
     pub fn is_empty(&self) -> bool {
         self.browser_extensions.is_empty()
             && self.client_ips.is_empty()
+            && self.asns.is_empty()
             && self.web_crawlers.is_empty()
             && self.csp.is_empty()
             && self.error_messages.is_empty()
@@ -231,6 +251,9 @@ mod tests {
             client_ips: ClientIpsFilterConfig {
                 blacklisted_ips: [],
             },
+            asns: AsnsFilterConfig {
+                blacklisted_asns: [],
+            },
             web_crawlers: FilterConfig {
                 is_enabled: false,
             },
@@ -268,6 +291,9 @@ mod tests {
             client_ips: ClientIpsFilterConfig {
                 blacklisted_ips: vec!["127.0.0.1".to_string()],
             },
+            asns: AsnsFilterConfig {
+                blacklisted_asns: vec![16509],
+            },
             web_crawlers: FilterConfig { is_enabled: true },
             csp: CspFilterConfig {
                 disallowed_sources: vec!["https://*".to_string()],
@@ -295,6 +321,11 @@ mod tests {
               "127.0.0.1"
             ]
           },
+          "asns": {
+            "blacklistedAsns": [
+              16509
+            ]
+          },
           "webCrawlers": {
             "isEnabled": true
           },
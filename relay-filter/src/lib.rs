@@ -6,12 +6,14 @@
 //! * browser extensions (filter events caused by known problematic browser extensions)
 //! * web crawlers (filter events sent by user agents known to be web crawlers)
 //! * legacy browsers (filter events originating from legacy browsers, can be configured)
+//! * autonomous systems (filter events from the networks of known scanners or crawlers)
 #![warn(missing_docs)]
 
 use std::net::IpAddr;
 
 use relay_general::protocol::Event;
 
+mod asns;
 mod browser_extensions;
 mod client_ips;
 mod common;
@@ -44,6 +46,7 @@ pub fn should_filter(
 
     csp::should_filter(event, &config.csp)?;
     client_ips::should_filter(client_ip, &config.client_ips)?;
+    asns::should_filter(event, &config.asns)?;
     releases::should_filter(event, &config.releases)?;
     error_messages::should_filter(event, &config.error_messages)?;
     localhost::should_filter(event, &config.localhost)?;
@@ -86,6 +86,13 @@ fn bench_store_processor(c: &mut Criterion) {
         grouping_config: None,
         is_renormalize: Some(false),
         normalize_user_agent: Some(false),
+        demangle_symbols: Some(false),
+        parse_raw_stacktraces: Some(false),
+        breakdowns: None,
+        repair_span_tree: Some(false),
+        transaction_names: None,
+        split_breadcrumb_urls: Some(false),
+        stacktrace_rules: None,
         remove_other: Some(true),
         user_agent: None,
         sent_at: None,
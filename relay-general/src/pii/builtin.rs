@@ -4,8 +4,8 @@ use std::collections::BTreeMap;
 use lazy_static::lazy_static;
 
 use crate::pii::{
-    AliasRule, HashRedaction, MaskRedaction, MultipleRule, PatternRule, Redaction,
-    ReplaceRedaction, RuleSpec, RuleType,
+    AliasRule, HashRedaction, MaskRedaction, MultipleRule, PatternRule, PseudonymizeRedaction,
+    Redaction, ReplaceRedaction, RuleSpec, RuleType,
 };
 
 macro_rules! declare_builtin_rules {
@@ -91,6 +91,10 @@ declare_builtin_rules! {
         ty: RuleType::Anything,
         redaction: Redaction::Mask(MaskRedaction::default()),
     };
+    "@anything:pseudonymize" => RuleSpec {
+        ty: RuleType::Anything,
+        redaction: Redaction::Pseudonymize(PseudonymizeRedaction::default()),
+    };
 
     // ip rules
     "@ip" => rule_alias!("@ip:replace");
@@ -108,6 +112,10 @@ declare_builtin_rules! {
         ty: RuleType::Ip,
         redaction: Redaction::Mask(MaskRedaction::default()),
     };
+    "@ip:pseudonymize" => RuleSpec {
+        ty: RuleType::Ip,
+        redaction: Redaction::Pseudonymize(PseudonymizeRedaction::default()),
+    };
     "@ip:remove" => RuleSpec {
         ty: RuleType::Ip,
         redaction: Redaction::Remove,
@@ -208,6 +216,10 @@ declare_builtin_rules! {
         ty: RuleType::Email,
         redaction: Redaction::Remove,
     };
+    "@email:pseudonymize" => RuleSpec {
+        ty: RuleType::Email,
+        redaction: Redaction::Pseudonymize(PseudonymizeRedaction::default()),
+    };
 
     // creditcard rules
     "@creditcard" => rule_alias!("@creditcard:replace");
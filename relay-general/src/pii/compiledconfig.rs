@@ -3,7 +3,7 @@ use std::collections::BTreeSet;
 
 use crate::pii::builtin::BUILTIN_RULES_MAP;
 use crate::pii::{PiiConfig, Redaction, RuleSpec, RuleType};
-use crate::processor::SelectorSpec;
+use crate::processor::{PredicateOperand, SelectorSpec};
 
 /// A representation of `PiiConfig` that is more (CPU-)efficient for use in `PiiProcessor`. It is
 /// lossy in the sense that it cannot be consumed by downstream relays, so both versions have to be
@@ -11,12 +11,27 @@ use crate::processor::SelectorSpec;
 #[derive(Debug, Clone)]
 pub struct CompiledPiiConfig {
     pub(super) applications: Vec<(SelectorSpec, BTreeSet<RuleRef>)>,
+    /// Whether any selector has a predicate on the value, such as `[?(@ ~ '^tok_')]`.
+    pub(super) matches_values: bool,
+    /// Whether any selector has a predicate on a sibling, such as `[?(@key == 'password')]`.
+    pub(super) matches_siblings: bool,
 }
 
 impl CompiledPiiConfig {
     pub fn new(config: &PiiConfig) -> Self {
         let mut applications = Vec::new();
+        let mut matches_values = false;
+        let mut matches_siblings = false;
         for (selector, rules) in &config.applications {
+            matches_values |= has_predicate(selector, &|operand| match operand {
+                PredicateOperand::Value => true,
+                PredicateOperand::Sibling(_) => false,
+            });
+            matches_siblings |= has_predicate(selector, &|operand| match operand {
+                PredicateOperand::Value => false,
+                PredicateOperand::Sibling(_) => true,
+            });
+
             #[allow(clippy::mutable_key_type)]
             let mut rule_set = BTreeSet::default();
             for rule_id in rules {
@@ -25,7 +40,25 @@ impl CompiledPiiConfig {
             applications.push((selector.clone(), rule_set));
         }
 
-        CompiledPiiConfig { applications }
+        CompiledPiiConfig {
+            applications,
+            matches_values,
+            matches_siblings,
+        }
+    }
+}
+
+/// Checks whether the selector contains a predicate with an operand accepted by `f`.
+fn has_predicate(selector: &SelectorSpec, f: &dyn Fn(&PredicateOperand) -> bool) -> bool {
+    match *selector {
+        SelectorSpec::And(ref xs) | SelectorSpec::Or(ref xs) => {
+            xs.iter().any(|x| has_predicate(x, f))
+        }
+        SelectorSpec::Not(ref x) => has_predicate(x, f),
+        SelectorSpec::Path(_) => false,
+        SelectorSpec::Predicate(ref x, ref predicate) => {
+            f(&predicate.operand) || has_predicate(x, f)
+        }
     }
 }
 
@@ -189,6 +189,30 @@ impl<'de> Deserialize<'de> for RuleType {
     }
 }
 
+impl RuleType {
+    /// Returns the name of this rule type as used in the `type` field of rule configs.
+    pub fn name(&self) -> &'static str {
+        match self {
+            RuleType::Anything => "anything",
+            RuleType::Pattern(_) => "pattern",
+            RuleType::Imei => "imei",
+            RuleType::Mac => "mac",
+            RuleType::Uuid => "uuid",
+            RuleType::Email => "email",
+            RuleType::Ip => "ip",
+            RuleType::Creditcard => "creditcard",
+            RuleType::Userpath => "userpath",
+            RuleType::Pemkey => "pemkey",
+            RuleType::UrlAuth => "url_auth",
+            RuleType::UsSsn => "us_ssn",
+            RuleType::Password => "password",
+            RuleType::RedactPair(_) => "redact_pair",
+            RuleType::Multiple(_) => "multiple",
+            RuleType::Alias(_) => "alias",
+        }
+    }
+}
+
 /// A single rule configuration.
 #[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
 pub struct RuleSpec {
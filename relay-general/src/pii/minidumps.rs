@@ -0,0 +1,440 @@
+//! Locating memory regions and strings in minidumps.
+//!
+//! Only the parts of the minidump format required to find memory regions are parsed: the stream
+//! directory, the thread list, and the two memory lists. All other data is left as-is, so that
+//! scrubbing memory in place keeps the file structurally valid.
+use std::ops::Range;
+
+use failure::Fail;
+
+/// Minidump signature `MDMP` in little endian.
+const MINIDUMP_SIGNATURE: u32 = 0x504d_444d;
+
+const THREAD_LIST_STREAM: u32 = 3;
+const MEMORY_LIST_STREAM: u32 = 5;
+const MEMORY64_LIST_STREAM: u32 = 9;
+
+/// Size of a `MINIDUMP_DIRECTORY` entry.
+const DIRECTORY_ENTRY_SIZE: usize = 12;
+/// Size of a `MINIDUMP_THREAD` entry.
+const THREAD_SIZE: usize = 48;
+/// Offset of the stack memory descriptor within `MINIDUMP_THREAD`.
+const THREAD_STACK_OFFSET: usize = 24;
+/// Size of a `MINIDUMP_MEMORY_DESCRIPTOR` entry.
+const MEMORY_DESCRIPTOR_SIZE: usize = 16;
+/// Size of a `MINIDUMP_MEMORY_DESCRIPTOR64` entry.
+const MEMORY_DESCRIPTOR64_SIZE: usize = 16;
+
+/// The minimum number of characters for strings found in memory.
+const MIN_STRING_LEN: usize = 5;
+
+/// An error returned when parsing a minidump fails.
+#[derive(Debug, Fail, Clone, Copy, Eq, PartialEq)]
+pub enum ScrubMinidumpError {
+    /// The file does not start with a minidump header.
+    #[fail(display = "invalid minidump header")]
+    InvalidHeader,
+
+    /// A stream or memory region points outside of the file.
+    #[fail(display = "minidump data out of bounds")]
+    OutOfBounds,
+}
+
+/// The kind of memory captured in a minidump.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum MemoryRegionKind {
+    /// Stack memory of a thread.
+    Stack,
+    /// Any other memory, usually heap memory referenced from stacks.
+    Heap,
+}
+
+impl MemoryRegionKind {
+    /// The key of this memory kind in selectors, such as `$minidump.stack_memory`.
+    pub fn key(self) -> &'static str {
+        match self {
+            MemoryRegionKind::Stack => "stack_memory",
+            MemoryRegionKind::Heap => "heap_memory",
+        }
+    }
+}
+
+/// A region of memory within the minidump file.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct MemoryRegion {
+    /// The kind of memory.
+    pub kind: MemoryRegionKind,
+    /// The byte range of the memory contents within the file.
+    pub range: Range<usize>,
+}
+
+/// The text encoding of a string found in memory.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum StringEncoding {
+    Utf8,
+    Utf16Le,
+}
+
+struct Reader<'a> {
+    data: &'a [u8],
+}
+
+impl<'a> Reader<'a> {
+    fn bytes(&self, offset: usize, len: usize) -> Result<&'a [u8], ScrubMinidumpError> {
+        let end = offset
+            .checked_add(len)
+            .ok_or(ScrubMinidumpError::OutOfBounds)?;
+        self.data
+            .get(offset..end)
+            .ok_or(ScrubMinidumpError::OutOfBounds)
+    }
+
+    fn u32(&self, offset: usize) -> Result<u32, ScrubMinidumpError> {
+        let mut buf = [0; 4];
+        buf.copy_from_slice(self.bytes(offset, 4)?);
+        Ok(u32::from_le_bytes(buf))
+    }
+
+    fn u64(&self, offset: usize) -> Result<u64, ScrubMinidumpError> {
+        let mut buf = [0; 8];
+        buf.copy_from_slice(self.bytes(offset, 8)?);
+        Ok(u64::from_le_bytes(buf))
+    }
+
+    fn range(&self, offset: u64, len: u64) -> Result<Range<usize>, ScrubMinidumpError> {
+        let start = offset as usize;
+        let end = start
+            .checked_add(len as usize)
+            .ok_or(ScrubMinidumpError::OutOfBounds)?;
+        if end > self.data.len() {
+            return Err(ScrubMinidumpError::OutOfBounds);
+        }
+        Ok(start..end)
+    }
+}
+
+/// Returns all memory regions captured in a minidump.
+///
+/// Memory referenced as the stack of a thread is reported as stack memory, all other regions of
+/// the memory lists as heap memory. Regions are reported once, even if multiple streams refer to
+/// them.
+pub fn memory_regions(data: &[u8]) -> Result<Vec<MemoryRegion>, ScrubMinidumpError> {
+    let reader = Reader { data };
+
+    let signature = reader
+        .u32(0)
+        .map_err(|_| ScrubMinidumpError::InvalidHeader)?;
+    if signature != MINIDUMP_SIGNATURE {
+        return Err(ScrubMinidumpError::InvalidHeader);
+    }
+
+    let stream_count = reader.u32(8)? as usize;
+    let directory_offset = reader.u32(12)? as usize;
+
+    let mut stacks = Vec::new();
+    let mut memory = Vec::new();
+
+    for index in 0..stream_count {
+        let entry = directory_offset + index * DIRECTORY_ENTRY_SIZE;
+        let stream_type = reader.u32(entry)?;
+        let stream = reader.u32(entry + 8)? as usize;
+
+        match stream_type {
+            THREAD_LIST_STREAM => {
+                let count = reader.u32(stream)? as usize;
+                for thread in 0..count {
+                    let descriptor = stream + 4 + thread * THREAD_SIZE + THREAD_STACK_OFFSET;
+                    let size = reader.u32(descriptor + 8)?;
+                    let rva = reader.u32(descriptor + 12)?;
+                    stacks.push(reader.range(rva.into(), size.into())?);
+                }
+            }
+            MEMORY_LIST_STREAM => {
+                let count = reader.u32(stream)? as usize;
+                for region in 0..count {
+                    let descriptor = stream + 4 + region * MEMORY_DESCRIPTOR_SIZE;
+                    let size = reader.u32(descriptor + 8)?;
+                    let rva = reader.u32(descriptor + 12)?;
+                    memory.push(reader.range(rva.into(), size.into())?);
+                }
+            }
+            MEMORY64_LIST_STREAM => {
+                let count = reader.u64(stream)? as usize;
+                let mut rva = reader.u64(stream + 8)?;
+                for region in 0..count {
+                    let descriptor = stream + 16 + region * MEMORY_DESCRIPTOR64_SIZE;
+                    let size = reader.u64(descriptor + 8)?;
+                    memory.push(reader.range(rva, size)?);
+                    rva = rva.saturating_add(size);
+                }
+            }
+            _ => (),
+        }
+    }
+
+    let mut regions = Vec::with_capacity(stacks.len() + memory.len());
+    for range in stacks {
+        if !regions.iter().any(|r: &MemoryRegion| r.range == range) {
+            regions.push(MemoryRegion {
+                kind: MemoryRegionKind::Stack,
+                range,
+            });
+        }
+    }
+    for range in memory {
+        if !regions.iter().any(|r: &MemoryRegion| r.range == range) {
+            regions.push(MemoryRegion {
+                kind: MemoryRegionKind::Heap,
+                range,
+            });
+        }
+    }
+
+    Ok(regions)
+}
+
+/// Returns regions covering the entire file as both stack and heap memory.
+///
+/// This is used for files that cannot be parsed, so that rules for either kind of memory apply.
+pub fn file_regions(data: &[u8]) -> Vec<MemoryRegion> {
+    [MemoryRegionKind::Stack, MemoryRegionKind::Heap]
+        .iter()
+        .map(|&kind| MemoryRegion {
+            kind,
+            range: 0..data.len(),
+        })
+        .collect()
+}
+
+/// Returns the byte ranges of printable UTF-8 strings in the given memory.
+pub fn utf8_strings(data: &[u8]) -> Vec<Range<usize>> {
+    let mut strings = Vec::new();
+    let mut start = None;
+    let mut chars = 0;
+    let mut offset = 0;
+
+    while offset < data.len() {
+        let width = match data[offset] {
+            0x00..=0x7f => 1,
+            0xc2..=0xdf => 2,
+            0xe0..=0xef => 3,
+            0xf0..=0xf4 => 4,
+            _ => 0,
+        };
+
+        let printable = width > 0
+            && data
+                .get(offset..offset + width)
+                .and_then(|bytes| std::str::from_utf8(bytes).ok())
+                .and_then(|s| s.chars().next())
+                .map_or(false, |c| !c.is_control());
+
+        if printable {
+            start.get_or_insert(offset);
+            chars += 1;
+            offset += width;
+        } else {
+            if let Some(start) = start.take() {
+                if chars >= MIN_STRING_LEN {
+                    strings.push(start..offset);
+                }
+            }
+            chars = 0;
+            offset += 1;
+        }
+    }
+
+    if let Some(start) = start {
+        if chars >= MIN_STRING_LEN {
+            strings.push(start..data.len());
+        }
+    }
+
+    strings
+}
+
+/// Returns the byte ranges of UTF-16 strings in the given memory.
+///
+/// To avoid reading arbitrary binary data as text, only strings consisting of printable Latin-1
+/// characters are considered. Strings are expected at even offsets.
+pub fn utf16_strings(data: &[u8]) -> Vec<Range<usize>> {
+    let mut strings = Vec::new();
+    let mut start = None;
+
+    let mut offset = 0;
+    while offset + 1 < data.len() {
+        let printable = match (data[offset], data[offset + 1]) {
+            (0x20..=0x7e, 0) | (0xa0..=0xff, 0) => true,
+            _ => false,
+        };
+
+        if printable {
+            start.get_or_insert(offset);
+        } else if let Some(start) = start.take() {
+            if (offset - start) / 2 >= MIN_STRING_LEN {
+                strings.push(start..offset);
+            }
+        }
+
+        offset += 2;
+    }
+
+    if let Some(start) = start {
+        if (offset - start) / 2 >= MIN_STRING_LEN {
+            strings.push(start..offset);
+        }
+    }
+
+    strings
+}
+
+/// Decodes a string found in memory.
+pub fn decode_string(data: &[u8], encoding: StringEncoding) -> String {
+    match encoding {
+        StringEncoding::Utf8 => String::from_utf8_lossy(data).into_owned(),
+        StringEncoding::Utf16Le => {
+            let units = data
+                .chunks_exact(2)
+                .map(|pair| u16::from_le_bytes([pair[0], pair[1]]));
+            std::char::decode_utf16(units)
+                .map(|c| c.unwrap_or(std::char::REPLACEMENT_CHARACTER))
+                .collect()
+        }
+    }
+}
+
+/// Writes a scrubbed string back into memory without changing its size.
+///
+/// The encoded string is truncated if it is longer than the original, or padded with zeros if it
+/// is shorter.
+pub fn encode_string(data: &mut [u8], string: &str, encoding: StringEncoding) {
+    let mut encoded = match encoding {
+        StringEncoding::Utf8 => string.as_bytes().to_vec(),
+        StringEncoding::Utf16Le => string
+            .encode_utf16()
+            .flat_map(|unit| unit.to_le_bytes().to_vec())
+            .collect(),
+    };
+
+    encoded.resize(data.len(), 0);
+    data.copy_from_slice(&encoded);
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+
+    /// Builds a minimal minidump with one thread and the given stack and heap memory.
+    ///
+    /// The stack is listed in both the thread list and the memory list, like in minidumps written
+    /// by Breakpad and Crashpad.
+    pub fn build_minidump(stack: &[u8], heap: &[u8]) -> Vec<u8> {
+        let header_size = 32;
+        let directory_offset = header_size;
+        let thread_list_offset = directory_offset + 2 * DIRECTORY_ENTRY_SIZE;
+        let memory_list_offset = thread_list_offset + 4 + THREAD_SIZE;
+        let stack_offset = memory_list_offset + 4 + 2 * MEMORY_DESCRIPTOR_SIZE;
+        let heap_offset = stack_offset + stack.len();
+
+        let mut data = Vec::new();
+        let push_u32 = |data: &mut Vec<u8>, value: usize| {
+            data.extend_from_slice(&(value as u32).to_le_bytes())
+        };
+        let push_u64 =
+            |data: &mut Vec<u8>, value: u64| data.extend_from_slice(&value.to_le_bytes());
+
+        // MINIDUMP_HEADER
+        push_u32(&mut data, MINIDUMP_SIGNATURE as usize);
+        push_u32(&mut data, 0xa793);
+        push_u32(&mut data, 2);
+        push_u32(&mut data, directory_offset);
+        data.resize(header_size, 0);
+
+        // MINIDUMP_DIRECTORY
+        push_u32(&mut data, THREAD_LIST_STREAM as usize);
+        push_u32(&mut data, 4 + THREAD_SIZE);
+        push_u32(&mut data, thread_list_offset);
+        push_u32(&mut data, MEMORY_LIST_STREAM as usize);
+        push_u32(&mut data, 4 + 2 * MEMORY_DESCRIPTOR_SIZE);
+        push_u32(&mut data, memory_list_offset);
+
+        // MINIDUMP_THREAD_LIST
+        push_u32(&mut data, 1);
+        data.resize(thread_list_offset + 4 + THREAD_STACK_OFFSET, 0);
+        push_u64(&mut data, 0x7fff_0000);
+        push_u32(&mut data, stack.len());
+        push_u32(&mut data, stack_offset);
+        data.resize(memory_list_offset, 0);
+
+        // MINIDUMP_MEMORY_LIST
+        push_u32(&mut data, 2);
+        push_u64(&mut data, 0x7fff_0000);
+        push_u32(&mut data, stack.len());
+        push_u32(&mut data, stack_offset);
+        push_u64(&mut data, 0x1000_0000);
+        push_u32(&mut data, heap.len());
+        push_u32(&mut data, heap_offset);
+
+        data.extend_from_slice(stack);
+        data.extend_from_slice(heap);
+        data
+    }
+
+    #[test]
+    fn test_memory_regions() {
+        let data = build_minidump(b"stack memory", b"heap");
+        let regions = memory_regions(&data).unwrap();
+
+        assert_eq!(regions.len(), 2);
+        assert_eq!(regions[0].kind, MemoryRegionKind::Stack);
+        assert_eq!(&data[regions[0].range.clone()], b"stack memory");
+        assert_eq!(regions[1].kind, MemoryRegionKind::Heap);
+        assert_eq!(&data[regions[1].range.clone()], b"heap");
+    }
+
+    #[test]
+    fn test_memory_regions_invalid() {
+        assert_eq!(
+            memory_regions(b"MDMP"),
+            Err(ScrubMinidumpError::OutOfBounds)
+        );
+        assert_eq!(
+            memory_regions(b"not a minidump"),
+            Err(ScrubMinidumpError::InvalidHeader)
+        );
+
+        let mut data = build_minidump(b"stack memory", b"heap");
+        data.truncate(data.len() - 2);
+        assert_eq!(memory_regions(&data), Err(ScrubMinidumpError::OutOfBounds));
+    }
+
+    #[test]
+    fn test_utf8_strings() {
+        let data = b"\x00\x01hello world\x00abc\x00\xff\xc3\xa4bcdef";
+        let strings = utf8_strings(data);
+        assert_eq!(strings, vec![2..13, 19..26]);
+        assert_eq!(decode_string(&data[19..26], StringEncoding::Utf8), "äbcdef");
+    }
+
+    #[test]
+    fn test_utf16_strings() {
+        let data = b"\x01\x00h\x00e\x00l\x00l\x00o\x00\x00\x00a\x00b\x00";
+        let strings = utf16_strings(data);
+        assert_eq!(strings, vec![2..12]);
+        assert_eq!(
+            decode_string(&data[2..12], StringEncoding::Utf16Le),
+            "hello"
+        );
+    }
+
+    #[test]
+    fn test_encode_string() {
+        let mut data = *b"h\x00e\x00l\x00l\x00o\x00";
+        encode_string(&mut data, "[ip]", StringEncoding::Utf16Le);
+        assert_eq!(&data, b"[\x00i\x00p\x00]\x00\x00\x00");
+
+        let mut data = *b"hi";
+        encode_string(&mut data, "[ip]", StringEncoding::Utf8);
+        assert_eq!(&data, b"[i");
+    }
+}
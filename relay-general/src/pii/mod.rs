@@ -6,8 +6,10 @@ mod config;
 mod convert;
 mod generate_selectors;
 mod legacy;
+mod minidumps;
 mod processor;
 mod redactions;
+mod report;
 mod utils;
 
 pub use self::builtin::BUILTIN_RULES;
@@ -18,7 +20,11 @@ pub use self::config::{
 };
 pub use self::generate_selectors::selector_suggestions_from_value;
 pub use self::legacy::DataScrubbingConfig;
-pub use self::processor::PiiProcessor;
+pub use self::minidumps::ScrubMinidumpError;
+pub use self::processor::{PiiProcessor, PseudonymizationKeys};
 pub use self::redactions::{
-    HashAlgorithm, HashRedaction, MaskRedaction, Redaction, ReplaceRedaction,
+    HashAlgorithm, HashRedaction, MaskRedaction, PseudonymizeRedaction, Redaction, ReplaceRedaction,
 };
+pub use self::report::{PiiReport, PiiRuleMatch};
+
+pub(crate) use self::utils::process_pairlist;
@@ -1,6 +1,6 @@
 use std::borrow::Cow;
 use std::cmp;
-use std::collections::BTreeSet;
+use std::collections::{BTreeMap, BTreeSet};
 
 use hmac::{Hmac, Mac};
 use lazy_static::lazy_static;
@@ -9,14 +9,22 @@ use sha1::Sha1;
 use sha2::{Sha256, Sha512};
 
 use crate::pii::compiledconfig::RuleRef;
+use crate::pii::minidumps::{self, MemoryRegion, MemoryRegionKind, StringEncoding};
 use crate::pii::utils::process_pairlist;
-use crate::pii::{CompiledPiiConfig, HashAlgorithm, Redaction, RuleType};
+use crate::pii::{
+    CompiledPiiConfig, HashAlgorithm, PiiReport, PseudonymizeRedaction, Redaction, RuleType,
+    ScrubMinidumpError,
+};
 use crate::processor::{
-    process_chunked_value, Chunk, Pii, ProcessValue, ProcessingState, Processor, SelectorSpec,
-    ValueType,
+    process_chunked_value, process_value, Chunk, FieldAttrs, Pii, ProcessValue, ProcessingState,
+    Processor, SelectorSpec, ValueType,
+};
+use crate::protocol::{
+    AsPair, Event, IpAddr, LenientString, NativeImagePath, PairList, SessionUpdate, User,
+};
+use crate::types::{
+    Annotated, Meta, ProcessingAction, ProcessingResult, Remark, RemarkType, Value,
 };
-use crate::protocol::{AsPair, NativeImagePath, PairList};
-use crate::types::{Meta, ProcessingAction, ProcessingResult, Remark, RemarkType};
 
 lazy_static! {
     static ref NULL_SPLIT_RE: Regex = #[allow(clippy::trivial_regex)]
@@ -173,9 +181,24 @@ lazy_static! {
     ).unwrap();
 }
 
+/// Secret keys for the `pseudonymize` redaction, indexed by their key id.
+pub type PseudonymizationKeys = BTreeMap<String, String>;
+
 /// A processor that performs PII stripping.
+/// The children of a container whose siblings are known to selector predicates.
+enum Siblings {
+    /// String values of a data object, keyed by field name.
+    Object(BTreeMap<String, String>),
+    /// The values of a pair list, which have the key of their pair as sibling `key`.
+    PairList,
+}
+
 pub struct PiiProcessor<'a> {
     compiled_config: &'a CompiledPiiConfig,
+    pseudonymization_keys: Option<&'a PseudonymizationKeys>,
+    report: Option<PiiReport>,
+    /// Containers currently being processed, along with their depth.
+    siblings: Vec<(usize, Siblings)>,
 }
 
 impl<'a> PiiProcessor<'a> {
@@ -186,13 +209,303 @@ impl<'a> PiiProcessor<'a> {
         //
         // Note: We accept both `PiiConfig` and `CompiledPiiConfig` because the latter makes more
         // sense for benchmarks while the former is obviously the cleaner API for relay-server.
-        PiiProcessor { compiled_config }
+        PiiProcessor {
+            compiled_config,
+            pseudonymization_keys: None,
+            report: None,
+            siblings: Vec::new(),
+        }
+    }
+
+    /// Supplies the secret keys referenced by `pseudonymize` redactions.
+    ///
+    /// Without keys, values matched by a `pseudonymize` redaction are removed instead.
+    pub fn with_pseudonymization_keys(mut self, keys: &'a PseudonymizationKeys) -> Self {
+        self.pseudonymization_keys = Some(keys);
+        self
+    }
+
+    /// Enables recording of matched rules into a `PiiReport`.
+    ///
+    /// For a dry run that leaves the payload untouched, process a copy of the payload and only
+    /// keep the report.
+    pub fn with_report(mut self) -> Self {
+        self.report = Some(PiiReport::new());
+        self
+    }
+
+    /// Takes the report of matched rules, if enabled with `with_report`.
+    pub fn take_report(&mut self) -> Option<PiiReport> {
+        self.report.take()
+    }
+
+    /// Records matches of a rule that was just applied to the current value.
+    fn record_match(
+        &mut self,
+        rule: &RuleRef,
+        state: &ProcessingState<'_>,
+        meta: &Meta,
+        remarks_before: usize,
+        result: &ProcessingResult,
+    ) {
+        let report = match self.report {
+            Some(ref mut report) => report,
+            None => return,
+        };
+
+        let count = match result {
+            Err(ProcessingAction::DeleteValueHard) | Err(ProcessingAction::DeleteValueSoft) => 1,
+            _ => count_remarks(meta, rule).saturating_sub(remarks_before),
+        };
+
+        if count > 0 {
+            report.record(
+                &rule.origin,
+                rule.ty.name(),
+                state.path().to_string(),
+                count,
+            );
+        }
+    }
+
+    /// Applies PII rules to the user identifiers of a session update.
+    ///
+    /// Session updates are not annotated. Their distinct id and IP address are scrubbed as if they
+    /// were `user.id` and `user.ip_address` of an event, so that the same rules apply to both.
+    /// An IP address that no longer parses after scrubbing is removed.
+    pub fn process_session(&mut self, session: &mut SessionUpdate) -> ProcessingResult {
+        let user = User {
+            id: Annotated::from(session.distinct_id.take().map(LenientString)),
+            ip_address: Annotated::from(session.attributes.ip_address.map(IpAddr::from)),
+            ..User::default()
+        };
+
+        let mut event = Annotated::new(Event {
+            user: Annotated::new(user),
+            ..Event::default()
+        });
+
+        process_value(&mut event, self, ProcessingState::root())?;
+
+        let user = event.0.and_then(|event| event.user.0);
+        let (id, ip_address) = match user {
+            Some(user) => (user.id.0, user.ip_address.0),
+            None => (None, None),
+        };
+
+        session.distinct_id = id.map(|id| id.0);
+        session.attributes.ip_address = ip_address.and_then(|ip| ip.as_str().parse().ok());
+
+        Ok(())
+    }
+
+    /// Returns `true` if any rule applies to the given attachment.
+    ///
+    /// Use this to skip decoding attachments that are not selected by the PII config.
+    pub fn selects_attachment(&self, attachment_type: &str, filename: &str) -> bool {
+        let attrs = attachment_attrs();
+        let type_state = attachment_type_state(attachment_type);
+        let state = type_state.enter_borrowed(filename, Some(Cow::Borrowed(&attrs)), None);
+        self.iter_rules(&state, None).next().is_some()
+    }
+
+    /// Applies PII rules to the contents of a text attachment.
+    ///
+    /// Attachments are addressed by selectors as `$attachments.<filename>`. The key carrying the
+    /// `$attachments` type is the attachment type, so `'unreal.logs'.*` selects all Unreal logs.
+    /// Since attachments are treated like `pii=maybe` fields, generic selectors such as `$string`
+    /// or `**` do not apply.
+    ///
+    /// The text is processed line by line, so that matches cannot span multiple lines. The output
+    /// is only allocated once a rule matches. Returns the scrubbed text along with the remarks of
+    /// all rules that modified it, without ranges, or `None` if no rule matched.
+    pub fn scrub_attachment_text(
+        &mut self,
+        attachment_type: &str,
+        filename: &str,
+        text: &str,
+    ) -> Option<(String, Vec<Remark>)> {
+        let attrs = attachment_attrs();
+        let type_state = attachment_type_state(attachment_type);
+        let state = type_state.enter_borrowed(filename, Some(Cow::Borrowed(&attrs)), None);
+
+        let mut remarks: Vec<Remark> = Vec::new();
+        let mut output: Option<String> = None;
+        let mut offset = 0;
+
+        for line in text.split('\n') {
+            let mut scrubbed = line.to_owned();
+            let mut meta = Meta::default();
+            if self
+                .process_string(&mut scrubbed, &mut meta, &state)
+                .is_err()
+            {
+                scrubbed.clear();
+            }
+            merge_remarks(&mut remarks, &meta);
+
+            if output.is_none() && !remarks.is_empty() {
+                // Copy all previous lines, which have not been modified.
+                let mut buffer = String::with_capacity(text.len());
+                buffer.push_str(&text[..offset]);
+                output = Some(buffer);
+            }
+
+            offset += line.len();
+            if let Some(ref mut output) = output {
+                output.push_str(&scrubbed);
+                if offset < text.len() {
+                    output.push('\n');
+                }
+            }
+            offset += 1;
+        }
+
+        output.map(|output| (output, remarks))
+    }
+
+    /// Returns `true` if any rule applies to the memory of the given minidump.
+    pub fn selects_minidump(&self, filename: &str) -> bool {
+        let attrs = attachment_attrs();
+        let type_state = attachment_type_state("event.minidump");
+        let minidump_state = type_state.enter_borrowed(filename, None, Some(ValueType::Minidump));
+
+        [MemoryRegionKind::Stack, MemoryRegionKind::Heap]
+            .iter()
+            .any(|kind| {
+                let state =
+                    minidump_state.enter_static(kind.key(), Some(Cow::Borrowed(&attrs)), None);
+                self.iter_rules(&state, None).next().is_some()
+            })
+    }
+
+    /// Applies PII rules to the memory captured in a minidump.
+    ///
+    /// Memory is addressed by selectors as `$minidump.stack_memory` and `$minidump.heap_memory`.
+    /// The key carrying the `$minidump` type is the file name of the minidump. `@anything` rules
+    /// zero out entire memory regions, all other rules apply to UTF-8 and UTF-16 strings found in
+    /// memory. Scrubbed strings are truncated or padded with zeros to their original size, so that
+    /// the minidump remains structurally valid.
+    ///
+    /// Returns the remarks of all rules that modified memory, without ranges.
+    pub fn scrub_minidump(
+        &mut self,
+        filename: &str,
+        data: &mut [u8],
+    ) -> Result<Vec<Remark>, ScrubMinidumpError> {
+        self.scrub_minidump_regions(filename, data, minidumps::memory_regions)
+    }
+
+    /// Applies PII rules for minidump memory to a file that cannot be parsed as minidump.
+    ///
+    /// Since memory regions cannot be located, the entire file is treated as both stack and heap
+    /// memory. This is conservative: `@anything` rules zero out the entire file, and all other
+    /// rules apply to all strings in the file. The size of the file is retained.
+    ///
+    /// Returns the remarks of all rules that modified the file, without ranges.
+    pub fn scrub_unparseable_minidump(&mut self, filename: &str, data: &mut [u8]) -> Vec<Remark> {
+        self.scrub_minidump_regions(filename, data, |data| Ok(minidumps::file_regions(data)))
+            .unwrap_or_default()
+    }
+
+    fn scrub_minidump_regions<F>(
+        &mut self,
+        filename: &str,
+        data: &mut [u8],
+        memory_regions: F,
+    ) -> Result<Vec<Remark>, ScrubMinidumpError>
+    where
+        F: FnOnce(&[u8]) -> Result<Vec<MemoryRegion>, ScrubMinidumpError>,
+    {
+        let attrs = attachment_attrs();
+        let type_state = attachment_type_state("event.minidump");
+        let minidump_state = type_state.enter_borrowed(filename, None, Some(ValueType::Minidump));
+        let stack_state = minidump_state.enter_static(
+            MemoryRegionKind::Stack.key(),
+            Some(Cow::Borrowed(&attrs)),
+            None,
+        );
+        let heap_state = minidump_state.enter_static(
+            MemoryRegionKind::Heap.key(),
+            Some(Cow::Borrowed(&attrs)),
+            None,
+        );
+
+        let stack_rules = self.iter_rules(&stack_state, None).collect::<Vec<_>>();
+        let heap_rules = self.iter_rules(&heap_state, None).collect::<Vec<_>>();
+        if stack_rules.is_empty() && heap_rules.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let mut remarks = Vec::new();
+        for region in memory_regions(data)? {
+            let (state, rules) = match region.kind {
+                MemoryRegionKind::Stack => (&stack_state, &stack_rules),
+                MemoryRegionKind::Heap => (&heap_state, &heap_rules),
+            };
+
+            let mut meta = Meta::default();
+            for rule in rules {
+                let remarks_before = count_remarks(&meta, rule);
+                let memory = &mut data[region.range.clone()];
+                scrub_memory(&mut meta, rule, memory, self.pseudonymization_keys);
+                self.record_match(rule, state, &meta, remarks_before, &Ok(()));
+            }
+
+            merge_remarks(&mut remarks, &meta);
+        }
+
+        Ok(remarks)
+    }
+
+    /// Returns the string values of the siblings of the value at the given state.
+    ///
+    /// Siblings are only known for values in data objects and pair lists, and only if a selector
+    /// has a predicate on them.
+    fn siblings_of(&self, state: &ProcessingState<'_>) -> Option<BTreeMap<String, String>> {
+        let (depth, siblings) = self.siblings.last()?;
+        if depth + 1 != state.depth() {
+            return None;
+        }
+
+        match siblings {
+            Siblings::Object(values) => Some(values.clone()),
+            Siblings::PairList => {
+                let mut values = BTreeMap::new();
+                values.insert("key".to_owned(), state.path().key()?.to_owned());
+                Some(values)
+            }
+        }
+    }
+
+    /// Processes the children of a container, making their siblings known to predicates.
+    fn process_with_siblings<F>(
+        &mut self,
+        siblings: Siblings,
+        state: &ProcessingState<'_>,
+        f: F,
+    ) -> ProcessingResult
+    where
+        F: FnOnce(&mut Self) -> ProcessingResult,
+    {
+        self.siblings.push((state.depth(), siblings));
+        let result = f(self);
+        self.siblings.pop();
+        result
     }
 
     /// Iterate over all matching rules.
-    fn iter_rules<'b>(&self, state: &'b ProcessingState<'b>) -> RuleIterator<'a, 'b> {
+    ///
+    /// The value is used to evaluate selector predicates. It should be `None` for non-strings.
+    fn iter_rules<'b>(
+        &self,
+        state: &'b ProcessingState<'b>,
+        value: Option<&'b str>,
+    ) -> RuleIterator<'a, 'b> {
         RuleIterator {
             state,
+            value,
+            siblings: None,
             application_iter: self.compiled_config.applications.iter(),
             pending_refs: None,
         }
@@ -201,10 +514,20 @@ impl<'a> PiiProcessor<'a> {
 
 struct RuleIterator<'a, 'b> {
     state: &'b ProcessingState<'b>,
+    value: Option<&'b str>,
+    siblings: Option<&'b BTreeMap<String, String>>,
     application_iter: std::slice::Iter<'a, (SelectorSpec, BTreeSet<RuleRef>)>,
     pending_refs: Option<std::collections::btree_set::Iter<'a, RuleRef>>,
 }
 
+impl<'a, 'b> RuleIterator<'a, 'b> {
+    /// Evaluates selector predicates on siblings against the given values.
+    fn with_siblings(mut self, siblings: Option<&'b BTreeMap<String, String>>) -> Self {
+        self.siblings = siblings;
+        self
+    }
+}
+
 impl<'a, 'b> Iterator for RuleIterator<'a, 'b> {
     type Item = &'a RuleRef;
 
@@ -222,7 +545,11 @@ impl<'a, 'b> Iterator for RuleIterator<'a, 'b> {
                 if self.state.attrs().pii == Pii::Maybe && !selector.is_specific() {
                     continue;
                 }
-                if self.state.path().matches_selector(selector) {
+                if self
+                    .state
+                    .path()
+                    .matches_selector_siblings(selector, self.value, self.siblings)
+                {
                     self.pending_refs = Some(rules.iter());
                     continue 'outer;
                 }
@@ -249,9 +576,22 @@ impl<'a> Processor for PiiProcessor<'a> {
             return Ok(());
         }
 
+        let siblings = self.siblings_of(state);
+
         // apply rules based on key/path
-        for rule in self.iter_rules(state) {
-            match apply_rule_to_value(meta, rule, state.path().key(), None) {
+        let rules = self.iter_rules(state, None);
+        for rule in rules.with_siblings(siblings.as_ref()) {
+            let remarks_before = count_remarks(meta, rule);
+            let result = apply_rule_to_value(
+                meta,
+                rule,
+                state.path().key(),
+                None,
+                self.pseudonymization_keys,
+            );
+            self.record_match(rule, state, meta, remarks_before, &result);
+
+            match result {
                 Ok(()) => continue,
                 other => return other,
             }
@@ -269,10 +609,30 @@ impl<'a> Processor for PiiProcessor<'a> {
             return Ok(());
         }
 
+        // Predicates match the original value, before any rule has modified it.
+        let original = if self.compiled_config.matches_values {
+            Some(value.clone())
+        } else {
+            None
+        };
+
+        let siblings = self.siblings_of(state);
+
         // same as before_process. duplicated here because we can only check for "true",
         // "false" etc in process_string.
-        for rule in self.iter_rules(state) {
-            match apply_rule_to_value(meta, rule, state.path().key(), Some(value)) {
+        let rules = self.iter_rules(state, original.as_deref());
+        for rule in rules.with_siblings(siblings.as_ref()) {
+            let remarks_before = count_remarks(meta, rule);
+            let result = apply_rule_to_value(
+                meta,
+                rule,
+                state.path().key(),
+                Some(value),
+                self.pseudonymization_keys,
+            );
+            self.record_match(rule, state, meta, remarks_before, &result);
+
+            match result {
                 Ok(()) => continue,
                 other => return other,
             }
@@ -312,21 +672,128 @@ impl<'a> Processor for PiiProcessor<'a> {
         Ok(())
     }
 
+    fn process_value(
+        &mut self,
+        value: &mut Value,
+        _meta: &mut Meta,
+        state: &ProcessingState<'_>,
+    ) -> ProcessingResult {
+        let object = match value {
+            Value::Object(ref object) if self.compiled_config.matches_siblings => object,
+            _ => return value.process_child_values(self, state),
+        };
+
+        let strings = object
+            .iter()
+            .filter_map(|(key, value)| match value.value() {
+                Some(Value::String(string)) => Some((key.clone(), string.clone())),
+                _ => None,
+            })
+            .collect();
+
+        self.process_with_siblings(Siblings::Object(strings), state, |slf| {
+            value.process_child_values(slf, state)
+        })
+    }
+
     fn process_pairlist<T: ProcessValue + AsPair>(
         &mut self,
         value: &mut PairList<T>,
         _meta: &mut Meta,
         state: &ProcessingState,
     ) -> ProcessingResult {
-        process_pairlist(self, value, state)
+        if !self.compiled_config.matches_siblings {
+            return process_pairlist(self, value, state);
+        }
+
+        self.process_with_siblings(Siblings::PairList, state, |slf| {
+            process_pairlist(slf, value, state)
+        })
     }
 }
 
+/// Field attributes of attachment contents. Only specific selectors apply to attachments.
+fn attachment_attrs() -> FieldAttrs {
+    FieldAttrs {
+        pii: Pii::Maybe,
+        ..FieldAttrs::default()
+    }
+}
+
+/// The processing state of an attachment type, the parent of all attachments of that type.
+fn attachment_type_state(attachment_type: &str) -> ProcessingState<'_> {
+    ProcessingState::root().enter_borrowed(attachment_type, None, Some(ValueType::Attachments))
+}
+
+/// Adds the rule ids and types of all remarks in `meta` to `remarks`, skipping duplicates.
+fn merge_remarks(remarks: &mut Vec<Remark>, meta: &Meta) {
+    for remark in meta.iter_remarks() {
+        let known = remarks
+            .iter()
+            .any(|r| r.rule_id() == remark.rule_id() && r.ty() == remark.ty());
+        if !known {
+            remarks.push(Remark::new(remark.ty(), remark.rule_id()));
+        }
+    }
+}
+
+/// Applies a rule to a memory region of a minidump.
+fn scrub_memory(
+    meta: &mut Meta,
+    rule: &RuleRef,
+    memory: &mut [u8],
+    keys: Option<&PseudonymizationKeys>,
+) {
+    if let RuleType::Anything = rule.ty {
+        for byte in memory.iter_mut() {
+            *byte = 0;
+        }
+        meta.add_remark(Remark::new(RemarkType::Removed, rule.origin.clone()));
+        return;
+    }
+
+    let strings = minidumps::utf8_strings(memory)
+        .into_iter()
+        .map(|range| (range, StringEncoding::Utf8))
+        .chain(
+            minidumps::utf16_strings(memory)
+                .into_iter()
+                .map(|range| (range, StringEncoding::Utf16Le)),
+        )
+        .collect::<Vec<_>>();
+
+    for (range, encoding) in strings {
+        let mut string = minidumps::decode_string(&memory[range.clone()], encoding);
+
+        // Every string needs its own meta, since remarks carry ranges into the string.
+        let mut string_meta = Meta::default();
+        let result = apply_rule_to_value(&mut string_meta, rule, None, Some(&mut string), keys);
+        if result.is_err() {
+            string.clear();
+        }
+
+        if result.is_err() || string_meta.has_remarks() {
+            minidumps::encode_string(&mut memory[range], &string, encoding);
+        }
+
+        for remark in string_meta.iter_remarks() {
+            meta.add_remark(Remark::new(remark.ty(), remark.rule_id()));
+        }
+    }
+}
+
+fn count_remarks(meta: &Meta, rule: &RuleRef) -> usize {
+    meta.iter_remarks()
+        .filter(|remark| remark.rule_id() == rule.origin)
+        .count()
+}
+
 fn apply_rule_to_value(
     meta: &mut Meta,
     rule: &RuleRef,
     key: Option<&str>,
     mut value: Option<&mut String>,
+    keys: Option<&PseudonymizationKeys>,
 ) -> ProcessingResult {
     // The rule might specify to remove or to redact. If redaction is chosen, we need to
     // chunk up the value, otherwise we need to simply mark the value for deletion.
@@ -339,7 +806,7 @@ fn apply_rule_to_value(
         ($regex:expr, $replace_groups:expr) => {
             if let Some(ref mut value) = value {
                 process_chunked_value(value, meta, |chunks| {
-                    apply_regex_to_chunks(chunks, rule, $regex, $replace_groups)
+                    apply_regex_to_chunks(chunks, rule, $regex, $replace_groups, keys)
                 });
             }
         };
@@ -408,6 +875,7 @@ fn apply_regex_to_chunks<'a>(
     rule: &RuleRef,
     regex: &Regex,
     replace_groups: Option<&BTreeSet<u8>>,
+    keys: Option<&PseudonymizationKeys>,
 ) -> Vec<Chunk<'a>> {
     // NB: This function allocates the entire string and all chunks a second time. This means it
     // cannot reuse chunks and reallocates them. Ideally, we would be able to run the regex directly
@@ -472,7 +940,7 @@ fn apply_regex_to_chunks<'a>(
                                 &mut rv,
                                 &mut replacement_chunks,
                             );
-                            insert_replacement_chunks(&rule, g.as_str(), &mut rv);
+                            insert_replacement_chunks(&rule, g.as_str(), keys, &mut rv);
                             pos = g.end();
                         }
                     }
@@ -480,7 +948,7 @@ fn apply_regex_to_chunks<'a>(
             }
             None => {
                 process_text(&"", &mut rv, &mut replacement_chunks);
-                insert_replacement_chunks(&rule, &search_string, &mut rv);
+                insert_replacement_chunks(&rule, &search_string, keys, &mut rv);
                 pos = search_string.len();
                 break;
             }
@@ -507,7 +975,12 @@ fn in_range(range: (Option<i32>, Option<i32>), pos: usize, len: usize) -> bool {
     pos >= start && pos < end
 }
 
-fn insert_replacement_chunks(rule: &RuleRef, text: &str, output: &mut Vec<Chunk<'_>>) {
+fn insert_replacement_chunks(
+    rule: &RuleRef,
+    text: &str,
+    keys: Option<&PseudonymizationKeys>,
+    output: &mut Vec<Chunk<'_>>,
+) {
     match &rule.redaction {
         Redaction::Default | Redaction::Remove => {
             output.push(Chunk::Redaction {
@@ -540,6 +1013,23 @@ fn insert_replacement_chunks(rule: &RuleRef, text: &str, output: &mut Vec<Chunk<
                 text: Cow::Owned(hash_value(hash.algorithm, text, hash.key.as_deref())),
             });
         }
+        Redaction::Pseudonymize(pseudonymize) => {
+            // Without the secret, a stable pseudonym cannot be produced. Never leak the original
+            // value in this case and remove it instead.
+            let chunk = match pseudonymize_value(pseudonymize, text, keys) {
+                Some(pseudonym) => Chunk::Redaction {
+                    ty: RemarkType::Pseudonymized,
+                    rule_id: Cow::Owned(rule.origin.to_string()),
+                    text: Cow::Owned(pseudonym),
+                },
+                None => Chunk::Redaction {
+                    ty: RemarkType::Removed,
+                    rule_id: Cow::Owned(rule.origin.to_string()),
+                    text: Cow::Borrowed(""),
+                },
+            };
+            output.push(chunk);
+        }
         Redaction::Replace(replace) => {
             output.push(Chunk::Redaction {
                 ty: RemarkType::Substituted,
@@ -550,6 +1040,16 @@ fn insert_replacement_chunks(rule: &RuleRef, text: &str, output: &mut Vec<Chunk<
     }
 }
 
+fn pseudonymize_value(
+    redaction: &PseudonymizeRedaction,
+    text: &str,
+    keys: Option<&PseudonymizationKeys>,
+) -> Option<String> {
+    let key = keys?.get(&redaction.key_id)?;
+    let hash = hash_value(redaction.algorithm, text, Some(key));
+    Some(format!("{}:{}", redaction.prefix, hash))
+}
+
 fn hash_value(algorithm: HashAlgorithm, text: &str, key: Option<&str>) -> String {
     let key = key.unwrap_or("");
     macro_rules! hmac {
@@ -568,13 +1068,13 @@ fn hash_value(algorithm: HashAlgorithm, text: &str, key: Option<&str>) -> String
 
 #[cfg(test)]
 use {
+    crate::pii::minidumps::tests::build_minidump,
     crate::pii::PiiConfig,
-    crate::processor::process_value,
     crate::protocol::{
-        Addr, DebugImage, DebugMeta, Event, ExtraValue, Headers, LogEntry, NativeDebugImage,
-        Request,
+        Addr, DebugImage, DebugMeta, ExtraValue, Headers, LogEntry, NativeDebugImage, Request,
+        SessionAttributes, SessionStatus,
     },
-    crate::types::{Annotated, Object, Value},
+    crate::types::Object,
 };
 
 #[test]
@@ -1083,3 +1583,510 @@ fn test_logentry_value_types() {
             .is_none());
     }
 }
+
+#[test]
+fn test_pseudonymize_user() {
+    let config = PiiConfig::from_json(
+        r##"
+        {
+            "rules": {
+                "pseudonymize_user": {
+                    "type": "anything",
+                    "redaction": {
+                        "method": "pseudonymize",
+                        "keyId": "org",
+                        "prefix": "user"
+                    }
+                }
+            },
+            "applications": {
+                "user.id || user.email": ["pseudonymize_user"]
+            }
+        }
+        "##,
+    )
+    .unwrap();
+
+    let mut event = Annotated::new(Event {
+        user: Annotated::new(User {
+            id: Annotated::new(LenientString("42".to_owned())),
+            email: Annotated::new("foo@example.com".to_owned()),
+            ..User::default()
+        }),
+        ..Event::default()
+    });
+
+    let mut keys = PseudonymizationKeys::new();
+    keys.insert("org".to_owned(), "s3cret".to_owned());
+
+    let compiled = config.compiled();
+    let mut processor = PiiProcessor::new(&compiled).with_pseudonymization_keys(&keys);
+    process_value(&mut event, &mut processor, ProcessingState::root()).unwrap();
+
+    let user = event.value().unwrap().user.value().unwrap();
+    assert_eq_str!(
+        user.id.value().unwrap().0,
+        "user:555B3722C342F46DD02F9AA31D0C379C4F4B6A83"
+    );
+    assert_eq_str!(
+        user.email.value().unwrap(),
+        "user:1E56E23AD768295597B92D19950BE8D0CAF0F4CF"
+    );
+    assert_eq_dbg!(
+        user.id.meta().iter_remarks().collect::<Vec<_>>(),
+        vec![&Remark::with_range(
+            RemarkType::Pseudonymized,
+            "pseudonymize_user",
+            (0, 45)
+        )]
+    );
+}
+
+#[test]
+fn test_pseudonymize_missing_key() {
+    let config = PiiConfig::from_json(
+        r##"
+        {
+            "applications": {
+                "$string": ["@anything:pseudonymize"]
+            }
+        }
+        "##,
+    )
+    .unwrap();
+
+    let mut event = Annotated::new(Event {
+        extra: {
+            let mut map = Object::new();
+            map.insert(
+                "myvalue".to_string(),
+                Annotated::new(ExtraValue(Value::String("foobar".to_string()))),
+            );
+            Annotated::new(map)
+        },
+        ..Default::default()
+    });
+
+    let compiled = config.compiled();
+    let mut processor = PiiProcessor::new(&compiled);
+    process_value(&mut event, &mut processor, ProcessingState::root()).unwrap();
+
+    let extra = event.value().unwrap().extra.value().unwrap();
+    let value = extra.get("myvalue").unwrap();
+    assert_eq_dbg!(
+        value.value(),
+        Some(&ExtraValue(Value::String("".to_owned())))
+    );
+    assert_eq_dbg!(
+        value.meta().iter_remarks().collect::<Vec<_>>(),
+        vec![&Remark::with_range(
+            RemarkType::Removed,
+            "@anything:pseudonymize",
+            (0, 0)
+        )]
+    );
+}
+
+#[test]
+fn test_pseudonymize_session() {
+    let config = PiiConfig::from_json(
+        r##"
+        {
+            "applications": {
+                "user.id || user.ip_address": ["@anything:pseudonymize"]
+            }
+        }
+        "##,
+    )
+    .unwrap();
+
+    let mut session = SessionUpdate {
+        session_id: "8333339f-5675-4f89-a9a0-1c935255ab58".parse().unwrap(),
+        distinct_id: Some("42".to_owned()),
+        sequence: 42,
+        init: true,
+        timestamp: "2020-02-07T15:17:00Z".parse().unwrap(),
+        started: "2020-02-07T14:16:00Z".parse().unwrap(),
+        duration: None,
+        status: SessionStatus::Ok,
+        errors: 0,
+        attributes: SessionAttributes {
+            release: "sentry-test@1.0.0".to_owned(),
+            environment: None,
+            ip_address: Some("127.0.0.1".parse().unwrap()),
+            user_agent: None,
+        },
+    };
+
+    let mut keys = PseudonymizationKeys::new();
+    keys.insert("default".to_owned(), "s3cret".to_owned());
+
+    let compiled = config.compiled();
+    let mut processor = PiiProcessor::new(&compiled).with_pseudonymization_keys(&keys);
+    processor.process_session(&mut session).unwrap();
+
+    assert_eq_dbg!(
+        session.distinct_id.as_deref(),
+        Some("pseudo:555B3722C342F46DD02F9AA31D0C379C4F4B6A83")
+    );
+    // A pseudonym is not a valid IP address.
+    assert_eq_dbg!(session.attributes.ip_address, None);
+}
+
+#[test]
+fn test_report_matches() {
+    let config = PiiConfig::from_json(
+        r##"
+        {
+            "applications": {
+                "$string": ["@ip:replace"],
+                "extra.bar": ["@anything:remove"]
+            }
+        }
+        "##,
+    )
+    .unwrap();
+
+    let mut event = Annotated::new(Event {
+        extra: {
+            let mut map = Object::new();
+            map.insert(
+                "foo".to_string(),
+                Annotated::new(ExtraValue(Value::String(
+                    "127.0.0.1 and 10.0.0.1".to_string(),
+                ))),
+            );
+            map.insert(
+                "bar".to_string(),
+                Annotated::new(ExtraValue(Value::String("hello".to_string()))),
+            );
+            Annotated::new(map)
+        },
+        ..Default::default()
+    });
+
+    let compiled = config.compiled();
+    let mut processor = PiiProcessor::new(&compiled).with_report();
+    process_value(&mut event, &mut processor, ProcessingState::root()).unwrap();
+
+    let report = processor.take_report().unwrap();
+    assert_eq_str!(
+        serde_json::to_string(&report).unwrap(),
+        r#"[{"rule_id":"@anything:remove","path":"extra.bar","count":1},{"rule_id":"@ip:replace","path":"extra.foo","count":2}]"#
+    );
+
+    let type_counts: Vec<_> = report.type_counts().iter().collect();
+    assert_eq_dbg!(type_counts, vec![(&"anything", &1), (&"ip", &2)]);
+}
+
+#[test]
+fn test_scrub_attachment_text() {
+    let config = PiiConfig::from_json(
+        r##"
+        {
+            "applications": {
+                "$attachments.'debug.log'": ["@ip:replace"],
+                "'unreal.logs'.*": ["@email:mask"]
+            }
+        }
+        "##,
+    )
+    .unwrap();
+
+    let compiled = config.compiled();
+    let mut processor = PiiProcessor::new(&compiled);
+
+    assert!(processor.selects_attachment("event.attachment", "debug.log"));
+    assert!(processor.selects_attachment("unreal.logs", "UE4Minidump.log"));
+    assert!(!processor.selects_attachment("event.attachment", "other.log"));
+
+    let text = "starting\nconnected to 127.0.0.1\nuser foo@example.com\n";
+    let (text, remarks) = processor
+        .scrub_attachment_text("event.attachment", "debug.log", text)
+        .unwrap();
+    assert_eq_str!(text, "starting\nconnected to [ip]\nuser foo@example.com\n");
+    assert_eq_dbg!(
+        remarks,
+        vec![Remark::new(RemarkType::Substituted, "@ip:replace")]
+    );
+
+    let text = "user foo@example.com";
+    let (text, remarks) = processor
+        .scrub_attachment_text("unreal.logs", "UE4Minidump.log", text)
+        .unwrap();
+    assert_eq_str!(text, "user ***@*******.***");
+    assert_eq_dbg!(
+        remarks,
+        vec![Remark::new(RemarkType::Masked, "@email:mask")]
+    );
+
+    let text = "nothing to scrub\n";
+    assert!(processor
+        .scrub_attachment_text("event.attachment", "debug.log", text)
+        .is_none());
+}
+
+#[test]
+fn test_scrub_attachment_text_generic_selectors() {
+    let config = PiiConfig::from_json(
+        r##"
+        {
+            "applications": {
+                "$string": ["@ip:replace"],
+                "**": ["@anything:remove"]
+            }
+        }
+        "##,
+    )
+    .unwrap();
+
+    let compiled = config.compiled();
+    let mut processor = PiiProcessor::new(&compiled);
+    assert!(!processor.selects_attachment("event.attachment", "debug.log"));
+
+    let text = "connected to 127.0.0.1";
+    assert!(processor
+        .scrub_attachment_text("event.attachment", "debug.log", text)
+        .is_none());
+}
+
+#[test]
+fn test_scrub_minidump() {
+    let config = PiiConfig::from_json(
+        r##"
+        {
+            "applications": {
+                "$minidump.stack_memory": ["@ip:replace"],
+                "$minidump.heap_memory": ["@anything:remove"]
+            }
+        }
+        "##,
+    )
+    .unwrap();
+
+    let stack = b"\x00addr 127.0.0.1\x001\x002\x007\x00.\x000\x00.\x000\x00.\x001\x00";
+    let mut data = build_minidump(stack, b"secret heap");
+
+    let compiled = config.compiled();
+    let mut processor = PiiProcessor::new(&compiled);
+    assert!(processor.selects_minidump("minidump.dmp"));
+    let remarks = processor.scrub_minidump("minidump.dmp", &mut data).unwrap();
+
+    assert_eq_dbg!(
+        remarks,
+        vec![
+            Remark::new(RemarkType::Substituted, "@ip:replace"),
+            Remark::new(RemarkType::Removed, "@anything:remove"),
+        ]
+    );
+
+    let mut expected_stack = b"\x00addr [ip]".to_vec();
+    expected_stack.resize(16, 0);
+    expected_stack.extend_from_slice(b"[\x00i\x00p\x00]\x00");
+    expected_stack.resize(stack.len(), 0);
+    assert_eq!(data, build_minidump(&expected_stack, &[0; 11]));
+}
+
+#[test]
+fn test_scrub_minidump_not_selected() {
+    let config = PiiConfig::from_json(
+        r##"
+        {
+            "applications": {
+                "$string": ["@ip:replace"],
+                "$attachments.*": ["@ip:replace"]
+            }
+        }
+        "##,
+    )
+    .unwrap();
+
+    let compiled = config.compiled();
+    let mut processor = PiiProcessor::new(&compiled);
+
+    // Without selected memory, the minidump is not parsed at all.
+    assert!(!processor.selects_minidump("minidump.dmp"));
+    let mut data = b"not a minidump".to_vec();
+    let remarks = processor.scrub_minidump("minidump.dmp", &mut data).unwrap();
+    assert!(remarks.is_empty());
+}
+
+#[test]
+fn test_scrub_minidump_invalid() {
+    let config = PiiConfig::from_json(
+        r##"
+        {
+            "applications": {
+                "$minidump.*": ["@anything:remove"]
+            }
+        }
+        "##,
+    )
+    .unwrap();
+
+    let compiled = config.compiled();
+    let mut processor = PiiProcessor::new(&compiled);
+
+    let mut data = b"not a minidump".to_vec();
+    assert_eq_dbg!(
+        processor.scrub_minidump("minidump.dmp", &mut data),
+        Err(ScrubMinidumpError::InvalidHeader)
+    );
+
+    // Without memory regions, the entire file is scrubbed.
+    let remarks = processor.scrub_unparseable_minidump("minidump.dmp", &mut data);
+    assert_eq!(data, vec![0; 14]);
+    assert_eq!(remarks.len(), 1);
+}
+
+#[test]
+fn test_selector_predicates() {
+    let config = PiiConfig::from_json(
+        r##"
+        {
+            "applications": {
+                "$http.headers.*[?(@key ~ '(?i)token$')]": ["@anything:remove"],
+                "extra.*[?(@ ~ '^tok_')]": ["@anything:replace"]
+            }
+        }
+        "##,
+    )
+    .unwrap();
+
+    let mut event = Annotated::<Event>::from_json(
+        r#"
+        {
+            "request": {
+                "headers": [
+                    ["X-Api-Token", "secret"],
+                    ["Accept", "text/html"]
+                ]
+            },
+            "extra": {
+                "token": "tok_123",
+                "other": "hello"
+            }
+        }
+        "#,
+    )
+    .unwrap();
+
+    let compiled = config.compiled();
+    let mut processor = PiiProcessor::new(&compiled);
+    process_value(&mut event, &mut processor, ProcessingState::root()).unwrap();
+
+    let event = event.value().unwrap();
+    let headers = event
+        .request
+        .value()
+        .and_then(|request| request.headers.value())
+        .unwrap();
+    assert_eq!(headers.get_header("X-Api-Token"), None);
+    assert_eq!(headers.get_header("Accept"), Some("text/html"));
+
+    let extra = event.extra.value().unwrap();
+    let extra_str = |key: &str| match extra.get(key).and_then(Annotated::value) {
+        Some(ExtraValue(Value::String(s))) => Some(s.as_str()),
+        _ => None,
+    };
+    assert_eq!(extra_str("token"), Some("[Filtered]"));
+    assert_eq!(extra_str("other"), Some("hello"));
+}
+
+#[test]
+fn test_selector_sibling_predicates() {
+    let config = PiiConfig::from_json(
+        r##"
+        {
+            "applications": {
+                "extra.params.*.value[?(@key == 'password')]": ["@anything:remove"]
+            }
+        }
+        "##,
+    )
+    .unwrap();
+
+    let mut event = Annotated::<Event>::from_json(
+        r#"
+        {
+            "extra": {
+                "params": [
+                    {"key": "password", "value": "hunter2"},
+                    {"key": "username", "value": "admin"}
+                ]
+            }
+        }
+        "#,
+    )
+    .unwrap();
+
+    let compiled = config.compiled();
+    let mut processor = PiiProcessor::new(&compiled);
+    process_value(&mut event, &mut processor, ProcessingState::root()).unwrap();
+
+    let params = event
+        .value()
+        .and_then(|event| event.extra.value())
+        .and_then(|extra| extra.get("params"))
+        .and_then(Annotated::value);
+
+    let params = match params {
+        Some(ExtraValue(Value::Array(params))) => params,
+        other => panic!("unexpected params: {:?}", other),
+    };
+
+    let param_value = |index: usize| match params[index].value() {
+        Some(Value::Object(param)) => param.get("value").and_then(Annotated::value).cloned(),
+        _ => None,
+    };
+    assert_eq!(param_value(0), None);
+    assert_eq!(param_value(1), Some(Value::String("admin".to_owned())));
+}
+
+#[test]
+fn test_span_data_selectors() {
+    let config = PiiConfig::from_json(
+        r##"
+        {
+            "applications": {
+                "spans.*.data.password": ["@anything:remove"],
+                "$span.tags.user": ["@anything:replace"]
+            }
+        }
+        "##,
+    )
+    .unwrap();
+
+    let mut event = Annotated::<Event>::from_json(
+        r#"
+        {
+            "spans": [
+                {
+                    "tags": {"user": "jane", "route": "/login"},
+                    "data": {"password": "hunter2", "query": "SELECT 1"}
+                }
+            ]
+        }
+        "#,
+    )
+    .unwrap();
+
+    let compiled = config.compiled();
+    let mut processor = PiiProcessor::new(&compiled);
+    process_value(&mut event, &mut processor, ProcessingState::root()).unwrap();
+
+    let spans = event.value().unwrap().spans.value().unwrap();
+    let span = spans[0].value().unwrap();
+
+    let data = span.data.value().unwrap();
+    assert_eq!(data.get("password").and_then(Annotated::value), None);
+    assert_eq!(
+        data.get("query").and_then(Annotated::value),
+        Some(&Value::String("SELECT 1".to_owned()))
+    );
+
+    let tags = span.tags.value().unwrap();
+    let tag = |key: &str| tags.get(key).and_then(|tag| tag.as_str());
+    assert_eq!(tag("user"), Some("[Filtered]"));
+    assert_eq!(tag("route"), Some("/login"));
+}
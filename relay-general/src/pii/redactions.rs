@@ -30,6 +30,14 @@ fn default_replace_text() -> String {
     "[Filtered]".into()
 }
 
+fn default_pseudonymization_key_id() -> String {
+    "default".into()
+}
+
+fn default_pseudonym_prefix() -> String {
+    "pseudo".into()
+}
+
 /// Replaces a value with a specific string.
 #[derive(Serialize, Deserialize, Debug, Clone, Eq, PartialEq)]
 #[serde(rename_all = "camelCase")]
@@ -99,6 +107,35 @@ impl Default for HashRedaction {
     }
 }
 
+/// Replaces the value with a stable, prefix-tagged pseudonym.
+///
+/// Unlike `HashRedaction`, the secret is never part of the PII config. It is looked up by
+/// `key_id` in the pseudonymization keys configured in Relay. Equal inputs yield equal pseudonyms
+/// as long as the key does not change, which keeps user counts intact after scrubbing.
+#[derive(Serialize, Deserialize, Debug, Clone, Eq, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct PseudonymizeRedaction {
+    /// The identifier of the secret key in Relay's configuration.
+    #[serde(default = "default_pseudonymization_key_id")]
+    pub key_id: String,
+    /// The hash algorithm
+    #[serde(default)]
+    pub algorithm: HashAlgorithm,
+    /// A tag prepended to the pseudonym, separated by a colon.
+    #[serde(default = "default_pseudonym_prefix")]
+    pub prefix: String,
+}
+
+impl Default for PseudonymizeRedaction {
+    fn default() -> Self {
+        PseudonymizeRedaction {
+            key_id: default_pseudonymization_key_id(),
+            algorithm: HashAlgorithm::default(),
+            prefix: default_pseudonym_prefix(),
+        }
+    }
+}
+
 /// Defines how replacements happen.
 #[derive(Serialize, Deserialize, Debug, Clone, Eq, PartialEq)]
 #[serde(tag = "method", rename_all = "snake_case")]
@@ -117,6 +154,8 @@ pub enum Redaction {
     Mask(MaskRedaction),
     /// Replaces the value with a hash
     Hash(HashRedaction),
+    /// Replaces the value with a keyed pseudonym
+    Pseudonymize(PseudonymizeRedaction),
 }
 
 impl Default for Redaction {
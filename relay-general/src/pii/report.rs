@@ -0,0 +1,68 @@
+//! Statistics on matched PII rules.
+use std::collections::BTreeMap;
+
+use serde::{Serialize, Serializer};
+
+/// A PII rule that matched values at a specific path.
+#[derive(Clone, Debug, Eq, PartialEq, Serialize)]
+pub struct PiiRuleMatch<'a> {
+    /// The id of the rule, or of the outer rule if it hides its inner rules.
+    pub rule_id: &'a str,
+    /// The path of the matched value, which can be used as a selector.
+    pub path: &'a str,
+    /// The number of matches at this path.
+    pub count: usize,
+}
+
+/// A summary of values that PII rules matched during processing.
+///
+/// The report lists every combination of rule and path along with the number of matches. In dry
+/// runs, this is what would have been redacted.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct PiiReport {
+    matches: BTreeMap<(String, String), usize>,
+    types: BTreeMap<&'static str, usize>,
+}
+
+impl PiiReport {
+    /// Creates an empty report.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns `true` if no rule has matched.
+    pub fn is_empty(&self) -> bool {
+        self.matches.is_empty()
+    }
+
+    /// Records `count` matches of a rule with the given type at the given path.
+    pub fn record(&mut self, rule_id: &str, rule_type: &'static str, path: String, count: usize) {
+        *self.matches.entry((rule_id.to_owned(), path)).or_insert(0) += count;
+        *self.types.entry(rule_type).or_insert(0) += count;
+    }
+
+    /// Iterates all matches ordered by rule id and path.
+    pub fn matches(&self) -> impl Iterator<Item = PiiRuleMatch<'_>> {
+        self.matches
+            .iter()
+            .map(|((rule_id, path), count)| PiiRuleMatch {
+                rule_id,
+                path,
+                count: *count,
+            })
+    }
+
+    /// Returns the total number of matches per rule type, such as `ip` or `pattern`.
+    ///
+    /// Unlike rule ids, the set of rule types is fixed, which makes these counts suitable for
+    /// metrics.
+    pub fn type_counts(&self) -> &BTreeMap<&'static str, usize> {
+        &self.types
+    }
+}
+
+impl Serialize for PiiReport {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_seq(self.matches())
+    }
+}
@@ -1,4 +1,5 @@
 use std::borrow::Cow;
+use std::collections::BTreeMap;
 use std::fmt;
 use std::str::FromStr;
 
@@ -35,6 +36,8 @@ pub enum ValueType {
     Breadcrumb,
     Span,
     ClientSdkInfo,
+    Attachments,
+    Minidump,
 }
 
 impl ValueType {
@@ -62,6 +65,8 @@ impl ValueType {
             ValueType::Breadcrumb => "breadcrumb",
             ValueType::Span => "span",
             ValueType::ClientSdkInfo => "sdk",
+            ValueType::Attachments => "attachments",
+            ValueType::Minidump => "minidump",
         }
     }
 }
@@ -94,6 +99,8 @@ impl FromStr for ValueType {
             "thread" => ValueType::Thread,
             "breadcrumb" => ValueType::Breadcrumb,
             "sdk" => ValueType::ClientSdkInfo,
+            "attachments" => ValueType::Attachments,
+            "minidump" => ValueType::Minidump,
             _ => return Err(UnknownValueTypeError),
         })
     }
@@ -510,7 +517,31 @@ impl<'a> Path<'a> {
     }
 
     /// Checks if a path matches given selector.
+    ///
+    /// Predicates, such as `[?(@ ~ '^tok_')]`, never match. Use `matches_selector_value` to supply
+    /// the value.
     pub fn matches_selector(&self, selector: &SelectorSpec) -> bool {
+        self.matches_selector_value(selector, None)
+    }
+
+    /// Checks if a path matches given selector, evaluating predicates against the given value.
+    ///
+    /// The value is the string at this path, if any. Predicates on siblings, such as
+    /// `[?(@key == 'password')]`, never match. Use `matches_selector_siblings` to supply them.
+    pub fn matches_selector_value(&self, selector: &SelectorSpec, value: Option<&str>) -> bool {
+        self.matches_selector_siblings(selector, value, None)
+    }
+
+    /// Checks if a path matches given selector, evaluating predicates against the given value and
+    /// the string values of its siblings, keyed by field name.
+    ///
+    /// Predicates always refer to the value at the end of the path.
+    pub fn matches_selector_siblings(
+        &self,
+        selector: &SelectorSpec,
+        value: Option<&str>,
+        siblings: Option<&BTreeMap<String, String>>,
+    ) -> bool {
         match *selector {
             SelectorSpec::Path(ref path) => {
                 // fastest path: the selector is deeper than the current structure.
@@ -561,9 +592,17 @@ impl<'a> Path<'a> {
                     .all(|(state, selector_path)| selector_path.matches_state(state))
                     && selector_iter.next().is_none()
             }
-            SelectorSpec::And(ref xs) => xs.iter().all(|x| self.matches_selector(x)),
-            SelectorSpec::Or(ref xs) => xs.iter().any(|x| self.matches_selector(x)),
-            SelectorSpec::Not(ref x) => !self.matches_selector(x),
+            SelectorSpec::And(ref xs) => xs
+                .iter()
+                .all(|x| self.matches_selector_siblings(x, value, siblings)),
+            SelectorSpec::Or(ref xs) => xs
+                .iter()
+                .any(|x| self.matches_selector_siblings(x, value, siblings)),
+            SelectorSpec::Not(ref x) => !self.matches_selector_siblings(x, value, siblings),
+            SelectorSpec::Predicate(ref x, ref predicate) => {
+                predicate.matches(value, siblings)
+                    && self.matches_selector_siblings(x, value, siblings)
+            }
         }
     }
 }
@@ -692,3 +731,40 @@ fn test_path_matching() {
         .path()
         .matches_selector(&"(~$object.**)".parse().unwrap()));
 }
+
+#[test]
+fn test_path_matching_predicates() {
+    let event_state = ProcessingState::new_root(None, Some(ValueType::Event));
+    let request_state = event_state.enter_static("request", None, Some(ValueType::Request));
+    let headers_state = request_state.enter_static("headers", None, None);
+    let token_state = headers_state.enter_borrowed("X-Token", None, Some(ValueType::String));
+
+    let mut siblings = BTreeMap::new();
+    siblings.insert("key".to_owned(), "X-Token".to_owned());
+
+    let selector = "$http.headers.*[?(@key ~ '(?i)token')]".parse().unwrap();
+    assert!(token_state
+        .path()
+        .matches_selector_siblings(&selector, None, Some(&siblings)));
+    assert!(!headers_state
+        .path()
+        .matches_selector_siblings(&selector, None, Some(&siblings)));
+    assert!(!token_state.path().matches_selector(&selector));
+
+    let selector = "$http.headers.*[?(@ ~ '^tok_')]".parse().unwrap();
+    assert!(token_state
+        .path()
+        .matches_selector_value(&selector, Some("tok_123")));
+    assert!(!token_state
+        .path()
+        .matches_selector_value(&selector, Some("123")));
+    assert!(!token_state.path().matches_selector(&selector));
+
+    // excluding a single key from a wildcard
+    let selector = "($http.headers.* && !*[?(@key == 'X-Token')])"
+        .parse()
+        .unwrap();
+    assert!(!token_state
+        .path()
+        .matches_selector_siblings(&selector, None, Some(&siblings)));
+}
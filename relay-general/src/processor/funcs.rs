@@ -12,6 +12,9 @@ where
     T: ProcessValue,
     P: Processor,
 {
+    let action = processor.process_annotated(annotated, state);
+    annotated.apply(|_, _| action)?;
+
     let action = processor.before_process(annotated.0.as_ref(), &mut annotated.1, state);
     annotated.apply(|_, _| action)?;
 
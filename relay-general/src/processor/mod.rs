@@ -13,6 +13,9 @@ pub use self::attrs::{
 };
 pub use self::chunks::{join_chunks, process_chunked_value, split_chunks, Chunk};
 pub use self::funcs::process_value;
-pub use self::selector::{SelectorPathItem, SelectorSpec};
+pub use self::selector::{
+    InvalidSelectorError, PredicateOperand, PredicateOperator, SelectorPathItem, SelectorPredicate,
+    SelectorSpec,
+};
 pub use self::size::{estimate_size, estimate_size_flat};
 pub use self::traits::{ProcessValue, Processor};
@@ -1,3 +1,5 @@
+use std::cmp::Ordering;
+use std::collections::BTreeMap;
 use std::fmt;
 use std::str::FromStr;
 
@@ -5,6 +7,7 @@ use failure::Fail;
 use pest::error::Error;
 use pest::iterators::Pair;
 use pest::Parser;
+use regex::Regex;
 
 use crate::processor::{ProcessingState, ValueType};
 
@@ -23,6 +26,9 @@ pub enum InvalidSelectorError {
     #[fail(display = "invalid selector: unknown value")]
     UnknownType,
 
+    #[fail(display = "invalid selector: invalid regex in predicate: {}", _0)]
+    InvalidRegex(regex::Error),
+
     #[fail(display = "parser bug: consumed {} (expected {})", _0, _1)]
     UnexpectedToken(String, &'static str),
 }
@@ -80,12 +86,113 @@ impl SelectorPathItem {
     }
 }
 
+/// The part of a value that a `SelectorPredicate` compares.
+#[derive(Clone, Debug, Eq, PartialEq, Ord, PartialOrd)]
+pub enum PredicateOperand {
+    /// The value itself, written as `@`. Only strings can be compared.
+    Value,
+    /// The value of a sibling field, written as `@name`. Only strings can be compared.
+    ///
+    /// In pair lists, the key of each pair is available as sibling `key` of the pair's value.
+    Sibling(String),
+}
+
+/// The comparison of a `SelectorPredicate`.
+#[derive(Clone, Debug)]
+pub enum PredicateOperator {
+    /// Exact, case-sensitive equality, written as `==`.
+    Equals,
+    /// A regex search, written as `~`.
+    Matches(Regex),
+}
+
+/// A condition on the value matched by a selector, such as `[?(@ ~ '^tok_')]`.
+#[derive(Clone, Debug)]
+pub struct SelectorPredicate {
+    pub operand: PredicateOperand,
+    pub operator: PredicateOperator,
+    pub literal: String,
+}
+
+impl SelectorPredicate {
+    /// Checks the predicate against the string value of the current state and the string values
+    /// of its siblings, keyed by field name.
+    pub fn matches(
+        &self,
+        value: Option<&str>,
+        siblings: Option<&BTreeMap<String, String>>,
+    ) -> bool {
+        let operand = match self.operand {
+            PredicateOperand::Value => value,
+            PredicateOperand::Sibling(ref name) => siblings
+                .and_then(|siblings| siblings.get(name))
+                .map(String::as_str),
+        };
+
+        match (operand, &self.operator) {
+            (Some(operand), PredicateOperator::Equals) => operand == self.literal,
+            (Some(operand), PredicateOperator::Matches(regex)) => regex.is_match(operand),
+            (None, _) => false,
+        }
+    }
+
+    fn sort_key(&self) -> (&PredicateOperand, bool, &str) {
+        let is_regex = match self.operator {
+            PredicateOperator::Equals => false,
+            PredicateOperator::Matches(_) => true,
+        };
+        (&self.operand, is_regex, &self.literal)
+    }
+}
+
+impl PartialEq for SelectorPredicate {
+    fn eq(&self, other: &Self) -> bool {
+        // The regex is compiled from the literal, so it does not need to be compared.
+        self.sort_key() == other.sort_key()
+    }
+}
+
+impl Eq for SelectorPredicate {}
+
+impl PartialOrd for SelectorPredicate {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for SelectorPredicate {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.sort_key().cmp(&other.sort_key())
+    }
+}
+
+impl fmt::Display for SelectorPredicate {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let operand = match self.operand {
+            PredicateOperand::Value => "",
+            PredicateOperand::Sibling(ref name) => name.as_str(),
+        };
+        let operator = match self.operator {
+            PredicateOperator::Equals => "==",
+            PredicateOperator::Matches(_) => "~",
+        };
+        write!(
+            f,
+            "[?(@{} {} '{}')]",
+            operand,
+            operator,
+            self.literal.replace("'", "''")
+        )
+    }
+}
+
 #[derive(Clone, Debug, Eq, PartialEq, Ord, PartialOrd)]
 pub enum SelectorSpec {
     And(Vec<SelectorSpec>),
     Or(Vec<SelectorSpec>),
     Not(Box<SelectorSpec>),
     Path(Vec<SelectorPathItem>),
+    Predicate(Box<SelectorSpec>, SelectorPredicate),
 }
 
 impl SelectorSpec {
@@ -96,6 +203,7 @@ impl SelectorSpec {
             SelectorSpec::And(ref selectors) => selectors.iter().any(SelectorSpec::is_specific),
             SelectorSpec::Or(ref selectors) => selectors.iter().all(SelectorSpec::is_specific),
             SelectorSpec::Not(_) => false,
+            SelectorSpec::Predicate(ref selector, _) => selector.is_specific(),
             SelectorSpec::Path(ref path) => {
                 path.iter().enumerate().all(|(i, item)| {
                     match *item {
@@ -126,7 +234,9 @@ impl SelectorSpec {
                             | ValueType::Thread
                             | ValueType::Breadcrumb
                             | ValueType::Span
-                            | ValueType::ClientSdkInfo => i == 0,
+                            | ValueType::ClientSdkInfo
+                            | ValueType::Attachments
+                            | ValueType::Minidump => i == 0,
                         },
                         SelectorPathItem::Index(_) => true,
                         SelectorPathItem::Key(_) => true,
@@ -155,6 +265,7 @@ impl fmt::Display for SelectorSpec {
                         SelectorSpec::Or(_) => true,
                         SelectorSpec::Not(_) => false,
                         SelectorSpec::Path(_) => false,
+                        SelectorSpec::Predicate(..) => false,
                     };
 
                     if needs_parens {
@@ -182,6 +293,7 @@ impl fmt::Display for SelectorSpec {
                     SelectorSpec::Or(_) => true,
                     SelectorSpec::Not(_) => true,
                     SelectorSpec::Path(_) => false,
+                    SelectorSpec::Predicate(..) => false,
                 };
 
                 if needs_parens {
@@ -198,6 +310,13 @@ impl fmt::Display for SelectorSpec {
                     write!(f, "{}", item)?;
                 }
             }
+            SelectorSpec::Predicate(ref x, ref predicate) => {
+                if let SelectorSpec::Path(_) = **x {
+                    write!(f, "{}{}", x, predicate)?;
+                } else {
+                    write!(f, "({}){}", x, predicate)?;
+                }
+            }
         }
         Ok(())
     }
@@ -264,9 +383,18 @@ fn handle_selector(pair: Pair<Rule>) -> Result<SelectorSpec, InvalidSelectorErro
     }
 
     match pair.as_rule() {
-        Rule::ParenthesisOrPath | Rule::MaybeNotSelector => {
-            handle_selector(pair.into_inner().next().unwrap())
+        Rule::ParenthesisOrPath => {
+            let mut inner = pair.into_inner();
+            let selector = handle_selector(inner.next().unwrap())?;
+            match inner.next() {
+                Some(predicate) => Ok(SelectorSpec::Predicate(
+                    Box::new(selector),
+                    handle_predicate(predicate)?,
+                )),
+                None => Ok(selector),
+            }
         }
+        Rule::MaybeNotSelector => handle_selector(pair.into_inner().next().unwrap()),
         Rule::SelectorPath => {
             let mut used_deep_wildcard = false;
             let items = pair
@@ -339,6 +467,62 @@ fn handle_key(pair: Pair<Rule>) -> Result<String, InvalidSelectorError> {
     }
 }
 
+fn handle_predicate(pair: Pair<Rule>) -> Result<SelectorPredicate, InvalidSelectorError> {
+    let mut inner = pair.into_inner();
+
+    let operand = inner.next().unwrap().into_inner().next().unwrap();
+    let operand = match operand.as_rule() {
+        Rule::ValueOperand => PredicateOperand::Value,
+        Rule::SiblingOperand => PredicateOperand::Sibling(operand.as_str()[1..].to_owned()),
+        rule => {
+            return Err(InvalidSelectorError::UnexpectedToken(
+                format!("{:?}", rule),
+                "an operand",
+            ))
+        }
+    };
+
+    let operator = inner.next().unwrap().into_inner().next().unwrap();
+    let literal = handle_literal(inner.next().unwrap())?;
+
+    let operator = match operator.as_rule() {
+        Rule::EqualsOperator => PredicateOperator::Equals,
+        Rule::MatchesOperator => PredicateOperator::Matches(
+            Regex::new(&literal).map_err(InvalidSelectorError::InvalidRegex)?,
+        ),
+        rule => {
+            return Err(InvalidSelectorError::UnexpectedToken(
+                format!("{:?}", rule),
+                "an operator",
+            ))
+        }
+    };
+
+    Ok(SelectorPredicate {
+        operand,
+        operator,
+        literal,
+    })
+}
+
+fn handle_literal(pair: Pair<Rule>) -> Result<String, InvalidSelectorError> {
+    let pair = pair.into_inner().next().unwrap();
+    match pair.as_rule() {
+        Rule::DoubleQuotedLiteral => Ok(pair.as_str().to_owned()),
+        Rule::QuotedLiteral => Ok({
+            let mut literal = String::new();
+            for token in pair.into_inner() {
+                literal.push_str(token.as_str());
+            }
+            literal
+        }),
+        rule => Err(InvalidSelectorError::UnexpectedToken(
+            format!("{:?}", rule),
+            "a literal",
+        )),
+    }
+}
+
 fn key_needs_quoting(key: &str) -> bool {
     SelectorParser::parse(Rule::RootUnquotedKey, key).is_err()
 }
@@ -354,6 +538,47 @@ fn test_roundtrip() {
     check_roundtrip("!a && !b");
     check_roundtrip("!(a && !b)");
     check_roundtrip("!(a && b)");
+    check_roundtrip("extra.*[?(@ ~ '^tok_')]");
+    check_roundtrip("(a || b)[?(@key == 'it''s')]");
+    check_roundtrip("!a[?(@ == '')] && b");
+}
+
+#[test]
+fn test_parse_predicate() {
+    let selector = SelectorSpec::from_str(r#"extra.*[?(@ ~ "^tok_")]"#).unwrap();
+    assert_eq!(selector.to_string(), "extra.*[?(@ ~ '^tok_')]");
+
+    let predicate = match selector {
+        SelectorSpec::Predicate(_, predicate) => predicate,
+        other => panic!("unexpected selector {:?}", other),
+    };
+
+    assert_eq!(predicate.operand, PredicateOperand::Value);
+    assert!(predicate.matches(Some("tok_123"), None));
+    assert!(!predicate.matches(Some("my tok_123"), None));
+    assert!(!predicate.matches(None, None));
+
+    let selector = SelectorSpec::from_str("*.value[?(@key == 'password')]").unwrap();
+    let predicate = match selector {
+        SelectorSpec::Predicate(_, predicate) => predicate,
+        other => panic!("unexpected selector {:?}", other),
+    };
+
+    let mut siblings = BTreeMap::new();
+    siblings.insert("key".to_owned(), "password".to_owned());
+    assert_eq!(
+        predicate.operand,
+        PredicateOperand::Sibling("key".to_owned())
+    );
+    assert!(predicate.matches(Some("secret"), Some(&siblings)));
+    assert!(predicate.matches(None, Some(&siblings)));
+    assert!(!predicate.matches(Some("password"), None));
+
+    let selector = SelectorSpec::from_str("$http.headers.*[?(@key == 'X-Token')]").unwrap();
+    assert!(selector.is_specific());
+
+    assert!(SelectorSpec::from_str("extra.*[?(@ ~ '(')]").is_err());
+    assert!(SelectorSpec::from_str("extra.*[?(@ == a)]").is_err());
 }
 
 #[test]
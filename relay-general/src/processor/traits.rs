@@ -4,7 +4,7 @@
 use std::fmt::Debug;
 
 use crate::processor::{process_value, ProcessingState, ValueType};
-use crate::types::{FromValue, Meta, ProcessingResult, Timestamp, ToValue};
+use crate::types::{Annotated, FromValue, Meta, ProcessingResult, Timestamp, ToValue};
 
 macro_rules! process_method {
     ($name: ident, $ty:ident $(::$path:ident)*) => {
@@ -31,6 +31,19 @@ macro_rules! process_method {
 
 /// A trait for processing processable values.
 pub trait Processor: Sized {
+    /// Called with mutable access to the annotated value before `before_process`.
+    ///
+    /// Unlike the other callbacks, this is also invoked for empty values, so that processors can
+    /// replace or insert values of arbitrary types.
+    #[inline]
+    fn process_annotated<T: ProcessValue>(
+        &mut self,
+        annotated: &mut Annotated<T>,
+        state: &ProcessingState<'_>,
+    ) -> ProcessingResult {
+        Ok(())
+    }
+
     #[inline]
     fn before_process<T: ProcessValue>(
         &mut self,
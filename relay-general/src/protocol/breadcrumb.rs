@@ -29,10 +29,23 @@ pub struct Breadcrumb {
     pub message: Annotated<String>,
 
     /// Custom user-defined data of this breadcrumb.
+    ///
+    /// For `http` breadcrumbs, `method` and `status_code` are normalized and the query string of
+    /// `url` is moved into `http.query`. For `navigation` breadcrumbs, the query strings of `from`
+    /// and `to` are moved into `from.query` and `to.query`.
     #[metastructure(pii = "true", bag_size = "medium")]
     #[metastructure(skip_serialization = "empty")]
     pub data: Annotated<Object<Value>>,
 
+    /// Number of consecutive occurrences of this breadcrumb.
+    ///
+    /// Runs of identical breadcrumbs are collapsed into a single entry during normalization. In
+    /// that case, `timestamp` holds the time of the first occurrence.
+    pub repeat_count: Annotated<u64>,
+
+    /// The timestamp of the last occurrence of a repeated breadcrumb.
+    pub last_timestamp: Annotated<DateTime<Utc>>,
+
     /// Additional arbitrary fields for forwards compatibility.
     #[metastructure(additional_properties)]
     pub other: Object<Value>,
@@ -51,6 +64,8 @@ fn test_breadcrumb_roundtrip() {
   "data": {
     "a": "b"
   },
+  "repeat_count": 3,
+  "last_timestamp": 946684860,
   "c": "d"
 }"#;
 
@@ -63,6 +78,8 @@ fn test_breadcrumb_roundtrip() {
   "data": {
     "a": "b"
   },
+  "repeat_count": 3,
+  "last_timestamp": 946684860.0,
   "c": "d"
 }"#;
 
@@ -80,6 +97,8 @@ fn test_breadcrumb_roundtrip() {
             );
             Annotated::new(map)
         },
+        repeat_count: Annotated::new(3),
+        last_timestamp: Annotated::new(Utc.ymd(2000, 1, 1).and_hms(0, 1, 0)),
         other: {
             let mut map = Map::new();
             map.insert(
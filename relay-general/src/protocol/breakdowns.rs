@@ -0,0 +1,23 @@
+use crate::protocol::Measurements;
+use crate::types::Object;
+
+/// Breakdowns of the duration of a transaction, keyed by the name of the breakdown.
+///
+/// Each breakdown holds measurements derived from the spans of the transaction, for instance the
+/// time spent in `http` or `db` operations.
+#[derive(Clone, Debug, Default, PartialEq, Empty, FromValue, ToValue, ProcessValue)]
+pub struct Breakdowns(pub Object<Measurements>);
+
+impl std::ops::Deref for Breakdowns {
+    type Target = Object<Measurements>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+impl std::ops::DerefMut for Breakdowns {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.0
+    }
+}
@@ -6,7 +6,7 @@ use regex::Regex;
 use serde::{Serialize, Serializer};
 
 use crate::processor::ProcessValue;
-use crate::protocol::LenientString;
+use crate::protocol::{Headers, LenientString};
 use crate::types::{Annotated, Empty, Error, FromValue, Object, SkipSerialization, ToValue, Value};
 
 /// Device information.
@@ -236,6 +236,143 @@ impl BrowserContext {
     }
 }
 
+/// Network information of the client, derived from its IP address.
+#[derive(Clone, Debug, Default, PartialEq, Empty, FromValue, ToValue, ProcessValue)]
+pub struct NetworkContext {
+    /// Autonomous system number of the network.
+    pub asn: Annotated<u64>,
+
+    /// Organization that operates the autonomous system.
+    #[metastructure(max_chars = "summary")]
+    pub organization: Annotated<String>,
+
+    /// Additional arbitrary fields for forwards compatibility.
+    #[metastructure(additional_properties, retain = "true", pii = "maybe")]
+    pub other: Object<Value>,
+}
+
+impl NetworkContext {
+    /// The key under which a network context is generally stored (in `Contexts`)
+    pub fn default_key() -> &'static str {
+        "network"
+    }
+}
+
+/// Culture and locale settings of the client.
+#[derive(Clone, Debug, Default, PartialEq, Empty, FromValue, ToValue, ProcessValue)]
+pub struct CultureContext {
+    /// The locale of the client, such as `en-US`.
+    #[metastructure(max_chars = "enumlike")]
+    pub locale: Annotated<String>,
+
+    /// The timezone of the client as IANA identifier, such as `Europe/Vienna`.
+    #[metastructure(max_chars = "enumlike")]
+    pub timezone: Annotated<String>,
+
+    /// The calendar system in use, such as `GregorianCalendar`.
+    #[metastructure(max_chars = "enumlike")]
+    pub calendar: Annotated<String>,
+
+    /// Additional arbitrary fields for forwards compatibility.
+    #[metastructure(additional_properties, retain = "true", pii = "maybe")]
+    pub other: Object<Value>,
+}
+
+impl CultureContext {
+    /// The key under which a culture context is generally stored (in `Contexts`)
+    pub fn default_key() -> &'static str {
+        "culture"
+    }
+}
+
+/// Information about the HTTP response sent by the application.
+#[derive(Clone, Debug, Default, PartialEq, Empty, FromValue, ToValue, ProcessValue)]
+pub struct ResponseContext {
+    /// The HTTP status code of the response.
+    pub status_code: Annotated<u64>,
+
+    /// HTTP response headers.
+    #[metastructure(pii = "true", bag_size = "large")]
+    #[metastructure(skip_serialization = "empty")]
+    pub headers: Annotated<Headers>,
+
+    /// The size of the response body in bytes.
+    pub body_size: Annotated<u64>,
+
+    /// Additional arbitrary fields for forwards compatibility.
+    #[metastructure(additional_properties, retain = "true", pii = "true")]
+    pub other: Object<Value>,
+}
+
+impl ResponseContext {
+    /// The key under which a response context is generally stored (in `Contexts`)
+    pub fn default_key() -> &'static str {
+        "response"
+    }
+}
+
+/// Cloud infrastructure that the application is running on.
+#[derive(Clone, Debug, Default, PartialEq, Empty, FromValue, ToValue, ProcessValue)]
+pub struct CloudResourceContext {
+    /// Name of the cloud provider, such as `aws`, `azure` or `gcp`.
+    #[metastructure(max_chars = "enumlike")]
+    pub provider: Annotated<String>,
+
+    /// The geographical region of the resource, such as `us-east-1`.
+    #[metastructure(max_chars = "enumlike")]
+    pub region: Annotated<String>,
+
+    /// The account or project that owns the resource.
+    #[metastructure(pii = "maybe", max_chars = "summary")]
+    pub account: Annotated<String>,
+
+    /// The availability zone within the region, such as `us-east-1c`.
+    #[metastructure(max_chars = "enumlike")]
+    pub zone: Annotated<String>,
+
+    /// Additional arbitrary fields for forwards compatibility.
+    #[metastructure(additional_properties, retain = "true", pii = "maybe")]
+    pub other: Object<Value>,
+}
+
+impl CloudResourceContext {
+    /// The key under which a cloud resource context is generally stored (in `Contexts`)
+    pub fn default_key() -> &'static str {
+        "cloud_resource"
+    }
+}
+
+/// Kubernetes deployment information of the application.
+#[derive(Clone, Debug, Default, PartialEq, Empty, FromValue, ToValue, ProcessValue)]
+pub struct KubernetesContext {
+    /// The namespace of the pod.
+    #[metastructure(pii = "maybe", max_chars = "summary")]
+    pub namespace: Annotated<String>,
+
+    /// Name of the pod running the application.
+    #[metastructure(pii = "maybe", max_chars = "summary")]
+    pub pod: Annotated<String>,
+
+    /// Name of the node that the pod is scheduled on.
+    #[metastructure(pii = "maybe", max_chars = "summary")]
+    pub node: Annotated<String>,
+
+    /// Name of the container within the pod.
+    #[metastructure(pii = "maybe", max_chars = "summary")]
+    pub container: Annotated<String>,
+
+    /// Additional arbitrary fields for forwards compatibility.
+    #[metastructure(additional_properties, retain = "true", pii = "maybe")]
+    pub other: Object<Value>,
+}
+
+impl KubernetesContext {
+    /// The key under which a Kubernetes context is generally stored (in `Contexts`)
+    pub fn default_key() -> &'static str {
+        "kubernetes"
+    }
+}
+
 /// Operation type such as `db.statement` for database queries or `http` for external HTTP calls.
 /// Tries to follow OpenCensus/OpenTracing's span types.
 pub type OperationType = String;
@@ -626,6 +763,17 @@ pub enum Context {
     Trace(Box<TraceContext>),
     /// Information related to Monitors feature.
     Monitor(Box<MonitorContext>),
+    /// Network information of the client.
+    Network(Box<NetworkContext>),
+    /// Culture and locale settings of the client.
+    Culture(Box<CultureContext>),
+    /// Information about the HTTP response.
+    Response(Box<ResponseContext>),
+    /// Cloud infrastructure information.
+    #[metastructure(tag = "cloud_resource")]
+    CloudResource(Box<CloudResourceContext>),
+    /// Kubernetes deployment information.
+    Kubernetes(Box<KubernetesContext>),
     /// Additional arbitrary fields for forwards compatibility.
     #[metastructure(fallback_variant)]
     Other(#[metastructure(pii = "true")] Object<Value>),
@@ -645,6 +793,11 @@ impl Context {
             Context::Gpu(_) => Some(GpuContext::default_key()),
             Context::Trace(_) => Some(TraceContext::default_key()),
             Context::Monitor(_) => Some(MonitorContext::default_key()),
+            Context::Network(_) => Some(NetworkContext::default_key()),
+            Context::Culture(_) => Some(CultureContext::default_key()),
+            Context::Response(_) => Some(ResponseContext::default_key()),
+            Context::CloudResource(_) => Some(CloudResourceContext::default_key()),
+            Context::Kubernetes(_) => Some(KubernetesContext::default_key()),
             _ => None,
         }
     }
@@ -928,6 +1081,122 @@ fn test_browser_context_roundtrip() {
     assert_eq_str!(json, context.to_json_pretty().unwrap());
 }
 
+#[test]
+fn test_network_context_roundtrip() {
+    let json = r#"{
+  "asn": 15169,
+  "organization": "Google LLC",
+  "type": "network"
+}"#;
+    let context = Annotated::new(Context::Network(Box::new(NetworkContext {
+        asn: Annotated::new(15169),
+        organization: Annotated::new("Google LLC".to_string()),
+        other: Object::default(),
+    })));
+
+    assert_eq_dbg!(context, Annotated::from_json(json).unwrap());
+    assert_eq_str!(json, context.to_json_pretty().unwrap());
+}
+
+#[test]
+fn test_culture_context_roundtrip() {
+    let json = r#"{
+  "locale": "de-AT",
+  "timezone": "Europe/Vienna",
+  "calendar": "GregorianCalendar",
+  "other": "value",
+  "type": "culture"
+}"#;
+    let context = Annotated::new(Context::Culture(Box::new(CultureContext {
+        locale: Annotated::new("de-AT".to_string()),
+        timezone: Annotated::new("Europe/Vienna".to_string()),
+        calendar: Annotated::new("GregorianCalendar".to_string()),
+        other: {
+            let mut map = Object::new();
+            map.insert(
+                "other".to_string(),
+                Annotated::new(Value::String("value".to_string())),
+            );
+            map
+        },
+    })));
+
+    assert_eq_dbg!(context, Annotated::from_json(json).unwrap());
+    assert_eq_str!(json, context.to_json_pretty().unwrap());
+}
+
+#[test]
+fn test_response_context_roundtrip() {
+    use crate::protocol::PairList;
+
+    let json = r#"{
+  "status_code": 500,
+  "headers": [
+    [
+      "Content-Type",
+      "text/html"
+    ]
+  ],
+  "body_size": 1000,
+  "type": "response"
+}"#;
+    let headers = vec![Annotated::new((
+        Annotated::new("Content-Type".to_string().into()),
+        Annotated::new("text/html".to_string().into()),
+    ))];
+    let context = Annotated::new(Context::Response(Box::new(ResponseContext {
+        status_code: Annotated::new(500),
+        headers: Annotated::new(Headers(PairList(headers))),
+        body_size: Annotated::new(1000),
+        other: Object::default(),
+    })));
+
+    assert_eq_dbg!(context, Annotated::from_json(json).unwrap());
+    assert_eq_str!(json, context.to_json_pretty().unwrap());
+}
+
+#[test]
+fn test_cloud_resource_context_roundtrip() {
+    let json = r#"{
+  "provider": "aws",
+  "region": "us-east-1",
+  "account": "123456789012",
+  "zone": "us-east-1c",
+  "type": "cloud_resource"
+}"#;
+    let context = Annotated::new(Context::CloudResource(Box::new(CloudResourceContext {
+        provider: Annotated::new("aws".to_string()),
+        region: Annotated::new("us-east-1".to_string()),
+        account: Annotated::new("123456789012".to_string()),
+        zone: Annotated::new("us-east-1c".to_string()),
+        other: Object::default(),
+    })));
+
+    assert_eq_dbg!(context, Annotated::from_json(json).unwrap());
+    assert_eq_str!(json, context.to_json_pretty().unwrap());
+}
+
+#[test]
+fn test_kubernetes_context_roundtrip() {
+    let json = r#"{
+  "namespace": "default",
+  "pod": "web-5d8f7b9c4-x2x7k",
+  "node": "node-1",
+  "container": "web",
+  "type": "kubernetes"
+}"#;
+    let context = Annotated::new(Context::Kubernetes(Box::new(KubernetesContext {
+        namespace: Annotated::new("default".to_string()),
+        pod: Annotated::new("web-5d8f7b9c4-x2x7k".to_string()),
+        node: Annotated::new("node-1".to_string()),
+        container: Annotated::new("web".to_string()),
+        other: Object::default(),
+    })));
+
+    assert_eq_dbg!(context, Annotated::from_json(json).unwrap());
+    assert_eq_str!(json, context.to_json_pretty().unwrap());
+}
+
 #[test]
 fn test_trace_context_roundtrip() {
     let json = r#"{
@@ -223,6 +223,47 @@ pub struct ProguardDebugImage {
     pub other: Object<Value>,
 }
 
+/// A WebAssembly module with optional external debug information.
+#[derive(Clone, Debug, Default, PartialEq, Empty, FromValue, ToValue, ProcessValue)]
+pub struct WasmDebugImage {
+    /// Optional identifier of the code file, usually the build id of the module.
+    pub code_id: Annotated<CodeId>,
+
+    /// URL or path of the WebAssembly module (required).
+    #[metastructure(required = "true")]
+    #[metastructure(pii = "maybe")]
+    pub code_file: Annotated<NativeImagePath>,
+
+    /// Unique debug identifier of the module.
+    #[metastructure(required = "true")]
+    pub debug_id: Annotated<DebugId>,
+
+    /// URL or path of the external debug file, if the module does not embed debug information.
+    #[metastructure(pii = "maybe")]
+    pub debug_file: Annotated<NativeImagePath>,
+
+    /// Additional arbitrary fields for forwards compatibility.
+    #[metastructure(additional_properties)]
+    pub other: Object<Value>,
+}
+
+/// A source map for a minified or transpiled JavaScript file.
+#[derive(Clone, Debug, Default, PartialEq, Empty, FromValue, ToValue, ProcessValue)]
+pub struct SourceMapDebugImage {
+    /// URL or path of the minified source file (required).
+    #[metastructure(required = "true")]
+    #[metastructure(pii = "maybe")]
+    pub code_file: Annotated<NativeImagePath>,
+
+    /// Unique debug identifier of the source map.
+    #[metastructure(required = "true")]
+    pub debug_id: Annotated<DebugId>,
+
+    /// Additional arbitrary fields for forwards compatibility.
+    #[metastructure(additional_properties)]
+    pub other: Object<Value>,
+}
+
 /// A debug information file (debug image).
 #[derive(Clone, Debug, PartialEq, Empty, FromValue, ToValue, ProcessValue)]
 #[metastructure(process_func = "process_debug_image")]
@@ -241,6 +282,10 @@ pub enum DebugImage {
     Pe(Box<NativeDebugImage>),
     /// A reference to a proguard debug file.
     Proguard(Box<ProguardDebugImage>),
+    /// WebAssembly debug image.
+    Wasm(Box<WasmDebugImage>),
+    /// A reference to a JavaScript source map.
+    SourceMap(Box<SourceMapDebugImage>),
     /// A debug image that is unknown to this protocol specification.
     #[metastructure(fallback_variant)]
     Other(Object<Value>),
@@ -560,6 +605,64 @@ fn test_debug_image_pe_roundtrip() {
     assert_eq_str!(json, image.to_json_pretty().unwrap());
 }
 
+#[test]
+fn test_debug_image_wasm_roundtrip() {
+    let json = r#"{
+  "code_id": "4b8e4a3f0c3a1d9b",
+  "code_file": "https://example.com/app.wasm",
+  "debug_id": "3f4a8e4b-3a0c-9b1d-8a7e-4c2d1f6b5a90",
+  "debug_file": "https://example.com/app.debug.wasm",
+  "type": "wasm"
+}"#;
+
+    let image = Annotated::new(DebugImage::Wasm(Box::new(WasmDebugImage {
+        code_id: Annotated::new("4b8e4a3f0c3a1d9b".parse().unwrap()),
+        code_file: Annotated::new("https://example.com/app.wasm".into()),
+        debug_id: Annotated::new("3f4a8e4b-3a0c-9b1d-8a7e-4c2d1f6b5a90".parse().unwrap()),
+        debug_file: Annotated::new("https://example.com/app.debug.wasm".into()),
+        other: Object::default(),
+    })));
+
+    assert_eq_dbg!(image, Annotated::from_json(json).unwrap());
+    assert_eq_str!(json, image.to_json_pretty().unwrap());
+}
+
+#[test]
+fn test_debug_image_sourcemap_roundtrip() {
+    let json = r#"{
+  "code_file": "https://example.com/static/app.min.js",
+  "debug_id": "3f4a8e4b-3a0c-9b1d-8a7e-4c2d1f6b5a90",
+  "type": "sourcemap"
+}"#;
+
+    let image = Annotated::new(DebugImage::SourceMap(Box::new(SourceMapDebugImage {
+        code_file: Annotated::new("https://example.com/static/app.min.js".into()),
+        debug_id: Annotated::new("3f4a8e4b-3a0c-9b1d-8a7e-4c2d1f6b5a90".parse().unwrap()),
+        other: Object::default(),
+    })));
+
+    assert_eq_dbg!(image, Annotated::from_json(json).unwrap());
+    assert_eq_str!(json, image.to_json_pretty().unwrap());
+}
+
+#[test]
+fn test_debug_image_sourcemap_invalid_debug_id() {
+    let json = r#"{
+  "code_file": "https://example.com/static/app.min.js",
+  "debug_id": "not-a-debug-id",
+  "type": "sourcemap"
+}"#;
+
+    let image = Annotated::<DebugImage>::from_json(json).unwrap();
+    match image.value() {
+        Some(DebugImage::SourceMap(image)) => {
+            assert!(image.debug_id.value().is_none());
+            assert!(image.debug_id.meta().has_errors());
+        }
+        other => panic!("unexpected debug image: {:?}", other),
+    }
+}
+
 #[test]
 fn test_debug_image_other_roundtrip() {
     let json = r#"{"other":"value","type":"mytype"}"#;
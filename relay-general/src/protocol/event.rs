@@ -10,9 +10,9 @@ use chrono::TimeZone;
 
 use crate::processor::ProcessValue;
 use crate::protocol::{
-    Breadcrumb, ClientSdkInfo, Contexts, Csp, DebugMeta, Exception, ExpectCt, ExpectStaple,
-    Fingerprint, Hpkp, LenientString, Level, LogEntry, Metrics, Request, Span, Stacktrace, Tags,
-    TemplateInfo, Thread, User, Values,
+    Breadcrumb, Breakdowns, ClientSdkInfo, Contexts, Csp, DebugMeta, Exception, ExpectCt,
+    ExpectStaple, Fingerprint, Hpkp, LenientString, Level, LogEntry, Measurements, Metrics,
+    Request, Span, Stacktrace, Tags, TemplateInfo, Thread, User, Values,
 };
 use crate::types::{
     Annotated, Array, Empty, ErrorKind, FromValue, Object, SkipSerialization, ToValue, Value,
@@ -392,6 +392,16 @@ pub struct Event {
     /// Spans for tracing.
     pub spans: Annotated<Array<Span>>,
 
+    /// Measurements of a transaction, such as web vitals.
+    ///
+    /// Only transaction events may carry measurements.
+    pub measurements: Annotated<Measurements>,
+
+    /// Breakdowns of the transaction duration computed from its spans.
+    ///
+    /// This value should not be ingested and will be overwritten by the store normalizer.
+    pub breakdowns: Annotated<Breakdowns>,
+
     /// Internal ingestion and processing metrics.
     ///
     /// This value should not be ingested and will be overwritten by the store normalizer.
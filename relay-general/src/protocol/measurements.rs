@@ -0,0 +1,79 @@
+use crate::types::{Annotated, Object, Value};
+
+/// A single measurement value with an optional unit.
+#[derive(Clone, Debug, Default, PartialEq, Empty, FromValue, ToValue, ProcessValue)]
+pub struct Measurement {
+    /// Value of the measurement.
+    #[metastructure(required = "true")]
+    pub value: Annotated<f64>,
+
+    /// Unit of the measurement, such as `millisecond` or `byte`.
+    #[metastructure(max_chars = "enumlike")]
+    pub unit: Annotated<String>,
+
+    /// Additional arbitrary fields for forwards compatibility.
+    #[metastructure(additional_properties, retain = "true")]
+    pub other: Object<Value>,
+}
+
+/// Numeric measurements of a transaction, such as web vitals or custom application metrics.
+///
+/// Keys are measurement names, for example `lcp`, `fcp`, `cls`, `fid` or `ttfb`.
+#[derive(Clone, Debug, Default, PartialEq, Empty, FromValue, ToValue, ProcessValue)]
+pub struct Measurements(pub Object<Measurement>);
+
+impl Measurements {
+    /// Returns the underlying object of measurements.
+    pub fn into_inner(self) -> Object<Measurement> {
+        self.0
+    }
+}
+
+impl std::ops::Deref for Measurements {
+    type Target = Object<Measurement>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+impl std::ops::DerefMut for Measurements {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.0
+    }
+}
+
+#[test]
+fn test_measurements_roundtrip() {
+    let json = r#"{
+  "fcp": {
+    "value": 1237.5,
+    "unit": "millisecond"
+  },
+  "lcp": {
+    "value": 420.0
+  }
+}"#;
+
+    let mut measurements = Object::new();
+    measurements.insert(
+        "fcp".to_owned(),
+        Annotated::new(Measurement {
+            value: Annotated::new(1237.5),
+            unit: Annotated::new("millisecond".to_owned()),
+            other: Object::new(),
+        }),
+    );
+    measurements.insert(
+        "lcp".to_owned(),
+        Annotated::new(Measurement {
+            value: Annotated::new(420.0),
+            unit: Annotated::empty(),
+            other: Object::new(),
+        }),
+    );
+    let measurements = Annotated::new(Measurements(measurements));
+
+    assert_eq_dbg!(measurements, Annotated::from_json(json).unwrap());
+    assert_eq_str!(json, measurements.to_json_pretty().unwrap());
+}
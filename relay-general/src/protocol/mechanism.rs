@@ -43,6 +43,35 @@ pub struct PosixSignal {
     pub code_name: Annotated<String>,
 }
 
+/// Windows structured exception or NTSTATUS code.
+#[derive(Clone, Debug, Default, PartialEq, Empty, FromValue, ToValue, ProcessValue)]
+pub struct WindowsException {
+    /// The exception code, such as `0xC0000005` for access violations.
+    pub code: Annotated<u64>,
+
+    /// Optional name of the exception code.
+    pub name: Annotated<String>,
+}
+
+/// HRESULT error code of COM and .NET errors.
+#[derive(Clone, Debug, Default, PartialEq, Empty, FromValue, ToValue, ProcessValue)]
+pub struct HResult {
+    /// The HRESULT value. Negative numbers are interpreted as signed 32-bit integers.
+    pub code: Annotated<i64>,
+
+    /// Optional name of the HRESULT constant.
+    pub name: Annotated<String>,
+
+    /// The facility that produced the error.
+    pub facility: Annotated<u64>,
+
+    /// Optional name of the facility.
+    pub facility_name: Annotated<String>,
+
+    /// The facility-specific error code.
+    pub error_code: Annotated<u64>,
+}
+
 /// Operating system or runtime meta information to an exception mechanism.
 #[derive(Clone, Debug, Default, PartialEq, Empty, FromValue, ToValue, ProcessValue)]
 pub struct MechanismMeta {
@@ -55,6 +84,12 @@ pub struct MechanismMeta {
     /// Optional mach exception information.
     pub mach_exception: Annotated<MachException>,
 
+    /// Optional Windows exception or NTSTATUS code.
+    pub windows_exception: Annotated<WindowsException>,
+
+    /// Optional HRESULT of a COM or .NET error.
+    pub hresult: Annotated<HResult>,
+
     /// Additional arbitrary fields for forwards compatibility.
     #[metastructure(additional_properties)]
     pub other: Object<Value>,
@@ -89,6 +124,24 @@ pub struct Mechanism {
     /// Flag indicating whether this exception was handled.
     pub handled: Annotated<bool>,
 
+    /// An identifier for this exception, unique within the event.
+    ///
+    /// Exceptions in a group or chain reference their parent through `parent_id`.
+    pub exception_id: Annotated<u64>,
+
+    /// The `exception_id` of the parent exception, such as the exception group containing this
+    /// exception or the exception that this exception caused.
+    pub parent_id: Annotated<u64>,
+
+    /// Flag indicating that this exception groups other exceptions, such as a Python
+    /// `ExceptionGroup`, a .NET `AggregateException` or a JavaScript `AggregateError`.
+    pub is_exception_group: Annotated<bool>,
+
+    /// The attribute of the parent exception that holds this exception, such as `__context__`,
+    /// `InnerExceptions[1]`, `errors[0]` or `cause`.
+    #[metastructure(max_chars = "enumlike")]
+    pub source: Annotated<String>,
+
     /// Additional attributes depending on the mechanism type.
     #[metastructure(pii = "true", bag_size = "medium")]
     #[metastructure(skip_serialization = "empty")]
@@ -113,6 +166,10 @@ impl FromValue for Mechanism {
             pub description: Annotated<String>,
             pub help_link: Annotated<String>,
             pub handled: Annotated<bool>,
+            pub exception_id: Annotated<u64>,
+            pub parent_id: Annotated<u64>,
+            pub is_exception_group: Annotated<bool>,
+            pub source: Annotated<String>,
             pub data: Annotated<Object<Value>>,
             pub meta: Annotated<MechanismMeta>,
             #[metastructure(additional_properties)]
@@ -155,6 +212,10 @@ impl FromValue for Mechanism {
                         description: mechanism.description,
                         help_link: mechanism.help_link,
                         handled: mechanism.handled,
+                        exception_id: mechanism.exception_id,
+                        parent_id: mechanism.parent_id,
+                        is_exception_group: mechanism.is_exception_group,
+                        source: mechanism.source,
                         data: mechanism.data,
                         meta: mechanism.meta,
                         other: mechanism.other,
@@ -167,6 +228,10 @@ impl FromValue for Mechanism {
                         description: Annotated::empty(),
                         help_link: Annotated::empty(),
                         handled: Annotated::empty(),
+                        exception_id: Annotated::empty(),
+                        parent_id: Annotated::empty(),
+                        is_exception_group: Annotated::empty(),
+                        source: Annotated::empty(),
                         data: Annotated::new(legacy.other),
                         meta: Annotated::new(MechanismMeta {
                             errno: Annotated::empty(),
@@ -184,6 +249,8 @@ impl FromValue for Mechanism {
                                     name: legacy.exception_name,
                                 }
                             }),
+                            windows_exception: Annotated::empty(),
+                            hresult: Annotated::empty(),
                             other: Object::default(),
                         }),
                         other: Object::default(),
@@ -208,6 +275,10 @@ fn test_mechanism_roundtrip() {
   "description": "mydescription",
   "help_link": "https://developer.apple.com/library/content/qa/qa1367/_index.html",
   "handled": false,
+  "exception_id": 1,
+  "parent_id": 0,
+  "is_exception_group": true,
+  "source": "exceptions[0]",
   "data": {
     "relevant_address": "0x1"
   },
@@ -228,6 +299,17 @@ fn test_mechanism_roundtrip() {
       "subcode": 8,
       "name": "EXC_BAD_ACCESS"
     },
+    "windows_exception": {
+      "code": 3221225477,
+      "name": "EXCEPTION_ACCESS_VIOLATION"
+    },
+    "hresult": {
+      "code": -2147467261,
+      "name": "E_POINTER",
+      "facility": 0,
+      "facility_name": "FACILITY_NULL",
+      "error_code": 16387
+    },
     "other": "value"
   },
   "other": "value"
@@ -240,6 +322,10 @@ fn test_mechanism_roundtrip() {
             "https://developer.apple.com/library/content/qa/qa1367/_index.html".to_string(),
         ),
         handled: Annotated::new(false),
+        exception_id: Annotated::new(1),
+        parent_id: Annotated::new(0),
+        is_exception_group: Annotated::new(true),
+        source: Annotated::new("exceptions[0]".to_string()),
         data: {
             let mut map = Map::new();
             map.insert(
@@ -265,6 +351,17 @@ fn test_mechanism_roundtrip() {
                 name: Annotated::new("SIGSEGV".to_string()),
                 code_name: Annotated::new("SEGV_NOOP".to_string()),
             }),
+            windows_exception: Annotated::new(WindowsException {
+                code: Annotated::new(0xC000_0005),
+                name: Annotated::new("EXCEPTION_ACCESS_VIOLATION".to_string()),
+            }),
+            hresult: Annotated::new(HResult {
+                code: Annotated::new(-2_147_467_261),
+                name: Annotated::new("E_POINTER".to_string()),
+                facility: Annotated::new(0),
+                facility_name: Annotated::new("FACILITY_NULL".to_string()),
+                error_code: Annotated::new(0x4003),
+            }),
             other: {
                 let mut map = Object::new();
                 map.insert(
@@ -352,6 +449,10 @@ fn test_mechanism_legacy_conversion() {
         description: Annotated::empty(),
         help_link: Annotated::empty(),
         handled: Annotated::empty(),
+        exception_id: Annotated::empty(),
+        parent_id: Annotated::empty(),
+        is_exception_group: Annotated::empty(),
+        source: Annotated::empty(),
         data: {
             let mut map = Map::new();
             map.insert(
@@ -374,6 +475,8 @@ fn test_mechanism_legacy_conversion() {
                 name: Annotated::new("SIGSEGV".to_string()),
                 code_name: Annotated::new("SEGV_NOOP".to_string()),
             }),
+            windows_exception: Annotated::empty(),
+            hresult: Annotated::empty(),
             other: Object::default(),
         }),
         other: Object::default(),
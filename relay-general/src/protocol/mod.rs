@@ -1,5 +1,6 @@
 //! Implements the sentry event protocol.
 mod breadcrumb;
+mod breakdowns;
 mod clientsdk;
 mod constants;
 mod contexts;
@@ -8,6 +9,7 @@ mod event;
 mod exception;
 mod fingerprint;
 mod logentry;
+mod measurements;
 mod mechanism;
 mod metrics;
 mod request;
@@ -23,14 +25,17 @@ mod user;
 mod user_report;
 
 pub use self::breadcrumb::Breadcrumb;
+pub use self::breakdowns::Breakdowns;
 pub use self::clientsdk::{ClientSdkInfo, ClientSdkPackage};
 pub use self::constants::{INVALID_ENVIRONMENTS, INVALID_RELEASES, VALID_PLATFORMS};
 pub use self::contexts::{
-    AppContext, BrowserContext, Context, ContextInner, Contexts, DeviceContext, GpuContext,
-    OperationType, OsContext, RuntimeContext, SpanId, SpanStatus, TraceContext, TraceId,
+    AppContext, BrowserContext, CloudResourceContext, Context, ContextInner, Contexts,
+    CultureContext, DeviceContext, GpuContext, KubernetesContext, NetworkContext, OperationType,
+    OsContext, ResponseContext, RuntimeContext, SpanId, SpanStatus, TraceContext, TraceId,
 };
 pub use self::debugmeta::{
-    AppleDebugImage, DebugImage, DebugMeta, NativeDebugImage, NativeImagePath, SystemSdkInfo,
+    AppleDebugImage, DebugImage, DebugMeta, NativeDebugImage, NativeImagePath, SourceMapDebugImage,
+    SystemSdkInfo, WasmDebugImage,
 };
 pub use self::event::{
     Event, EventId, EventProcessingError, EventType, ExtraValue, GroupingConfig,
@@ -39,7 +44,10 @@ pub use self::event::{
 pub use self::exception::Exception;
 pub use self::fingerprint::Fingerprint;
 pub use self::logentry::{LogEntry, Message};
-pub use self::mechanism::{CError, MachException, Mechanism, MechanismMeta, PosixSignal};
+pub use self::measurements::{Measurement, Measurements};
+pub use self::mechanism::{
+    CError, HResult, MachException, Mechanism, MechanismMeta, PosixSignal, WindowsException,
+};
 pub use self::metrics::Metrics;
 pub use self::request::{Cookies, HeaderName, HeaderValue, Headers, Query, Request};
 pub use self::security_report::{Csp, ExpectCt, ExpectStaple, Hpkp, SecurityReportType};
@@ -48,7 +56,7 @@ pub use self::span::Span;
 pub use self::stacktrace::{Frame, FrameData, FrameVars, RawStacktrace, Stacktrace};
 pub use self::tags::{TagEntry, Tags};
 pub use self::templateinfo::TemplateInfo;
-pub use self::thread::{Thread, ThreadId};
+pub use self::thread::{LockReason, LockReasonType, ParseLockReasonTypeError, Thread, ThreadId};
 pub use self::types::{
     Addr, AsPair, InvalidRegVal, IpAddr, JsonLenientString, LenientString, Level, PairList,
     ParseLevelError, RegVal, Values,
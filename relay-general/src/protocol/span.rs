@@ -1,6 +1,6 @@
 use chrono::{DateTime, Utc};
 
-use crate::protocol::{OperationType, SpanId, TraceId};
+use crate::protocol::{JsonLenientString, OperationType, SpanId, SpanStatus, TraceId};
 use crate::types::{Annotated, Object, Value};
 
 #[derive(Clone, Debug, Default, PartialEq, Empty, FromValue, ToValue, ProcessValue)]
@@ -34,6 +34,17 @@ pub struct Span {
     #[metastructure(required = "true")]
     pub trace_id: Annotated<TraceId>,
 
+    /// The status of the span.
+    pub status: Annotated<SpanStatus>,
+
+    /// Arbitrary tags on a span, like on the top-level event.
+    #[metastructure(pii = "maybe", bag_size = "small")]
+    pub tags: Annotated<Object<JsonLenientString>>,
+
+    /// Arbitrary additional data on a span, like `extra` on the top-level event.
+    #[metastructure(pii = "true", bag_size = "medium")]
+    pub data: Annotated<Object<Value>>,
+
     // TODO remove retain when the api stabilizes
     /// Additional arbitrary fields for forwards compatibility.
     #[metastructure(additional_properties, retain = "true", pii = "maybe")]
@@ -53,7 +64,16 @@ mod tests {
   "description": "desc",
   "op": "operation",
   "span_id": "fa90fdead5f74052",
-  "trace_id": "4c79f60c11214eb38604f4ae0781bfb2"
+  "trace_id": "4c79f60c11214eb38604f4ae0781bfb2",
+  "status": "ok",
+  "tags": {
+    "http.status_code": "200"
+  },
+  "data": {
+    "query": [
+      "SELECT 1"
+    ]
+  }
 }"#;
 
         let span = Annotated::new(Span {
@@ -63,6 +83,25 @@ mod tests {
             op: Annotated::new("operation".to_owned()),
             trace_id: Annotated::new(TraceId("4c79f60c11214eb38604f4ae0781bfb2".into())),
             span_id: Annotated::new(SpanId("fa90fdead5f74052".into())),
+            status: Annotated::new(SpanStatus::Ok),
+            tags: {
+                let mut tags = Object::new();
+                tags.insert(
+                    "http.status_code".to_owned(),
+                    Annotated::new(JsonLenientString::from("200".to_owned())),
+                );
+                Annotated::new(tags)
+            },
+            data: {
+                let mut data = Object::new();
+                data.insert(
+                    "query".to_owned(),
+                    Annotated::new(Value::Array(vec![Annotated::new(Value::String(
+                        "SELECT 1".to_owned(),
+                    ))])),
+                );
+                Annotated::new(data)
+            },
             ..Default::default()
         });
         assert_eq_str!(json, span.to_json_pretty().unwrap());
@@ -105,6 +105,14 @@ pub struct Frame {
     /// Start address of the frame's function.
     pub symbol_addr: Annotated<Addr>,
 
+    /// Defines the addressing mode for addresses in this frame.
+    ///
+    /// Defaults to `abs` for absolute addresses. `rel:N` marks addresses as relative to the debug
+    /// image at index `N` in `debug_meta.images`, and `rel_func:N` as relative to the start of the
+    /// function within that image.
+    #[metastructure(max_chars = "enumlike")]
+    pub addr_mode: Annotated<String>,
+
     /// Used for native crashes to indicate how much we can "trust" the instruction_addr
     #[metastructure(max_chars = "enumlike")]
     pub trust: Annotated<String>,
@@ -146,7 +154,10 @@ pub struct FrameData {
     /// - `-1`: in_app was set to `null`
     /// - `0`: in_app was set to `false`
     /// - `1`: in_app was set to `true`
-    orig_in_app: Annotated<i64>,
+    pub orig_in_app: Annotated<i64>,
+    /// The category of this frame assigned by stack trace rules, such as `framework` or `ui`.
+    #[metastructure(max_chars = "enumlike")]
+    pub category: Annotated<String>,
     /// Additional keys not handled by this protocol.
     #[metastructure(additional_properties)]
     pub other: Object<Value>,
@@ -253,11 +264,13 @@ fn test_frame_roundtrip() {
     "variable": "value"
   },
   "data": {
-    "sourcemap": "http://example.com/invalid.map"
+    "sourcemap": "http://example.com/invalid.map",
+    "category": "framework"
   },
   "image_addr": "0x400",
   "instruction_addr": "0x404",
   "symbol_addr": "0x404",
+  "addr_mode": "rel:0",
   "trust": "69",
   "lang": "rust",
   "other": "value"
@@ -287,11 +300,13 @@ fn test_frame_roundtrip() {
         },
         data: Annotated::new(FrameData {
             sourcemap: Annotated::new("http://example.com/invalid.map".to_string()),
+            category: Annotated::new("framework".to_string()),
             ..Default::default()
         }),
         image_addr: Annotated::new(Addr(0x400)),
         instruction_addr: Annotated::new(Addr(0x404)),
         symbol_addr: Annotated::new(Addr(0x404)),
+        addr_mode: Annotated::new("rel:0".into()),
         trust: Annotated::new("69".into()),
         lang: Annotated::new("rust".into()),
         other: {
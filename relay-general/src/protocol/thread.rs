@@ -1,3 +1,7 @@
+use std::fmt;
+use std::str::FromStr;
+
+use failure::Fail;
 use serde::{Deserialize, Serialize, Serializer};
 
 use crate::processor::ProcessValue;
@@ -67,6 +71,80 @@ impl Empty for ThreadId {
     }
 }
 
+/// An error used when parsing `LockReasonType`.
+#[derive(Debug, Fail)]
+#[fail(display = "invalid lock reason type")]
+pub struct ParseLockReasonTypeError;
+
+/// The relation of a thread to a lock.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum LockReasonType {
+    /// The thread holds the lock.
+    Locked,
+    /// The thread waits on the lock to be notified.
+    Waiting,
+    /// The thread sleeps while holding the lock.
+    Sleeping,
+    /// The thread is blocked trying to acquire the lock.
+    Blocked,
+}
+
+impl FromStr for LockReasonType {
+    type Err = ParseLockReasonTypeError;
+
+    fn from_str(string: &str) -> Result<Self, Self::Err> {
+        Ok(match string {
+            "locked" => LockReasonType::Locked,
+            "waiting" => LockReasonType::Waiting,
+            "sleeping" => LockReasonType::Sleeping,
+            "blocked" => LockReasonType::Blocked,
+            _ => return Err(ParseLockReasonTypeError),
+        })
+    }
+}
+
+impl fmt::Display for LockReasonType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            LockReasonType::Locked => write!(f, "locked"),
+            LockReasonType::Waiting => write!(f, "waiting"),
+            LockReasonType::Sleeping => write!(f, "sleeping"),
+            LockReasonType::Blocked => write!(f, "blocked"),
+        }
+    }
+}
+
+derive_string_meta_structure!(LockReasonType, "a lock reason type");
+
+impl ProcessValue for LockReasonType {}
+
+/// A lock held or awaited by a thread, as found in JVM and Android thread dumps.
+#[derive(Clone, Debug, Default, PartialEq, Empty, FromValue, ToValue, ProcessValue)]
+pub struct LockReason {
+    /// How the thread relates to the lock.
+    #[metastructure(field = "type", required = "true")]
+    pub ty: Annotated<LockReasonType>,
+
+    /// Address of the monitor object, such as `0x0d3a2f0a`.
+    #[metastructure(max_chars = "symbol")]
+    pub address: Annotated<String>,
+
+    /// Package name of the locked object's class.
+    #[metastructure(max_chars = "symbol")]
+    pub package_name: Annotated<String>,
+
+    /// Class name of the locked object.
+    #[metastructure(max_chars = "symbol")]
+    pub class_name: Annotated<String>,
+
+    /// Identifier of the thread that owns the lock.
+    pub thread_id: Annotated<ThreadId>,
+
+    /// Additional arbitrary fields for forwards compatibility.
+    #[metastructure(additional_properties)]
+    pub other: Object<Value>,
+}
+
 /// A process thread of an event.
 #[derive(Clone, Debug, Default, PartialEq, Empty, FromValue, ToValue, ProcessValue)]
 #[metastructure(process_func = "process_thread", value_type = "Thread")]
@@ -93,6 +171,19 @@ pub struct Thread {
     /// Indicates that the thread was not suspended when the event was created.
     pub current: Annotated<bool>,
 
+    /// Indicates that this is the main thread of the application, such as the UI thread.
+    pub main: Annotated<bool>,
+
+    /// Execution state of the thread, such as `RUNNABLE`, `BLOCKED` or `WAITING`.
+    ///
+    /// Runtime-specific state names are normalized to the JVM thread states during processing.
+    #[metastructure(max_chars = "enumlike")]
+    pub state: Annotated<String>,
+
+    /// Locks held or awaited by this thread, keyed by the address of the lock.
+    #[metastructure(skip_serialization = "empty")]
+    pub held_locks: Annotated<Object<LockReason>>,
+
     /// Additional arbitrary fields for forwards compatibility.
     #[metastructure(additional_properties)]
     pub other: Object<Value>,
@@ -130,8 +221,32 @@ fn test_thread_roundtrip() {
   "name": "myname",
   "crashed": true,
   "current": true,
+  "main": false,
+  "state": "BLOCKED",
+  "held_locks": {
+    "0x0d3a2f0a": {
+      "type": "blocked",
+      "address": "0x0d3a2f0a",
+      "package_name": "java.lang",
+      "class_name": "Object",
+      "thread_id": 11
+    }
+  },
   "other": "value"
 }"#;
+    let mut held_locks = Map::new();
+    held_locks.insert(
+        "0x0d3a2f0a".to_string(),
+        Annotated::new(LockReason {
+            ty: Annotated::new(LockReasonType::Blocked),
+            address: Annotated::new("0x0d3a2f0a".to_string()),
+            package_name: Annotated::new("java.lang".to_string()),
+            class_name: Annotated::new("Object".to_string()),
+            thread_id: Annotated::new(ThreadId::Int(11)),
+            other: Map::new(),
+        }),
+    );
+
     let thread = Annotated::new(Thread {
         id: Annotated::new(ThreadId::Int(42)),
         name: Annotated::new("myname".to_string()),
@@ -139,6 +254,9 @@ fn test_thread_roundtrip() {
         raw_stacktrace: Annotated::empty(),
         crashed: Annotated::new(true),
         current: Annotated::new(true),
+        main: Annotated::new(false),
+        state: Annotated::new("BLOCKED".to_string()),
+        held_locks: Annotated::new(held_locks),
         other: {
             let mut map = Map::new();
             map.insert(
@@ -161,3 +279,14 @@ fn test_thread_default_values() {
     assert_eq_dbg!(thread, Annotated::from_json(json).unwrap());
     assert_eq_str!(json, thread.to_json_pretty().unwrap());
 }
+
+#[test]
+fn test_invalid_lock_reason_type() {
+    let json = r#"{"type": "spinning", "address": "0x0d3a2f0a"}"#;
+    let lock = Annotated::<LockReason>::from_json(json).unwrap();
+    let lock = lock.value().unwrap();
+
+    assert!(lock.ty.value().is_none());
+    assert!(lock.ty.meta().has_errors());
+    assert_eq_dbg!(lock.address.as_str(), Some("0x0d3a2f0a"));
+}
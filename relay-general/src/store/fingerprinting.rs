@@ -0,0 +1,442 @@
+//! Server-side fingerprinting rules.
+//!
+//! Fingerprinting rules override the fingerprint of events that match all of their matchers. This
+//! allows to customize grouping without deploying changes to SDKs.
+use lazy_static::lazy_static;
+use regex::{Captures, Regex};
+use serde::{Deserialize, Serialize};
+
+use relay_common::{glob_match, GlobOptions};
+
+use crate::protocol::{Event, Exception, Fingerprint, Frame};
+use crate::types::Annotated;
+
+lazy_static! {
+    /// Matches template variables in fingerprints, such as `{{ transaction }}`.
+    static ref VARIABLE_RE: Regex = Regex::new(r"\{\{\s*([\w.-]+)\s*\}\}").unwrap();
+}
+
+/// A matcher for a single attribute of an event.
+///
+/// Patterns are globs. Exception and frame matchers match if any exception or frame in the event
+/// matches, respectively.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "camelCase")]
+pub enum FingerprintMatcher {
+    /// Matches the type of an exception.
+    ExceptionType { pattern: String },
+    /// Matches the value of an exception.
+    ExceptionValue { pattern: String },
+    /// Matches the module of a frame.
+    Module { pattern: String },
+    /// Matches the function name of a frame.
+    Function { pattern: String },
+    /// Matches the absolute path or filename of a frame.
+    ///
+    /// Paths are matched case-insensitively with normalized separators, and `**` matches across
+    /// directories.
+    Path { pattern: String },
+    /// Matches the log message or the value of an exception.
+    Message { pattern: String },
+    /// Matches the value of the tag with the given key.
+    Tag { key: String, pattern: String },
+    /// Matches the level of the event.
+    Level { pattern: String },
+    /// Matches the name of the SDK, such as `sentry.python`.
+    Sdk { pattern: String },
+}
+
+fn exceptions(event: &Event) -> impl Iterator<Item = &Exception> {
+    event
+        .exceptions
+        .value()
+        .and_then(|exceptions| exceptions.values.value())
+        .into_iter()
+        .flatten()
+        .filter_map(Annotated::value)
+}
+
+fn frames(event: &Event) -> impl Iterator<Item = &Frame> {
+    let exception_stacktraces = exceptions(event).map(|exception| &exception.stacktrace);
+
+    let thread_stacktraces = event
+        .threads
+        .value()
+        .and_then(|threads| threads.values.value())
+        .into_iter()
+        .flatten()
+        .filter_map(Annotated::value)
+        .map(|thread| &thread.stacktrace);
+
+    exception_stacktraces
+        .chain(std::iter::once(&event.stacktrace))
+        .chain(thread_stacktraces)
+        .filter_map(Annotated::value)
+        .filter_map(|stacktrace| stacktrace.frames.value())
+        .flatten()
+        .filter_map(Annotated::value)
+}
+
+fn tag_value<'a>(event: &'a Event, key: &str) -> Option<&'a str> {
+    event
+        .tags
+        .value()?
+        .iter()
+        .filter_map(Annotated::value)
+        .find(|entry| entry.0.as_str() == Some(key))
+        .and_then(|entry| entry.1.as_str())
+}
+
+fn log_message(event: &Event) -> Option<&str> {
+    let logentry = event.logentry.value()?;
+    logentry
+        .formatted
+        .value()
+        .or_else(|| logentry.message.value())
+        .map(AsRef::as_ref)
+}
+
+/// Returns the exception that caused the event, which is the last one in the list.
+fn main_exception(event: &Event) -> Option<&Exception> {
+    exceptions(event).last()
+}
+
+/// Returns the frame at the top of the stack trace of the main exception.
+fn top_frame(event: &Event) -> Option<&Frame> {
+    let stacktrace = main_exception(event)?.stacktrace.value()?;
+    stacktrace.frames.value()?.last()?.value()
+}
+
+impl FingerprintMatcher {
+    fn matches(&self, event: &Event) -> bool {
+        let glob = |value: &str, pattern: &str| glob_match(value, pattern, GlobOptions::default());
+
+        match self {
+            FingerprintMatcher::ExceptionType { pattern } => exceptions(event)
+                .filter_map(|exception| exception.ty.as_str())
+                .any(|ty| glob(ty, pattern)),
+            FingerprintMatcher::ExceptionValue { pattern } => exceptions(event)
+                .filter_map(|exception| exception.value.as_str())
+                .any(|value| glob(value, pattern)),
+            FingerprintMatcher::Module { pattern } => frames(event)
+                .filter_map(|frame| frame.module.as_str())
+                .any(|module| glob(module, pattern)),
+            FingerprintMatcher::Function { pattern } => frames(event)
+                .filter_map(|frame| frame.function.as_str())
+                .any(|function| glob(function, pattern)),
+            FingerprintMatcher::Path { pattern } => {
+                let options = GlobOptions {
+                    double_star: true,
+                    case_insensitive: true,
+                    path_normalize: true,
+                    ..Default::default()
+                };
+
+                frames(event)
+                    .flat_map(|frame| vec![frame.abs_path.value(), frame.filename.value()])
+                    .flatten()
+                    .any(|path| glob_match(path.as_str(), pattern, options))
+            }
+            FingerprintMatcher::Message { pattern } => {
+                let exception_values =
+                    exceptions(event).filter_map(|exception| exception.value.as_str());
+
+                log_message(event)
+                    .into_iter()
+                    .chain(exception_values)
+                    .any(|message| glob(message, pattern))
+            }
+            FingerprintMatcher::Tag { key, pattern } => {
+                tag_value(event, key).map_or(false, |value| glob(value, pattern))
+            }
+            FingerprintMatcher::Level { pattern } => event
+                .level
+                .value()
+                .map_or(false, |level| glob(&level.to_string(), pattern)),
+            FingerprintMatcher::Sdk { pattern } => event
+                .client_sdk
+                .value()
+                .and_then(|sdk| sdk.name.as_str())
+                .map_or(false, |name| glob(name, pattern)),
+        }
+    }
+}
+
+/// A rule that assigns a fingerprint to events matching all of its matchers.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct FingerprintingRule {
+    /// Matchers that all need to match the event.
+    pub matchers: Vec<FingerprintMatcher>,
+
+    /// The resulting fingerprint.
+    ///
+    /// Values may contain variables such as `{{ transaction }}`, `{{ type }}`, `{{ value }}`,
+    /// `{{ function }}`, `{{ module }}`, `{{ message }}`, `{{ level }}` and `{{ tags.KEY }}`, which
+    /// are resolved from the event. `{{ default }}` is retained for the default grouping.
+    pub fingerprint: Vec<String>,
+}
+
+/// Resolves a single template variable from the event.
+///
+/// Returns `None` for unknown variables, which are retained verbatim.
+fn resolve_variable(event: &Event, variable: &str) -> Option<String> {
+    let value = match variable {
+        "transaction" => event.transaction.as_str().unwrap_or("<no-transaction>"),
+        "type" => main_exception(event)
+            .and_then(|exception| exception.ty.as_str())
+            .unwrap_or("<no-type>"),
+        "value" => main_exception(event)
+            .and_then(|exception| exception.value.as_str())
+            .unwrap_or("<no-value>"),
+        "function" => top_frame(event)
+            .and_then(|frame| frame.function.as_str())
+            .unwrap_or("<no-function>"),
+        "module" => top_frame(event)
+            .and_then(|frame| frame.module.as_str())
+            .unwrap_or("<no-module>"),
+        "message" => log_message(event).unwrap_or("<no-message>"),
+        "level" => {
+            return Some(
+                event
+                    .level
+                    .value()
+                    .map_or_else(|| "<no-level>".to_owned(), |level| level.to_string()),
+            )
+        }
+        _ if variable.starts_with("tags.") => {
+            let key = &variable["tags.".len()..];
+            return Some(match tag_value(event, key) {
+                Some(value) => value.to_owned(),
+                None => format!("<no-value-for-tag-{}>", key),
+            });
+        }
+        _ => return None,
+    };
+
+    Some(value.to_owned())
+}
+
+impl FingerprintingRule {
+    /// Returns `true` if all matchers of this rule match the event.
+    ///
+    /// A rule without matchers never matches.
+    pub fn matches(&self, event: &Event) -> bool {
+        !self.matchers.is_empty() && self.matchers.iter().all(|matcher| matcher.matches(event))
+    }
+
+    /// Resolves the fingerprint of this rule for the given event.
+    pub fn resolve_fingerprint(&self, event: &Event) -> Fingerprint {
+        let values = self.fingerprint.iter().map(|value| {
+            VARIABLE_RE
+                .replace_all(value, |captures: &Captures<'_>| {
+                    resolve_variable(event, &captures[1]).unwrap_or_else(|| captures[0].to_owned())
+                })
+                .into_owned()
+        });
+
+        Fingerprint::from(values.collect::<Vec<_>>())
+    }
+}
+
+/// An ordered list of fingerprinting rules. The first matching rule applies.
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct FingerprintingRules(pub Vec<FingerprintingRule>);
+
+impl FingerprintingRules {
+    /// Returns `true` if there are no rules.
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    /// Returns the index and the first rule matching the event.
+    pub fn match_event(&self, event: &Event) -> Option<(usize, &FingerprintingRule)> {
+        self.0
+            .iter()
+            .enumerate()
+            .find(|(_, rule)| rule.matches(event))
+    }
+
+    /// Applies the first matching rule to the event and returns its index.
+    ///
+    /// The fingerprint sent by the SDK is overridden and retained as original value in meta.
+    pub fn apply(&self, event: &mut Event) -> Option<usize> {
+        let (index, rule) = self.match_event(event)?;
+        let fingerprint = rule.resolve_fingerprint(event);
+
+        let original = event.fingerprint.value_mut().take();
+        event.fingerprint.set_value(Some(fingerprint));
+        if original.is_some() {
+            event.fingerprint.meta_mut().set_original_value(original);
+        }
+
+        Some(index)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rules(json: &str) -> FingerprintingRules {
+        serde_json::from_str(json).unwrap()
+    }
+
+    fn event(json: &str) -> Annotated<Event> {
+        Annotated::from_json(json).unwrap()
+    }
+
+    const EVENT: &str = r#"{
+        "transaction": "/api/users",
+        "level": "error",
+        "tags": {"server_name": "db-1"},
+        "sdk": {"name": "sentry.python", "version": "0.16.0"},
+        "exception": {
+            "values": [{
+                "type": "DatabaseTimeout",
+                "value": "query timed out after 30s",
+                "module": "app.db",
+                "stacktrace": {
+                    "frames": [
+                        {"function": "main", "module": "app", "abs_path": "/srv/app/main.py"},
+                        {"function": "execute", "module": "app.db", "abs_path": "/srv/app/db.py"}
+                    ]
+                }
+            }]
+        }
+    }"#;
+
+    #[test]
+    fn test_matchers() {
+        let event = event(EVENT);
+        let event = event.value().unwrap();
+
+        let matches = |json: &str| {
+            let matcher: FingerprintMatcher = serde_json::from_str(json).unwrap();
+            matcher.matches(event)
+        };
+
+        assert!(matches(
+            r#"{"type": "exceptionType", "pattern": "Database*"}"#
+        ));
+        assert!(!matches(
+            r#"{"type": "exceptionType", "pattern": "Timeout"}"#
+        ));
+        assert!(matches(
+            r#"{"type": "exceptionValue", "pattern": "*timed out*"}"#
+        ));
+        assert!(matches(r#"{"type": "module", "pattern": "app.db"}"#));
+        assert!(matches(r#"{"type": "function", "pattern": "exec*"}"#));
+        assert!(matches(r#"{"type": "path", "pattern": "**/APP/*.py"}"#));
+        assert!(matches(r#"{"type": "message", "pattern": "query *"}"#));
+        assert!(matches(
+            r#"{"type": "tag", "key": "server_name", "pattern": "db-*"}"#
+        ));
+        assert!(!matches(
+            r#"{"type": "tag", "key": "missing", "pattern": "*"}"#
+        ));
+        assert!(matches(r#"{"type": "level", "pattern": "error"}"#));
+        assert!(matches(r#"{"type": "sdk", "pattern": "sentry.*"}"#));
+    }
+
+    #[test]
+    fn test_apply_first_matching_rule() {
+        let rules = rules(
+            r#"[
+                {
+                    "matchers": [{"type": "exceptionType", "pattern": "ConnectionError"}],
+                    "fingerprint": ["connection-error"]
+                },
+                {
+                    "matchers": [
+                        {"type": "exceptionType", "pattern": "DatabaseTimeout"},
+                        {"type": "level", "pattern": "error"}
+                    ],
+                    "fingerprint": ["database-timeout", "{{ transaction }}", "{{ tags.server_name }}"]
+                }
+            ]"#,
+        );
+
+        let mut event = event(EVENT);
+        let event = event.value_mut().as_mut().unwrap();
+        assert_eq_dbg!(rules.apply(event), Some(1));
+
+        assert_eq_dbg!(
+            event.fingerprint.value().map(|f| f.as_slice()),
+            Some(
+                &[
+                    "database-timeout".to_owned(),
+                    "/api/users".to_owned(),
+                    "db-1".to_owned()
+                ][..]
+            )
+        );
+        assert!(event.fingerprint.meta().original_value().is_none());
+    }
+
+    #[test]
+    fn test_template_variables() {
+        let rule: FingerprintingRule = serde_json::from_str(
+            r#"{
+                "matchers": [{"type": "exceptionType", "pattern": "*"}],
+                "fingerprint": [
+                    "{{ default }}",
+                    "{{type}}: {{ function }} in {{ module }}",
+                    "{{ message }}",
+                    "{{ tags.missing }}",
+                    "{{ unknown }}"
+                ]
+            }"#,
+        )
+        .unwrap();
+
+        let event = event(EVENT);
+        let fingerprint = rule.resolve_fingerprint(event.value().unwrap());
+
+        assert_eq_dbg!(
+            fingerprint.as_slice(),
+            &[
+                "{{ default }}".to_owned(),
+                "DatabaseTimeout: execute in app.db".to_owned(),
+                "<no-message>".to_owned(),
+                "<no-value-for-tag-missing>".to_owned(),
+                "{{ unknown }}".to_owned(),
+            ][..]
+        );
+    }
+
+    #[test]
+    fn test_client_fingerprint_overridden() {
+        let rules = rules(
+            r#"[{
+                "matchers": [{"type": "sdk", "pattern": "sentry.python"}],
+                "fingerprint": ["python"]
+            }]"#,
+        );
+
+        let mut event = event(r#"{"sdk": {"name": "sentry.python"}, "fingerprint": ["client"]}"#);
+        let event = event.value_mut().as_mut().unwrap();
+
+        assert_eq_dbg!(rules.apply(event), Some(0));
+        assert_eq_dbg!(
+            event.fingerprint.value().map(|f| f.as_slice()),
+            Some(&["python".to_owned()][..])
+        );
+        assert!(event.fingerprint.meta().original_value().is_some());
+    }
+
+    #[test]
+    fn test_no_match() {
+        let rules = rules(
+            r#"[{
+                "matchers": [{"type": "exceptionType", "pattern": "ConnectionError"}],
+                "fingerprint": ["connection-error"]
+            }]"#,
+        );
+
+        let mut event = event(EVENT);
+        let event = event.value_mut().as_mut().unwrap();
+
+        assert_eq_dbg!(rules.apply(event), None);
+        assert!(event.fingerprint.value().is_none());
+    }
+}
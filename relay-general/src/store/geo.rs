@@ -1,7 +1,10 @@
 use std::fmt;
+use std::net::IpAddr;
 use std::path::Path;
 
-use crate::protocol::Geo;
+use serde::Deserialize;
+
+use crate::protocol::{Geo, NetworkContext};
 use crate::types::Annotated;
 
 #[cfg(feature = "mmap")]
@@ -13,8 +16,26 @@ type ReaderType = Vec<u8>;
 /// An error in the `GeoIpLookup`.
 pub type GeoIpError = maxminddb::MaxMindDBError;
 
+/// A record in a GeoLite2-ASN or GeoIP2-ISP database.
+#[derive(Debug, Deserialize)]
+struct AsnRecord {
+    autonomous_system_number: Option<u32>,
+    autonomous_system_organization: Option<String>,
+}
+
+fn open_reader(path: &Path) -> Result<maxminddb::Reader<ReaderType>, GeoIpError> {
+    #[cfg(feature = "mmap")]
+    let reader = maxminddb::Reader::open_mmap(path)?;
+    #[cfg(not(feature = "mmap"))]
+    let reader = maxminddb::Reader::open_readfile(path)?;
+    Ok(reader)
+}
+
 /// A geo ip lookup helper based on maxmind db files.
-pub struct GeoIpLookup(maxminddb::Reader<ReaderType>);
+pub struct GeoIpLookup {
+    city: maxminddb::Reader<ReaderType>,
+    asn: Option<maxminddb::Reader<ReaderType>>,
+}
 
 impl GeoIpLookup {
     /// Opens a maxminddb file by path.
@@ -22,11 +43,26 @@ impl GeoIpLookup {
     where
         P: AsRef<Path>,
     {
-        #[cfg(feature = "mmap")]
-        let reader = maxminddb::Reader::open_mmap(path)?;
-        #[cfg(not(feature = "mmap"))]
-        let reader = maxminddb::Reader::open_readfile(path)?;
-        Ok(GeoIpLookup(reader))
+        Ok(GeoIpLookup {
+            city: open_reader(path.as_ref())?,
+            asn: None,
+        })
+    }
+
+    /// Opens an additional ASN database for network lookups.
+    ///
+    /// The file must be in the format of the GeoLite2-ASN or GeoIP2-ISP databases.
+    pub fn with_asn_database<P>(mut self, path: P) -> Result<Self, GeoIpError>
+    where
+        P: AsRef<Path>,
+    {
+        self.asn = Some(open_reader(path.as_ref())?);
+        Ok(self)
+    }
+
+    /// Returns `true` if an ASN database is available for network lookups.
+    pub fn has_asn_database(&self) -> bool {
+        self.asn.is_some()
     }
 
     /// Looks up an IP address.
@@ -37,7 +73,7 @@ impl GeoIpLookup {
             Err(_) => return Ok(None),
         };
 
-        let city: maxminddb::geoip2::City = match self.0.lookup(ip_address) {
+        let city: maxminddb::geoip2::City = match self.city.lookup(ip_address) {
             Ok(x) => x,
             Err(GeoIpError::AddressNotFoundError(_)) => return Ok(None),
             Err(e) => return Err(e),
@@ -62,6 +98,37 @@ impl GeoIpLookup {
             ..Default::default()
         }))
     }
+
+    /// Looks up the autonomous system of an IP address.
+    ///
+    /// Returns `None` if no ASN database was opened or the address is not contained.
+    pub fn lookup_network(&self, ip_address: &str) -> Result<Option<NetworkContext>, GeoIpError> {
+        let reader = match self.asn {
+            Some(ref reader) => reader,
+            None => return Ok(None),
+        };
+
+        let ip_address: IpAddr = match ip_address.parse() {
+            Ok(x) => x,
+            Err(_) => return Ok(None),
+        };
+
+        let record: AsnRecord = match reader.lookup(ip_address) {
+            Ok(x) => x,
+            Err(GeoIpError::AddressNotFoundError(_)) => return Ok(None),
+            Err(e) => return Err(e),
+        };
+
+        if record.autonomous_system_number.is_none() {
+            return Ok(None);
+        }
+
+        Ok(Some(NetworkContext {
+            asn: Annotated::from(record.autonomous_system_number.map(u64::from)),
+            organization: Annotated::from(record.autonomous_system_organization),
+            ..Default::default()
+        }))
+    }
 }
 
 impl fmt::Debug for GeoIpLookup {
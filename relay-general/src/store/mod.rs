@@ -1,4 +1,5 @@
 //! Utility code for sentry's internal store.
+use std::collections::BTreeMap;
 use std::sync::Arc;
 
 use chrono::{DateTime, Utc};
@@ -10,15 +11,31 @@ use crate::protocol::{Event, IpAddr};
 use crate::types::{Meta, ProcessingResult};
 
 mod event_error;
+mod fingerprinting;
 mod geo;
 mod legacy;
 mod normalize;
 mod remove_other;
 mod schema;
 mod transactions;
+mod transform;
 mod trimming;
 
+pub use crate::store::fingerprinting::{
+    FingerprintMatcher, FingerprintingRule, FingerprintingRules,
+};
 pub use crate::store::geo::{GeoIpError, GeoIpLookup};
+pub use crate::store::normalize::breakdowns::{
+    BreakdownConfig, BreakdownsConfig, SpanOperationsConfig,
+};
+pub use crate::store::normalize::stacktrace_rules::{
+    FrameMatcher, StacktraceRule, StacktraceRules,
+};
+pub use crate::store::transactions::{SpanTreeIssue, TransactionNameConfig, TransactionNameRule};
+pub use crate::store::transform::{
+    FieldPath, InvalidFieldPathError, TransformOperation, TransformProcessor, TransformRule,
+    TransformRules,
+};
 
 /// The config for store.
 #[derive(Serialize, Deserialize, Debug, Default)]
@@ -46,6 +63,31 @@ pub struct StoreConfig {
     /// When `true` it adds context information extracted from the user agent
     pub normalize_user_agent: Option<bool>,
 
+    /// When `true`, mangled function names of native frames are demangled. Ignored without the
+    /// `demangle` feature.
+    pub demangle_symbols: Option<bool>,
+
+    /// When `true`, stack traces pasted into the log message or extra data of events without
+    /// exceptions are parsed into exceptions. `None` equals false.
+    pub parse_raw_stacktraces: Option<bool>,
+
+    /// Breakdowns to compute from the spans of transactions.
+    pub breakdowns: Option<BreakdownsConfig>,
+
+    /// When `true`, orphaned spans and spans in cycles are re-parented to the root span, and
+    /// spans exceeding their parents are clamped. `None` equals false.
+    pub repair_span_tree: Option<bool>,
+
+    /// Rules for replacing identifiers in URL-like transaction names.
+    pub transaction_names: Option<TransactionNameConfig>,
+
+    /// When `true`, query strings and fragments are split off the URLs of `http` and `navigation`
+    /// breadcrumbs, so that PII rules can scrub their parameters. `None` equals false.
+    pub split_breadcrumb_urls: Option<bool>,
+
+    /// Rules for marking stack trace frames as in-app and assigning frame categories.
+    pub stacktrace_rules: Option<StacktraceRules>,
+
     /// When the event has been sent, according to the SDK. Passed in via envelope headers.
     pub sent_at: Option<DateTime<Utc>>,
 }
@@ -54,6 +96,7 @@ pub struct StoreConfig {
 pub struct StoreProcessor<'a> {
     config: Arc<StoreConfig>,
     normalize: normalize::NormalizeProcessor<'a>,
+    span_tree_issues: BTreeMap<SpanTreeIssue, usize>,
 }
 
 impl<'a> StoreProcessor<'a> {
@@ -63,6 +106,7 @@ impl<'a> StoreProcessor<'a> {
         StoreProcessor {
             normalize: normalize::NormalizeProcessor::new(config.clone(), geoip_lookup),
             config,
+            span_tree_issues: BTreeMap::new(),
         }
     }
 
@@ -70,6 +114,14 @@ impl<'a> StoreProcessor<'a> {
     pub fn config(&self) -> &StoreConfig {
         &self.config
     }
+
+    /// Returns the problems found in the span tree of a processed transaction, along with their
+    /// number of occurrences.
+    pub fn span_tree_issues(&self) -> impl Iterator<Item = (SpanTreeIssue, usize)> + '_ {
+        self.span_tree_issues
+            .iter()
+            .map(|(issue, count)| (*issue, *count))
+    }
 }
 
 impl<'a> Processor for StoreProcessor<'a> {
@@ -90,8 +142,11 @@ impl<'a> Processor for StoreProcessor<'a> {
             // internally noops for non-transaction events
             // TODO: Parts of this processor should probably be a filter once Relay is store so we
             // can revert some changes to ProcessingAction
-            transactions::TransactionsProcessor::new(self.config.sent_at)
-                .process_event(event, meta, state)?;
+            let mut transactions = transactions::TransactionsProcessor::new(self.config.sent_at)
+                .repair_span_tree(self.config.repair_span_tree.unwrap_or(false))
+                .transaction_name_config(self.config.transaction_names.as_ref());
+            transactions.process_event(event, meta, state)?;
+            self.span_tree_issues = transactions.into_span_tree_issues();
         }
 
         if !is_renormalize {
@@ -11,9 +11,10 @@ use smallvec::SmallVec;
 
 use crate::processor::{MaxChars, ProcessValue, ProcessingState, Processor};
 use crate::protocol::{
-    AsPair, Breadcrumb, ClientSdkInfo, Context, DebugImage, Event, EventId, EventType, Exception,
-    Frame, HeaderName, HeaderValue, Headers, IpAddr, Level, LogEntry, Request, SpanStatus,
-    Stacktrace, Tags, TraceContext, User, INVALID_ENVIRONMENTS, INVALID_RELEASES, VALID_PLATFORMS,
+    AsPair, Breadcrumb, ClientSdkInfo, Context, Contexts, DebugImage, Event, EventId, EventType,
+    Exception, ExtraValue, Frame, HeaderName, HeaderValue, Headers, IpAddr, Level, LogEntry,
+    Mechanism, Request, SpanStatus, Stacktrace, Tags, Thread, TraceContext, User, Values,
+    INVALID_ENVIRONMENTS, INVALID_RELEASES, VALID_PLATFORMS,
 };
 use crate::store::{GeoIpLookup, StoreConfig};
 use crate::types::{
@@ -21,11 +22,15 @@ use crate::types::{
     ProcessingResult, Value,
 };
 
+mod breadcrumb;
+pub mod breakdowns;
 mod contexts;
 mod logentry;
 mod mechanism;
 mod request;
 mod stacktrace;
+pub mod stacktrace_rules;
+mod thread;
 
 #[cfg(feature = "uaparser")]
 mod user_agent;
@@ -39,6 +44,12 @@ fn validate_bounded_integer_field(value: u64) -> ProcessingResult {
     }
 }
 
+/// Maximum number of measurements retained on a transaction.
+const MAX_MEASUREMENTS: usize = 32;
+
+/// Maximum length of a measurement name.
+const MAX_MEASUREMENT_NAME_LENGTH: usize = 64;
+
 struct DedupCache(SmallVec<[u64; 16]>);
 
 impl DedupCache {
@@ -78,14 +89,23 @@ pub fn is_valid_release(release: &str) -> bool {
 pub struct NormalizeProcessor<'a> {
     config: Arc<StoreConfig>,
     geoip_lookup: Option<&'a GeoIpLookup>,
+    event_platform: Option<String>,
 }
 
 impl<'a> NormalizeProcessor<'a> {
     /// Creates a new normalization processor.
     pub fn new(config: Arc<StoreConfig>, geoip_lookup: Option<&'a GeoIpLookup>) -> Self {
+        #[cfg(not(feature = "demangle"))]
+        {
+            if config.demangle_symbols.unwrap_or(false) {
+                log::warn!("ignoring demangle_symbols, relay not built with demangle feature");
+            }
+        }
+
         NormalizeProcessor {
             config,
             geoip_lookup,
+            event_platform: None,
         }
     }
 
@@ -216,6 +236,76 @@ impl<'a> NormalizeProcessor<'a> {
         Ok(())
     }
 
+    /// Normalizes measurement names and units, and enforces limits on measurements.
+    ///
+    /// Names are lowercased. Measurements with invalid or duplicate names are removed, as well as
+    /// all measurements exceeding `MAX_MEASUREMENTS`.
+    fn normalize_measurements(&self, event: &mut Event) -> ProcessingResult {
+        lazy_static! {
+            static ref MEASUREMENT_NAME_RE: Regex = Regex::new(r"^[a-z0-9_.-]+\z").unwrap();
+        }
+
+        event.measurements.apply(|measurements, meta| {
+            let original_length = measurements.len();
+            let mut normalized = Object::new();
+
+            for (name, mut measurement) in mem::take(&mut measurements.0) {
+                let name = name.trim().to_lowercase();
+
+                if bytecount::num_chars(name.as_bytes()) > MAX_MEASUREMENT_NAME_LENGTH
+                    || !MEASUREMENT_NAME_RE.is_match(&name)
+                {
+                    meta.add_error(Error::with(ErrorKind::InvalidData, |error| {
+                        error.insert("reason", "invalid measurement name");
+                        error.insert("name", name);
+                    }));
+                    continue;
+                }
+
+                if normalized.contains_key(&name) {
+                    meta.add_error(Error::with(ErrorKind::InvalidData, |error| {
+                        error.insert("reason", "duplicate measurement name");
+                        error.insert("name", name);
+                    }));
+                    continue;
+                }
+
+                if normalized.len() >= MAX_MEASUREMENTS {
+                    meta.set_original_length(Some(original_length));
+                    break;
+                }
+
+                if let Some(measurement) = measurement.value_mut() {
+                    let unit = measurement
+                        .unit
+                        .value()
+                        .map(|unit| unit.trim().to_lowercase())
+                        .filter(|unit| !unit.is_empty());
+                    measurement.unit.set_value(unit);
+                }
+
+                normalized.insert(name, measurement);
+            }
+
+            measurements.0 = normalized;
+            Ok(())
+        })
+    }
+
+    /// Computes breakdowns of the transaction duration from spans.
+    fn normalize_breakdowns(&self, event: &mut Event) {
+        // Breakdowns are derived by Relay. Values sent by clients are discarded.
+        event.breakdowns = Annotated::empty();
+
+        if event.ty.value() != Some(&EventType::Transaction) {
+            return;
+        }
+
+        if let Some(ref config) = self.config.breakdowns {
+            event.breakdowns = Annotated::from(breakdowns::get_breakdowns(event, config));
+        }
+    }
+
     /// Infers the `EventType` from the event's interfaces.
     fn infer_event_type(&self, event: &Event) -> EventType {
         // The event type may be set explicitly when constructing the event items from specific
@@ -370,19 +460,128 @@ impl<'a> NormalizeProcessor<'a> {
                 // names (they can only occur on macOS).
                 //
                 // We also want to validate some other aspects of it.
-                for exception in exceptions {
+                for exception in exceptions.iter_mut() {
                     if let Some(exception) = exception.value_mut() {
                         if let Some(mechanism) = exception.mechanism.value_mut() {
                             mechanism::normalize_mechanism(mechanism, os_hint)?;
                         }
                     }
                 }
+
+                mechanism::normalize_exception_tree(exceptions);
             }
         }
 
         Ok(())
     }
 
+    /// Synthesizes exceptions from a stack trace pasted into the log message or extra data.
+    ///
+    /// Only runs if the event carries no exceptions or stack trace of its own.
+    fn parse_raw_stacktraces(&self, event: &mut Event) {
+        if !self.config.parse_raw_stacktraces.unwrap_or(false) {
+            return;
+        }
+
+        if !event.exceptions.value().is_empty() || !event.stacktrace.value().is_empty() {
+            return;
+        }
+
+        let logentry = event.logentry.value();
+        let messages = logentry
+            .and_then(|logentry| logentry.formatted.as_str())
+            .into_iter()
+            .chain(logentry.and_then(|logentry| logentry.message.as_str()))
+            .map(|message| ("logentry", message));
+
+        let extra = event
+            .extra
+            .value()
+            .into_iter()
+            .flatten()
+            .filter_map(|(_, value)| match value.value() {
+                Some(ExtraValue(Value::String(string))) => Some(("extra", string.as_str())),
+                _ => None,
+            });
+
+        let parsed = messages.chain(extra).find_map(|(source, text)| {
+            stacktrace::parse_stacktrace_string(text).map(|exceptions| (source, exceptions))
+        });
+
+        let (source, exceptions) = match parsed {
+            Some(parsed) => parsed,
+            None => return,
+        };
+
+        let values = exceptions
+            .into_iter()
+            .map(|mut exception| {
+                let mut data = Object::new();
+                data.insert("source".to_owned(), Annotated::new(source.into()));
+
+                exception.mechanism = Annotated::new(Mechanism {
+                    ty: Annotated::new("raw_stacktrace".to_owned()),
+                    data: Annotated::new(data),
+                    ..Mechanism::default()
+                });
+
+                Annotated::new(exception)
+            })
+            .collect();
+
+        event.exceptions = Annotated::new(Values::new(values));
+    }
+
+    fn demangle_symbols(&self, _stacktrace: &mut Stacktrace) {
+        #[cfg(feature = "demangle")]
+        {
+            if self.config.demangle_symbols.unwrap_or(false) {
+                let platform = self.event_platform.as_deref();
+                stacktrace::demangle_stacktrace(_stacktrace, platform);
+            }
+        }
+    }
+
+    /// Collapses runs of consecutive identical breadcrumbs.
+    fn normalize_breadcrumbs(&self, event: &mut Event) {
+        if let Some(breadcrumbs) = event.breadcrumbs.value_mut() {
+            if let Some(values) = breadcrumbs.values.value_mut() {
+                breadcrumb::dedupe_breadcrumbs(values);
+            }
+        }
+    }
+
+    /// Adds the autonomous system of the user's IP address as network context.
+    ///
+    /// Without a user IP address, the IP address of the client that sent the event is used.
+    /// A network context sent by the client is replaced, since it must not be able to evade
+    /// filters for autonomous systems.
+    fn normalize_network(&self, event: &mut Event) {
+        let geoip_lookup = match self.geoip_lookup {
+            Some(geoip_lookup) if geoip_lookup.has_asn_database() => geoip_lookup,
+            _ => return,
+        };
+
+        let ip_address = event
+            .user
+            .value()
+            .and_then(|user| user.ip_address.value())
+            .or_else(|| self.config.client_ip.as_ref());
+
+        let ip_address = match ip_address {
+            Some(ip_address) => ip_address,
+            None => return,
+        };
+
+        let network = match geoip_lookup.lookup_network(ip_address.as_str()) {
+            Ok(Some(network)) => network,
+            _ => return,
+        };
+
+        let contexts = event.contexts.get_or_insert_with(Contexts::new);
+        contexts.add(Context::Network(Box::new(network)));
+    }
+
     fn normalize_user_agent(&self, _event: &mut Event) {
         if self.config.normalize_user_agent.unwrap_or(false) {
             #[cfg(feature = "uaparser")]
@@ -407,6 +606,11 @@ impl<'a> Processor for NormalizeProcessor<'a> {
         // Insert IP addrs before recursing, since geo lookup depends on it.
         self.normalize_ip_addresses(event);
 
+        // Synthesize exceptions before recursing, so that they are normalized like any other.
+        self.parse_raw_stacktraces(event);
+
+        // Frames without a platform inherit the event's platform, e.g. for demangling.
+        self.event_platform = event.platform.value().cloned();
         event.process_child_values(self, state)?;
 
         // Override internal attributes, even if they were set in the payload
@@ -464,8 +668,12 @@ impl<'a> Processor for NormalizeProcessor<'a> {
         self.normalize_release_dist(event);
         self.normalize_timestamps(event)?;
         self.normalize_event_tags(event)?;
+        self.normalize_measurements(event)?;
+        self.normalize_breakdowns(event);
         self.normalize_exceptions(event)?;
+        self.normalize_breadcrumbs(event);
         self.normalize_user_agent(event);
+        self.normalize_network(event);
 
         Ok(())
     }
@@ -478,6 +686,9 @@ impl<'a> Processor for NormalizeProcessor<'a> {
     ) -> ProcessingResult {
         breadcrumb.process_child_values(self, state)?;
 
+        let split_urls = self.config.split_breadcrumb_urls.unwrap_or(false);
+        breadcrumb::normalize_breadcrumb(breadcrumb, split_urls)?;
+
         if breadcrumb.ty.value().is_empty() {
             breadcrumb.ty.set_value(Some("default".to_string()));
         }
@@ -489,6 +700,19 @@ impl<'a> Processor for NormalizeProcessor<'a> {
         Ok(())
     }
 
+    fn process_thread(
+        &mut self,
+        thread: &mut Thread,
+        _meta: &mut Meta,
+        state: &ProcessingState<'_>,
+    ) -> ProcessingResult {
+        thread.process_child_values(self, state)?;
+
+        thread::normalize_thread(thread);
+
+        Ok(())
+    }
+
     fn process_request(
         &mut self,
         request: &mut Request,
@@ -604,6 +828,19 @@ impl<'a> Processor for NormalizeProcessor<'a> {
             frame.symbol.set_value(None);
         }
 
+        lazy_static! {
+            static ref ADDR_MODE_RE: Regex = Regex::new(r"^(abs|rel:\d+|rel_func:\d+)$").unwrap();
+        }
+
+        frame.addr_mode.apply(|addr_mode, meta| {
+            if ADDR_MODE_RE.is_match(addr_mode) {
+                Ok(())
+            } else {
+                meta.add_error(Error::invalid("unsupported addressing mode"));
+                Err(ProcessingAction::DeleteValueSoft)
+            }
+        })?;
+
         if let Some(lines) = frame.pre_context.value_mut() {
             for line in lines.iter_mut() {
                 line.get_or_insert_with(String::new);
@@ -622,6 +859,10 @@ impl<'a> Processor for NormalizeProcessor<'a> {
             frame.context_line.set_value(Some(String::new()));
         }
 
+        if let Some(ref stacktrace_rules) = self.config.stacktrace_rules {
+            stacktrace_rules.apply(frame);
+        }
+
         Ok(())
     }
 
@@ -632,6 +873,7 @@ impl<'a> Processor for NormalizeProcessor<'a> {
         _state: &ProcessingState<'_>,
     ) -> ProcessingResult {
         stacktrace::process_stacktrace(&mut stacktrace.0, meta)?;
+        self.demangle_symbols(stacktrace);
         Ok(())
     }
 
@@ -1268,6 +1510,55 @@ fn test_frame_null_context_lines() {
     );
 }
 
+#[test]
+fn test_frame_addr_mode() {
+    let mut processor = NormalizeProcessor::default();
+
+    for addr_mode in &["abs", "rel:0", "rel_func:12"] {
+        let mut frame = Annotated::new(Frame {
+            addr_mode: Annotated::new(addr_mode.to_string()),
+            ..Frame::default()
+        });
+
+        process_value(&mut frame, &mut processor, ProcessingState::root()).unwrap();
+        assert_eq_dbg!(frame.value().unwrap().addr_mode.as_str(), Some(*addr_mode));
+    }
+
+    let mut frame = Annotated::new(Frame {
+        addr_mode: Annotated::new("rel:first".to_string()),
+        ..Frame::default()
+    });
+
+    process_value(&mut frame, &mut processor, ProcessingState::root()).unwrap();
+    let addr_mode = &frame.value().unwrap().addr_mode;
+    assert!(addr_mode.value().is_none());
+    assert!(addr_mode.meta().has_errors());
+}
+
+#[test]
+fn test_frame_stacktrace_rules() {
+    use crate::store::StacktraceRules;
+
+    let rules: StacktraceRules =
+        serde_json::from_str(r#"[{"match": {"module": "django.*"}, "inApp": false}]"#).unwrap();
+    let config = StoreConfig {
+        stacktrace_rules: Some(rules),
+        ..StoreConfig::default()
+    };
+    let mut processor = NormalizeProcessor::new(Arc::new(config), None);
+
+    let mut frame = Annotated::new(Frame {
+        module: Annotated::new("django.core.handlers".to_string()),
+        in_app: Annotated::new(true),
+        ..Frame::default()
+    });
+
+    process_value(&mut frame, &mut processor, ProcessingState::root()).unwrap();
+    let in_app = &frame.value().unwrap().in_app;
+    assert_eq_dbg!(in_app.value(), Some(&false));
+    assert_eq_dbg!(in_app.meta().iter_remarks().count(), 1);
+}
+
 #[test]
 fn test_too_long_tags() {
     let mut event = Annotated::new(Event {
@@ -1449,3 +1740,149 @@ fn test_grouping_config() {
     }
     "###);
 }
+
+#[test]
+fn test_parse_raw_stacktraces() {
+    let text = "Traceback (most recent call last):\n  \
+                File \"app/tasks.py\", line 7, in run\n    \
+                return int(value)\n\
+                ValueError: invalid literal for int() with base 10: 'x'";
+
+    let mut event = Annotated::new(Event {
+        logentry: Annotated::new(LogEntry {
+            formatted: Annotated::new(text.to_string().into()),
+            ..LogEntry::default()
+        }),
+        ..Event::default()
+    });
+
+    let config = StoreConfig {
+        parse_raw_stacktraces: Some(true),
+        ..StoreConfig::default()
+    };
+    let mut processor = NormalizeProcessor::new(Arc::new(config), None);
+    process_value(&mut event, &mut processor, ProcessingState::root()).unwrap();
+
+    let exceptions = event.value().unwrap().exceptions.value().unwrap();
+    let exception = exceptions.values.value().unwrap()[0].value().unwrap();
+    assert_eq_dbg!(exception.ty.as_str(), Some("ValueError"));
+
+    let mechanism = exception.mechanism.value().unwrap();
+    assert_eq_dbg!(mechanism.ty.as_str(), Some("raw_stacktrace"));
+    assert_eq_dbg!(
+        mechanism.data.value().unwrap().get("source"),
+        Some(&Annotated::new(Value::String("logentry".to_string())))
+    );
+
+    let frames = exception
+        .stacktrace
+        .value()
+        .unwrap()
+        .frames
+        .value()
+        .unwrap();
+    let frame = frames[0].value().unwrap();
+    assert_eq_dbg!(frame.function.as_str(), Some("run"));
+    assert_eq_dbg!(
+        frame.abs_path.value().map(|p| p.as_str()),
+        Some("app/tasks.py")
+    );
+}
+
+#[test]
+fn test_parse_raw_stacktraces_disabled() {
+    let mut event = Annotated::new(Event {
+        logentry: Annotated::new(LogEntry {
+            formatted: Annotated::new(
+                "java.lang.IllegalStateException: boom\n\tat com.example.App.main(App.java:8)"
+                    .to_string()
+                    .into(),
+            ),
+            ..LogEntry::default()
+        }),
+        ..Event::default()
+    });
+
+    let mut processor = NormalizeProcessor::default();
+    process_value(&mut event, &mut processor, ProcessingState::root()).unwrap();
+    assert!(event.value().unwrap().exceptions.value().is_none());
+}
+
+#[test]
+fn test_normalize_measurements() {
+    use crate::protocol::{Measurement, Measurements};
+
+    let mut measurements = Object::new();
+    measurements.insert(
+        "LCP".to_string(),
+        Annotated::new(Measurement {
+            value: Annotated::new(420.69),
+            unit: Annotated::new(" Millisecond ".to_string()),
+            ..Measurement::default()
+        }),
+    );
+    measurements.insert(
+        "lcp".to_string(),
+        Annotated::new(Measurement {
+            value: Annotated::new(1.0),
+            ..Measurement::default()
+        }),
+    );
+    measurements.insert(
+        "my measurement".to_string(),
+        Annotated::new(Measurement {
+            value: Annotated::new(2.0),
+            unit: Annotated::new(String::new()),
+            ..Measurement::default()
+        }),
+    );
+
+    let mut event = Annotated::new(Event {
+        ty: Annotated::new(EventType::Transaction),
+        measurements: Annotated::new(Measurements(measurements)),
+        ..Event::default()
+    });
+
+    let mut processor = NormalizeProcessor::default();
+    process_value(&mut event, &mut processor, ProcessingState::root()).unwrap();
+
+    let measurements = &event.value().unwrap().measurements;
+    let values = measurements.value().unwrap();
+    assert_eq_dbg!(values.keys().collect::<Vec<_>>(), vec!["lcp"]);
+
+    let lcp = values["lcp"].value().unwrap();
+    assert_eq_dbg!(lcp.value.value(), Some(&420.69));
+    assert_eq_dbg!(lcp.unit.as_str(), Some("millisecond"));
+    assert_eq_dbg!(measurements.meta().iter_errors().count(), 2);
+}
+
+#[test]
+fn test_measurements_limit() {
+    use crate::protocol::{Measurement, Measurements};
+
+    let measurements = (0..MAX_MEASUREMENTS + 3)
+        .map(|i| {
+            let measurement = Measurement {
+                value: Annotated::new(i as f64),
+                ..Measurement::default()
+            };
+            (format!("m{:02}", i), Annotated::new(measurement))
+        })
+        .collect();
+
+    let mut event = Annotated::new(Event {
+        ty: Annotated::new(EventType::Transaction),
+        measurements: Annotated::new(Measurements(measurements)),
+        ..Event::default()
+    });
+
+    let mut processor = NormalizeProcessor::default();
+    process_value(&mut event, &mut processor, ProcessingState::root()).unwrap();
+
+    let measurements = &event.value().unwrap().measurements;
+    assert_eq_dbg!(measurements.value().unwrap().len(), MAX_MEASUREMENTS);
+    assert_eq_dbg!(
+        measurements.meta().original_length(),
+        Some(MAX_MEASUREMENTS + 3)
+    );
+}
@@ -0,0 +1,525 @@
+use url::form_urlencoded;
+
+use crate::protocol::Breadcrumb;
+use crate::types::{
+    Annotated, Array, Error, Meta, Object, ProcessingAction, ProcessingResult, Remark, RemarkType,
+    Value,
+};
+
+use super::request::normalize_method;
+
+/// Rule id recorded in meta when the query string or fragment is split off a URL.
+const SPLIT_URL_RULE_ID: &str = "@split_url";
+
+/// Splits the query string and optionally the fragment off a URL.
+///
+/// Returns the query string and the fragment, if present and not empty. Only the removed parts
+/// are cut from the URL, which is otherwise retained as sent.
+fn split_url(url: &mut String, split_fragment: bool) -> (Option<String>, Option<String>) {
+    let mut fragment = None;
+    if split_fragment {
+        if let Some(index) = url.find('#') {
+            if index + 1 < url.len() {
+                fragment = Some(url[index + 1..].to_owned());
+                url.truncate(index);
+            }
+        }
+    }
+
+    // The query ends at the fragment, which remains part of the URL if it is not split off.
+    let end = url.find('#').unwrap_or_else(|| url.len());
+    let mut query = None;
+    if let Some(index) = url[..end].find('?') {
+        if index + 1 < end {
+            query = Some(url[index + 1..end].to_owned());
+            url.replace_range(index..end, "");
+        }
+    }
+
+    (query, fragment)
+}
+
+/// Parses a query string into an object, so that its parameters are subject to key-based PII rules.
+///
+/// If a parameter occurs multiple times, the first occurrence is retained.
+fn parse_query(query: &str) -> Value {
+    let mut object = Object::new();
+
+    for (key, value) in form_urlencoded::parse(query.as_bytes()) {
+        object
+            .entry(key.into_owned())
+            .or_insert_with(|| Annotated::new(Value::String(value.into_owned())));
+    }
+
+    Value::Object(object)
+}
+
+/// Moves the query string and fragment of the URL at `url_key` into dedicated keys.
+///
+/// Values already present in the dedicated keys take precedence, but the URL is stripped regardless.
+fn normalize_url(
+    data: &mut Object<Value>,
+    url_key: &str,
+    query_key: &str,
+    fragment_key: Option<&str>,
+) {
+    let url = match data.get_mut(url_key) {
+        Some(url) => url,
+        None => return,
+    };
+
+    let (query, fragment) = match url.value_mut() {
+        Some(Value::String(url_string)) => split_url(url_string, fragment_key.is_some()),
+        _ => return,
+    };
+
+    // The original URL is not retained in meta, since the query string may contain PII.
+    if query.is_some() || fragment.is_some() {
+        url.meta_mut()
+            .add_remark(Remark::new(RemarkType::Removed, SPLIT_URL_RULE_ID));
+    }
+
+    if let Some(query) = query {
+        data.entry(query_key.to_owned())
+            .or_insert_with(|| Annotated::new(parse_query(&query)));
+    }
+
+    if let (Some(fragment_key), Some(fragment)) = (fragment_key, fragment) {
+        data.entry(fragment_key.to_owned())
+            .or_insert_with(|| Annotated::new(Value::String(fragment)));
+    }
+}
+
+fn normalize_method_value(value: &mut Value, meta: &mut Meta) -> ProcessingResult {
+    match value {
+        Value::String(method) => normalize_method(method, meta),
+        _ => {
+            meta.add_error(Error::expected("an HTTP method"));
+            Err(ProcessingAction::DeleteValueSoft)
+        }
+    }
+}
+
+fn normalize_status_code(value: &mut Value, meta: &mut Meta) -> ProcessingResult {
+    let status_code = match value {
+        Value::U64(status_code) => Some(*status_code),
+        Value::I64(status_code) if *status_code >= 0 => Some(*status_code as u64),
+        Value::F64(status_code) if status_code.fract() == 0.0 && *status_code >= 0.0 => {
+            Some(*status_code as u64)
+        }
+        Value::String(status_code) => status_code.trim().parse().ok(),
+        _ => None,
+    };
+
+    match status_code {
+        Some(status_code) => {
+            *value = Value::U64(status_code);
+            Ok(())
+        }
+        None => {
+            meta.add_error(Error::expected("an integer status code"));
+            Err(ProcessingAction::DeleteValueSoft)
+        }
+    }
+}
+
+fn normalize_http_data(data: &mut Object<Value>, split_urls: bool) -> ProcessingResult {
+    if let Some(method) = data.get_mut("method") {
+        method.apply(normalize_method_value)?;
+    }
+
+    if let Some(status_code) = data.get_mut("status_code") {
+        status_code.apply(normalize_status_code)?;
+    }
+
+    if split_urls {
+        normalize_url(data, "url", "http.query", Some("http.fragment"));
+    }
+
+    Ok(())
+}
+
+fn normalize_navigation_data(data: &mut Object<Value>) {
+    // Fragments are retained, since single-page applications commonly use them for routing.
+    normalize_url(data, "from", "from.query", None);
+    normalize_url(data, "to", "to.query", None);
+}
+
+/// Normalizes the known data keys of `http` and `navigation` breadcrumbs.
+///
+/// If `split_urls` is set, query strings are split off URLs into objects. Since breadcrumb data is
+/// marked as PII, their parameters are then scrubbed by key-based rules such as `@password`.
+pub fn normalize_breadcrumb(breadcrumb: &mut Breadcrumb, split_urls: bool) -> ProcessingResult {
+    let data = match breadcrumb.data.value_mut() {
+        Some(data) => data,
+        None => return Ok(()),
+    };
+
+    match breadcrumb.ty.as_str() {
+        Some("http") => normalize_http_data(data, split_urls),
+        Some("navigation") => {
+            if split_urls {
+                normalize_navigation_data(data);
+            }
+            Ok(())
+        }
+        _ => Ok(()),
+    }
+}
+
+/// Returns `true` if two breadcrumbs only differ in their timestamps.
+fn is_repetition(previous: &Breadcrumb, next: &Breadcrumb) -> bool {
+    previous.ty == next.ty
+        && previous.category == next.category
+        && previous.level == next.level
+        && previous.message == next.message
+        && previous.data == next.data
+        // Additional fields such as `event_id`, which links breadcrumbs to other events.
+        && previous.other == next.other
+}
+
+/// Collapses runs of consecutive identical breadcrumbs into a single entry.
+///
+/// The retained entry keeps the timestamp of the first occurrence. The total number of occurrences
+/// is recorded in `repeat_count` and the timestamp of the last occurrence in `last_timestamp`.
+pub fn dedupe_breadcrumbs(breadcrumbs: &mut Array<Breadcrumb>) {
+    let mut deduped: Array<Breadcrumb> = Vec::with_capacity(breadcrumbs.len());
+
+    for annotated in breadcrumbs.drain(..) {
+        let previous = deduped.last_mut().and_then(Annotated::value_mut);
+
+        if let (Some(previous), Some(next)) = (previous, annotated.value()) {
+            if is_repetition(previous, next) {
+                let count = |breadcrumb: &Breadcrumb| breadcrumb.repeat_count.value().copied();
+                let repeat_count = count(previous)
+                    .unwrap_or(1)
+                    .saturating_add(count(next).unwrap_or(1));
+                previous.repeat_count.set_value(Some(repeat_count));
+
+                let last_timestamp = next
+                    .last_timestamp
+                    .value()
+                    .or_else(|| next.timestamp.value());
+                if let Some(last_timestamp) = last_timestamp {
+                    previous.last_timestamp.set_value(Some(*last_timestamp));
+                }
+
+                continue;
+            }
+        }
+
+        deduped.push(annotated);
+    }
+
+    *breadcrumbs = deduped;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use chrono::{TimeZone, Utc};
+
+    use crate::pii::{PiiConfig, PiiProcessor};
+    use crate::processor::{process_value, ProcessingState};
+
+    fn normalized(json: &str) -> Annotated<Breadcrumb> {
+        let mut breadcrumb = Annotated::<Breadcrumb>::from_json(json).unwrap();
+        breadcrumb
+            .apply(|breadcrumb, _| normalize_breadcrumb(breadcrumb, true))
+            .unwrap();
+        breadcrumb
+    }
+
+    fn data_value<'a>(breadcrumb: &'a Annotated<Breadcrumb>, key: &str) -> Option<&'a Value> {
+        let data = breadcrumb.value()?.data.value()?;
+        data.get(key)?.value()
+    }
+
+    #[test]
+    fn test_normalize_http_breadcrumb() {
+        let breadcrumb = normalized(
+            r#"{
+                "type": "http",
+                "data": {
+                    "url": "https://example.com/api/users?access_token=secret&page=2#top",
+                    "method": "post",
+                    "status_code": "404",
+                    "reason": "Not Found"
+                }
+            }"#,
+        );
+
+        assert_eq_dbg!(
+            data_value(&breadcrumb, "url"),
+            Some(&Value::String("https://example.com/api/users".to_owned()))
+        );
+
+        let url_meta = breadcrumb.value().unwrap().data.value().unwrap()["url"].meta();
+        assert_eq_dbg!(
+            url_meta.iter_remarks().collect::<Vec<_>>(),
+            vec![&Remark::new(RemarkType::Removed, SPLIT_URL_RULE_ID)]
+        );
+        assert_eq_dbg!(url_meta.original_value(), None);
+
+        assert_eq_dbg!(
+            data_value(&breadcrumb, "method"),
+            Some(&Value::String("POST".to_owned()))
+        );
+        assert_eq_dbg!(
+            data_value(&breadcrumb, "status_code"),
+            Some(&Value::U64(404))
+        );
+        assert_eq_dbg!(
+            data_value(&breadcrumb, "http.fragment"),
+            Some(&Value::String("top".to_owned()))
+        );
+
+        let mut query = Object::new();
+        query.insert(
+            "access_token".to_owned(),
+            Annotated::new(Value::String("secret".to_owned())),
+        );
+        query.insert(
+            "page".to_owned(),
+            Annotated::new(Value::String("2".to_owned())),
+        );
+        assert_eq_dbg!(
+            data_value(&breadcrumb, "http.query"),
+            Some(&Value::Object(query))
+        );
+    }
+
+    #[test]
+    fn test_invalid_http_breadcrumb_data() {
+        let breadcrumb = normalized(
+            r#"{
+                "type": "http",
+                "data": {
+                    "method": "not a method",
+                    "status_code": "teapot"
+                }
+            }"#,
+        );
+
+        let data = breadcrumb.value().unwrap().data.value().unwrap();
+        for key in &["method", "status_code"] {
+            let value = &data[*key];
+            assert!(value.value().is_none());
+            assert!(value.meta().has_errors());
+        }
+    }
+
+    #[test]
+    fn test_normalize_navigation_breadcrumb() {
+        let breadcrumb = normalized(
+            r##"{
+                "type": "navigation",
+                "data": {
+                    "from": "/login?redirect=%2Fhome",
+                    "to": "/home#/dashboard"
+                }
+            }"##,
+        );
+
+        assert_eq_dbg!(
+            data_value(&breadcrumb, "from"),
+            Some(&Value::String("/login".to_owned()))
+        );
+        assert!(data_value(&breadcrumb, "from.query").is_some());
+        assert_eq_dbg!(
+            data_value(&breadcrumb, "to"),
+            Some(&Value::String("/home#/dashboard".to_owned()))
+        );
+        assert!(data_value(&breadcrumb, "to.query").is_none());
+    }
+
+    #[test]
+    fn test_split_url() {
+        let mut url = "HTTPS://Example.com:443/a%20b?q=1#top".to_owned();
+        let (query, fragment) = split_url(&mut url, true);
+        assert_eq_str!(url, "HTTPS://Example.com:443/a%20b");
+        assert_eq_dbg!(query.as_deref(), Some("q=1"));
+        assert_eq_dbg!(fragment.as_deref(), Some("top"));
+
+        // URLs without a query are retained exactly as sent.
+        let mut url = "https://Example.com".to_owned();
+        assert_eq_dbg!(split_url(&mut url, true), (None, None));
+        assert_eq_str!(url, "https://Example.com");
+
+        let mut url = "/search?#results".to_owned();
+        assert_eq_dbg!(split_url(&mut url, false), (None, None));
+        assert_eq_str!(url, "/search?#results");
+    }
+
+    #[test]
+    fn test_urls_retained_without_pii_settings() {
+        let mut breadcrumb = Annotated::<Breadcrumb>::from_json(
+            r#"{
+                "type": "http",
+                "data": {"url": "https://example.com/api?page=2", "method": "get"}
+            }"#,
+        )
+        .unwrap();
+        breadcrumb
+            .apply(|breadcrumb, _| normalize_breadcrumb(breadcrumb, false))
+            .unwrap();
+
+        assert_eq_dbg!(
+            data_value(&breadcrumb, "url"),
+            Some(&Value::String("https://example.com/api?page=2".to_owned()))
+        );
+        assert_eq_dbg!(
+            data_value(&breadcrumb, "method"),
+            Some(&Value::String("GET".to_owned()))
+        );
+        assert!(data_value(&breadcrumb, "http.query").is_none());
+    }
+
+    #[test]
+    fn test_other_breadcrumbs_untouched() {
+        let breadcrumb = normalized(
+            r#"{
+                "type": "default",
+                "data": {"url": "https://example.com/?token=secret", "method": "get"}
+            }"#,
+        );
+
+        assert_eq_dbg!(
+            data_value(&breadcrumb, "url"),
+            Some(&Value::String(
+                "https://example.com/?token=secret".to_owned()
+            ))
+        );
+        assert_eq_dbg!(
+            data_value(&breadcrumb, "method"),
+            Some(&Value::String("get".to_owned()))
+        );
+    }
+
+    #[test]
+    fn test_query_secrets_scrubbed() {
+        let mut breadcrumb = normalized(
+            r#"{
+                "type": "http",
+                "data": {"url": "https://example.com/api?access_token=secret&page=2"}
+            }"#,
+        );
+
+        let config = PiiConfig::from_json(r#"{"applications": {"$string": ["@common"]}}"#).unwrap();
+        let compiled = config.compiled();
+        let mut processor = PiiProcessor::new(&compiled);
+        process_value(&mut breadcrumb, &mut processor, ProcessingState::root()).unwrap();
+
+        let query = match data_value(&breadcrumb, "http.query") {
+            Some(Value::Object(query)) => query,
+            other => panic!("unexpected query: {:?}", other),
+        };
+
+        assert_eq_dbg!(query.get("access_token").and_then(Annotated::value), None);
+        assert_eq_dbg!(
+            query.get("page").and_then(Annotated::value),
+            Some(&Value::String("2".to_owned()))
+        );
+    }
+
+    #[test]
+    fn test_dedupe_consecutive_breadcrumbs() {
+        let breadcrumb = |message: &str, second: u32| {
+            Annotated::new(Breadcrumb {
+                timestamp: Annotated::new(Utc.ymd(2000, 1, 1).and_hms(0, 0, second)),
+                ty: Annotated::new("default".to_owned()),
+                message: Annotated::new(message.to_owned()),
+                ..Breadcrumb::default()
+            })
+        };
+
+        let mut breadcrumbs = vec![
+            breadcrumb("polling", 1),
+            breadcrumb("polling", 2),
+            breadcrumb("polling", 3),
+            breadcrumb("clicked", 4),
+            breadcrumb("polling", 5),
+        ];
+
+        dedupe_breadcrumbs(&mut breadcrumbs);
+
+        let summary: Vec<_> = breadcrumbs
+            .iter()
+            .map(|breadcrumb| {
+                let breadcrumb = breadcrumb.value().unwrap();
+                (
+                    breadcrumb.message.as_str().unwrap(),
+                    breadcrumb.timestamp.value().map(|ts| ts.timestamp() % 60),
+                    breadcrumb
+                        .last_timestamp
+                        .value()
+                        .map(|ts| ts.timestamp() % 60),
+                    breadcrumb.repeat_count.value().copied(),
+                )
+            })
+            .collect();
+
+        assert_eq_dbg!(
+            summary,
+            vec![
+                ("polling", Some(1), Some(3), Some(3)),
+                ("clicked", Some(4), None, None),
+                ("polling", Some(5), None, None),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_dedupe_accumulates_repeat_counts() {
+        let json = r#"[
+        {"message": "tick", "repeat_count": 5, "timestamp": 946684800, "last_timestamp": 946684805},
+        {"message": "tick", "repeat_count": 2, "timestamp": 946684806, "last_timestamp": 946684807},
+        {"message": "tick", "data": {"n": 1}}
+    ]"#;
+
+        let mut breadcrumbs = Annotated::<Array<Breadcrumb>>::from_json(json).unwrap();
+        dedupe_breadcrumbs(breadcrumbs.value_mut().as_mut().unwrap());
+
+        let breadcrumbs = breadcrumbs.value().unwrap();
+        assert_eq_dbg!(breadcrumbs.len(), 2);
+
+        let first = breadcrumbs[0].value().unwrap();
+        assert_eq_dbg!(first.repeat_count.value(), Some(&7));
+        assert_eq_dbg!(
+            first.last_timestamp.value().map(|ts| ts.timestamp()),
+            Some(946_684_807)
+        );
+    }
+
+    #[test]
+    fn test_dedupe_saturates_repeat_counts() {
+        let json = r#"[
+        {"message": "tick", "repeat_count": 18446744073709551615},
+        {"message": "tick", "repeat_count": 2}
+    ]"#;
+
+        let mut breadcrumbs = Annotated::<Array<Breadcrumb>>::from_json(json).unwrap();
+        dedupe_breadcrumbs(breadcrumbs.value_mut().as_mut().unwrap());
+
+        let breadcrumbs = breadcrumbs.value().unwrap();
+        assert_eq_dbg!(breadcrumbs.len(), 1);
+        assert_eq_dbg!(
+            breadcrumbs[0].value().unwrap().repeat_count.value(),
+            Some(&u64::max_value())
+        );
+    }
+
+    #[test]
+    fn test_dedupe_keeps_different_event_ids() {
+        let json = r#"[
+        {"message": "sent", "event_id": "52df9022835246eeb317dbd739ccd059"},
+        {"message": "sent", "event_id": "52df9022835246eeb317dbd739ccd060"}
+    ]"#;
+
+        let mut breadcrumbs = Annotated::<Array<Breadcrumb>>::from_json(json).unwrap();
+        dedupe_breadcrumbs(breadcrumbs.value_mut().as_mut().unwrap());
+
+        assert_eq_dbg!(breadcrumbs.value().unwrap().len(), 2);
+    }
+}
@@ -0,0 +1,251 @@
+//! Computation of transaction breakdowns from spans.
+
+use std::collections::BTreeMap;
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::protocol::{Breakdowns, Event, Measurement, Measurements};
+use crate::types::{Annotated, Object};
+
+/// Configuration of a breakdown that groups spans by their operation.
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct SpanOperationsConfig {
+    /// Prefixes of span operations, each of which forms a group in the breakdown.
+    ///
+    /// A span is assigned to the first group whose prefix matches its `op`.
+    pub matches: Vec<String>,
+}
+
+/// Configuration of a single breakdown.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "camelCase")]
+pub enum BreakdownConfig {
+    /// Computes the time spent in groups of span operations.
+    SpanOperations(SpanOperationsConfig),
+}
+
+/// Configuration of all breakdowns computed for transactions, keyed by breakdown name.
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct BreakdownsConfig(pub BTreeMap<String, BreakdownConfig>);
+
+impl BreakdownsConfig {
+    /// Returns `true` if no breakdowns are configured.
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+}
+
+/// A time interval covered by a span.
+type Interval = (DateTime<Utc>, DateTime<Utc>);
+
+/// Returns the total duration in milliseconds covered by the union of the given intervals.
+fn union_duration_ms(mut intervals: Vec<Interval>) -> f64 {
+    intervals.sort_unstable();
+
+    let mut total = chrono::Duration::zero();
+    let mut current: Option<Interval> = None;
+
+    for (start, end) in intervals {
+        current = match current {
+            Some((current_start, current_end)) if start <= current_end => {
+                Some((current_start, current_end.max(end)))
+            }
+            Some((current_start, current_end)) => {
+                total = total + (current_end - current_start);
+                Some((start, end))
+            }
+            None => Some((start, end)),
+        };
+    }
+
+    if let Some((start, end)) = current {
+        total = total + (end - start);
+    }
+
+    match total.num_microseconds() {
+        Some(micros) => micros as f64 / 1000.0,
+        None => total.num_milliseconds() as f64,
+    }
+}
+
+fn duration_measurement(value: f64) -> Annotated<Measurement> {
+    Annotated::new(Measurement {
+        value: Annotated::new(value),
+        unit: Annotated::new("millisecond".to_owned()),
+        ..Measurement::default()
+    })
+}
+
+impl SpanOperationsConfig {
+    /// Computes the time spent in each group of span operations.
+    ///
+    /// Overlapping spans within a group are only counted once. Groups without spans are omitted.
+    /// The sum over all groups is added as `total.time`.
+    fn compute(&self, event: &Event) -> Option<Measurements> {
+        let spans = event.spans.value()?;
+        let mut intervals = BTreeMap::<&str, Vec<Interval>>::new();
+
+        for span in spans.iter().filter_map(Annotated::value) {
+            let op = match span.op.as_str() {
+                Some(op) => op,
+                None => continue,
+            };
+
+            let group = match self
+                .matches
+                .iter()
+                .find(|prefix| op.starts_with(prefix.as_str()))
+            {
+                Some(group) => group,
+                None => continue,
+            };
+
+            if let (Some(start), Some(end)) = (span.start_timestamp.value(), span.timestamp.value())
+            {
+                intervals
+                    .entry(group.as_str())
+                    .or_default()
+                    .push((*start, *end));
+            }
+        }
+
+        if intervals.is_empty() {
+            return None;
+        }
+
+        let mut measurements = Object::new();
+        let mut total = 0.0;
+
+        for (group, intervals) in intervals {
+            let duration = union_duration_ms(intervals);
+            total += duration;
+            measurements.insert(format!("ops.{}", group), duration_measurement(duration));
+        }
+
+        measurements.insert("total.time".to_owned(), duration_measurement(total));
+        Some(Measurements(measurements))
+    }
+}
+
+impl BreakdownConfig {
+    fn compute(&self, event: &Event) -> Option<Measurements> {
+        match self {
+            BreakdownConfig::SpanOperations(config) => config.compute(event),
+        }
+    }
+}
+
+/// Computes all configured breakdowns for a transaction event.
+pub fn get_breakdowns(event: &Event, config: &BreakdownsConfig) -> Option<Breakdowns> {
+    let mut breakdowns = Object::new();
+
+    for (name, breakdown) in &config.0 {
+        if let Some(measurements) = breakdown.compute(event) {
+            breakdowns.insert(name.clone(), Annotated::new(measurements));
+        }
+    }
+
+    if breakdowns.is_empty() {
+        None
+    } else {
+        Some(Breakdowns(breakdowns))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use chrono::TimeZone;
+
+    use crate::protocol::Span;
+
+    fn span(op: &str, start: u32, end: u32) -> Annotated<Span> {
+        Annotated::new(Span {
+            op: Annotated::new(op.to_owned()),
+            start_timestamp: Annotated::new(Utc.ymd(2020, 1, 1).and_hms(0, 0, start)),
+            timestamp: Annotated::new(Utc.ymd(2020, 1, 1).and_hms(0, 0, end)),
+            ..Span::default()
+        })
+    }
+
+    fn config() -> BreakdownsConfig {
+        let span_ops = SpanOperationsConfig {
+            matches: vec!["http".to_owned(), "db".to_owned()],
+        };
+
+        let mut config = BTreeMap::new();
+        config.insert(
+            "span_ops".to_owned(),
+            BreakdownConfig::SpanOperations(span_ops),
+        );
+        BreakdownsConfig(config)
+    }
+
+    #[test]
+    fn test_union_duration() {
+        assert_eq_dbg!(union_duration_ms(vec![]), 0.0);
+
+        let t = |secs| Utc.ymd(2020, 1, 1).and_hms(0, 0, secs);
+        let intervals = vec![(t(5), t(8)), (t(0), t(2)), (t(1), t(3)), (t(7), t(9))];
+        assert_eq_dbg!(union_duration_ms(intervals), 7000.0);
+    }
+
+    #[test]
+    fn test_span_operations_breakdown() {
+        let event = Event {
+            spans: Annotated::new(vec![
+                span("http.client", 0, 4),
+                span("http.client", 2, 6),
+                span("db.query", 10, 11),
+                span("db", 20, 22),
+                span("resource.script", 0, 30),
+            ]),
+            ..Event::default()
+        };
+
+        let breakdowns = get_breakdowns(&event, &config()).unwrap();
+        let span_ops = breakdowns["span_ops"].value().unwrap();
+
+        let values: Vec<_> = span_ops
+            .iter()
+            .map(|(name, measurement)| {
+                let measurement = measurement.value().unwrap();
+                (name.as_str(), *measurement.value.value().unwrap())
+            })
+            .collect();
+
+        assert_eq_dbg!(
+            values,
+            vec![
+                ("ops.db", 3000.0),
+                ("ops.http", 6000.0),
+                ("total.time", 9000.0)
+            ]
+        );
+    }
+
+    #[test]
+    fn test_no_matching_spans() {
+        let event = Event {
+            spans: Annotated::new(vec![span("resource.script", 0, 30)]),
+            ..Event::default()
+        };
+
+        assert!(get_breakdowns(&event, &config()).is_none());
+    }
+
+    #[test]
+    fn test_deserialize_config() {
+        let json = r#"{"span_ops": {"type": "spanOperations", "matches": ["http", "db"]}}"#;
+        let config: BreakdownsConfig = serde_json::from_str(json).unwrap();
+
+        match config.0.get("span_ops") {
+            Some(BreakdownConfig::SpanOperations(config)) => {
+                assert_eq_dbg!(config.matches, vec!["http", "db"]);
+            }
+            None => panic!("missing breakdown config"),
+        }
+    }
+}
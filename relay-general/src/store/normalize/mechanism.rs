@@ -1,8 +1,11 @@
-use crate::protocol::{Context, ContextInner, Event, Mechanism};
-use crate::types::{Annotated, Error, ProcessingAction, ProcessingResult};
+use std::collections::HashMap;
+use std::convert::TryFrom;
+
+use crate::protocol::{Context, ContextInner, Event, Exception, HResult, Mechanism};
+use crate::types::{Annotated, Array, Error, ProcessingAction, ProcessingResult};
 
 #[cfg(test)]
-use crate::protocol::{CError, MachException, MechanismMeta, PosixSignal};
+use crate::protocol::{CError, MachException, MechanismMeta, PosixSignal, WindowsException};
 
 fn get_errno_name(errno: i64, os_hint: OsHint) -> Option<&'static str> {
     Some(match os_hint {
@@ -553,6 +556,167 @@ fn get_mach_exception_name(number: i64) -> Option<&'static str> {
     })
 }
 
+fn get_windows_exception_name(code: u64) -> Option<&'static str> {
+    // Structured exception codes from winnt.h and ntstatus.h.
+    Some(match code {
+        0x4001_0005 => "DBG_CONTROL_C",        // Debugger received Ctrl+C
+        0x4001_0008 => "DBG_CONTROL_BREAK",    // Debugger received Ctrl+Break
+        0x406D_1388 => "MS_VC_EXCEPTION",      // Thread name exception
+        0x8000_0001 => "EXCEPTION_GUARD_PAGE", // Guard page violation
+        0x8000_0002 => "EXCEPTION_DATATYPE_MISALIGNMENT", // Misaligned data access
+        0x8000_0003 => "EXCEPTION_BREAKPOINT", // Breakpoint encountered
+        0x8000_0004 => "EXCEPTION_SINGLE_STEP", // Single step instruction
+        0x8000_0029 => "STATUS_UNWIND_CONSOLIDATE", // Frame consolidation executed
+        0xC000_0005 => "EXCEPTION_ACCESS_VIOLATION", // Invalid memory access
+        0xC000_0006 => "EXCEPTION_IN_PAGE_ERROR", // Page could not be loaded
+        0xC000_0008 => "EXCEPTION_INVALID_HANDLE", // Invalid handle
+        0xC000_000D => "STATUS_INVALID_PARAMETER", // Invalid parameter
+        0xC000_0017 => "STATUS_NO_MEMORY",     // Not enough virtual memory
+        0xC000_001D => "EXCEPTION_ILLEGAL_INSTRUCTION", // Invalid instruction
+        0xC000_0025 => "EXCEPTION_NONCONTINUABLE_EXCEPTION", // Continued non-continuable
+        0xC000_0026 => "EXCEPTION_INVALID_DISPOSITION", // Invalid exception disposition
+        0xC000_008C => "EXCEPTION_ARRAY_BOUNDS_EXCEEDED", // Array bounds exceeded
+        0xC000_008D => "EXCEPTION_FLT_DENORMAL_OPERAND", // Denormal floating point operand
+        0xC000_008E => "EXCEPTION_FLT_DIVIDE_BY_ZERO", // Floating point division by zero
+        0xC000_008F => "EXCEPTION_FLT_INEXACT_RESULT", // Inexact floating point result
+        0xC000_0090 => "EXCEPTION_FLT_INVALID_OPERATION", // Invalid floating point operation
+        0xC000_0091 => "EXCEPTION_FLT_OVERFLOW", // Floating point overflow
+        0xC000_0092 => "EXCEPTION_FLT_STACK_CHECK", // Floating point stack overflow
+        0xC000_0093 => "EXCEPTION_FLT_UNDERFLOW", // Floating point underflow
+        0xC000_0094 => "EXCEPTION_INT_DIVIDE_BY_ZERO", // Integer division by zero
+        0xC000_0095 => "EXCEPTION_INT_OVERFLOW", // Integer overflow
+        0xC000_0096 => "EXCEPTION_PRIV_INSTRUCTION", // Privileged instruction
+        0xC000_00FD => "EXCEPTION_STACK_OVERFLOW", // Stack overflow
+        0xC000_0135 => "STATUS_DLL_NOT_FOUND", // DLL could not be found
+        0xC000_0138 => "STATUS_ORDINAL_NOT_FOUND", // Ordinal could not be located
+        0xC000_0139 => "STATUS_ENTRYPOINT_NOT_FOUND", // Entry point could not be located
+        0xC000_013A => "STATUS_CONTROL_C_EXIT", // Terminated by Ctrl+C
+        0xC000_0142 => "STATUS_DLL_INIT_FAILED", // DLL initialization failed
+        0xC000_0194 => "EXCEPTION_POSSIBLE_DEADLOCK", // Possible deadlock condition
+        0xC000_02B4 => "STATUS_FLOAT_MULTIPLE_FAULTS", // Multiple floating point faults
+        0xC000_02B5 => "STATUS_FLOAT_MULTIPLE_TRAPS", // Multiple floating point traps
+        0xC000_0374 => "STATUS_HEAP_CORRUPTION", // Heap has been corrupted
+        0xC000_0409 => "STATUS_STACK_BUFFER_OVERRUN", // Stack buffer overrun or fail fast
+        0xC000_0417 => "STATUS_INVALID_CRUNTIME_PARAMETER", // Invalid CRT parameter
+        0xC000_041D => "STATUS_FATAL_USER_CALLBACK_EXCEPTION", // Exception in user callback
+        0xC000_0420 => "STATUS_ASSERTION_FAILURE", // Assertion failure
+        0xC000_0602 => "STATUS_FAIL_FAST_EXCEPTION", // Fail fast exception
+        0xE043_4352 => "CLR_EXCEPTION",        // Managed .NET exception
+        0xE06D_7363 => "CPP_EH_EXCEPTION",     // MSVC C++ exception
+        _ => return None,
+    })
+}
+
+fn get_hresult_name(code: u32) -> Option<&'static str> {
+    // Common COM error codes from winerror.h and .NET runtime codes from corerror.h.
+    Some(match code {
+        0x0000_0000 => "S_OK",                     // Operation successful
+        0x0000_0001 => "S_FALSE",                  // Operation successful, returned false
+        0x8000_4001 => "E_NOTIMPL",                // Not implemented
+        0x8000_4002 => "E_NOINTERFACE",            // No such interface supported
+        0x8000_4003 => "E_POINTER",                // Invalid pointer
+        0x8000_4004 => "E_ABORT",                  // Operation aborted
+        0x8000_4005 => "E_FAIL",                   // Unspecified error
+        0x8000_FFFF => "E_UNEXPECTED",             // Catastrophic failure
+        0x8001_0105 => "RPC_E_SERVERFAULT",        // Server threw an exception
+        0x8001_010E => "RPC_E_WRONG_THREAD",       // Interface marshalled for another thread
+        0x8002_0012 => "DISP_E_DIVBYZERO",         // Division by zero
+        0x8004_0154 => "REGDB_E_CLASSNOTREG",      // Class not registered
+        0x8004_01F0 => "CO_E_NOTINITIALIZED",      // CoInitialize has not been called
+        0x8007_0002 => "ERROR_FILE_NOT_FOUND",     // File not found
+        0x8007_0003 => "ERROR_PATH_NOT_FOUND",     // Path not found
+        0x8007_0005 => "E_ACCESSDENIED",           // General access denied error
+        0x8007_0006 => "E_HANDLE",                 // Invalid handle
+        0x8007_000B => "ERROR_BAD_FORMAT",         // Program in incorrect format
+        0x8007_000E => "E_OUTOFMEMORY",            // Out of memory
+        0x8007_0020 => "ERROR_SHARING_VIOLATION",  // File in use by another process
+        0x8007_0026 => "ERROR_HANDLE_EOF",         // Reached end of file
+        0x8007_0057 => "E_INVALIDARG",             // Invalid argument
+        0x8007_03E9 => "ERROR_STACK_OVERFLOW",     // Recursion too deep
+        0x8013_1500 => "COR_E_EXCEPTION",          // Base .NET exception
+        0x8013_1501 => "COR_E_SYSTEM",             // Base .NET system exception
+        0x8013_1502 => "COR_E_ARGUMENTOUTOFRANGE", // Argument out of range
+        0x8013_1505 => "COR_E_TIMEOUT",            // Operation timed out
+        0x8013_1508 => "COR_E_INDEXOUTOFRANGE",    // Index out of range
+        0x8013_1509 => "COR_E_INVALIDOPERATION",   // Invalid operation
+        0x8013_1515 => "COR_E_NOTSUPPORTED",       // Operation not supported
+        0x8013_1516 => "COR_E_OVERFLOW",           // Arithmetic overflow
+        0x8013_1537 => "COR_E_FORMAT",             // Invalid format
+        0x8013_153B => "COR_E_OPERATIONCANCELED",  // Operation canceled
+        0x8013_1577 => "COR_E_KEYNOTFOUND",        // Key not found
+        0x8013_1604 => "COR_E_TARGETINVOCATION",   // Exception thrown by invocation target
+        0x8013_1620 => "COR_E_IO",                 // I/O error
+        0x8013_1622 => "COR_E_OBJECTDISPOSED",     // Object already disposed
+        _ => return None,
+    })
+}
+
+fn get_hresult_facility_name(facility: u32) -> Option<&'static str> {
+    Some(match facility {
+        0 => "FACILITY_NULL",
+        1 => "FACILITY_RPC",
+        2 => "FACILITY_DISPATCH",
+        3 => "FACILITY_STORAGE",
+        4 => "FACILITY_ITF",
+        7 => "FACILITY_WIN32",
+        8 => "FACILITY_WINDOWS",
+        9 => "FACILITY_SECURITY",
+        10 => "FACILITY_CONTROL",
+        11 => "FACILITY_CERT",
+        12 => "FACILITY_INTERNET",
+        13 => "FACILITY_MEDIASERVER",
+        14 => "FACILITY_MSMQ",
+        15 => "FACILITY_SETUPAPI",
+        16 => "FACILITY_SCARD",
+        17 => "FACILITY_COMPLUS",
+        18 => "FACILITY_AAF",
+        19 => "FACILITY_URT",
+        20 => "FACILITY_ACS",
+        21 => "FACILITY_DPLAY",
+        22 => "FACILITY_UMI",
+        23 => "FACILITY_SXS",
+        24 => "FACILITY_WINDOWS_CE",
+        25 => "FACILITY_HTTP",
+        _ => return None,
+    })
+}
+
+/// Fills in names and decodes the facility and error code of an HRESULT.
+fn normalize_hresult(hresult: &mut HResult) {
+    // HRESULTs are 32-bit values, which .NET exposes as signed integers.
+    let code = match hresult.code.value() {
+        Some(&code) => u32::try_from(code).or_else(|_| i32::try_from(code).map(|code| code as u32)),
+        None => return,
+    };
+
+    let code = match code {
+        Ok(code) => code,
+        Err(_) => return,
+    };
+
+    let facility = (code >> 16) & 0x1fff;
+
+    if hresult.name.value().is_none() {
+        if let Some(name) = get_hresult_name(code) {
+            hresult.name = Annotated::new(name.to_owned());
+        }
+    }
+
+    if hresult.facility.value().is_none() {
+        hresult.facility = Annotated::new(facility.into());
+    }
+
+    if hresult.facility_name.value().is_none() {
+        if let Some(facility_name) = get_hresult_facility_name(facility) {
+            hresult.facility_name = Annotated::new(facility_name.to_owned());
+        }
+    }
+
+    if hresult.error_code.value().is_none() {
+        hresult.error_code = Annotated::new((code & 0xffff).into());
+    }
+}
+
 /// Internal utility trait to indicate the OS.
 #[derive(Debug, PartialEq, Clone, Copy)]
 pub enum OsHint {
@@ -640,6 +804,22 @@ pub fn normalize_mechanism(mechanism: &mut Mechanism, os_hint: Option<OsHint>) -
         }
     }
 
+    if os_hint == Some(OsHint::Windows) {
+        if let Some(windows_exception) = meta.windows_exception.value_mut() {
+            if let Some(code) = windows_exception.code.value() {
+                if windows_exception.name.value().is_none() {
+                    if let Some(name) = get_windows_exception_name(*code) {
+                        windows_exception.name = Annotated::new(name.to_owned());
+                    }
+                }
+            }
+        }
+    }
+
+    if let Some(hresult) = meta.hresult.value_mut() {
+        normalize_hresult(hresult);
+    }
+
     if let Some(mach_exception) = meta.mach_exception.value_mut() {
         if let Some(number) = mach_exception.ty.value() {
             if mach_exception.name.value().is_none() {
@@ -653,6 +833,90 @@ pub fn normalize_mechanism(mechanism: &mut Mechanism, os_hint: Option<OsHint>) -
     Ok(())
 }
 
+/// Removes an exception id with an error, retaining the original value in meta.
+fn remove_exception_id(id: &mut Annotated<u64>, reason: &str) {
+    let original = id.value_mut().take();
+    let meta = id.meta_mut();
+    meta.add_error(Error::invalid(reason));
+    meta.set_original_value(original);
+}
+
+fn get_mechanism_mut(exception: &mut Annotated<Exception>) -> Option<&mut Mechanism> {
+    exception
+        .value_mut()
+        .as_mut()?
+        .mechanism
+        .value_mut()
+        .as_mut()
+}
+
+/// Validates the tree formed by `exception_id` and `parent_id` of exception mechanisms.
+///
+/// Duplicate exception ids, parent ids referencing no exception and parent ids closing a cycle are
+/// removed with an error.
+pub fn normalize_exception_tree(exceptions: &mut Array<Exception>) {
+    let mut indexes = HashMap::new();
+    for (index, exception) in exceptions.iter_mut().enumerate() {
+        if let Some(mechanism) = get_mechanism_mut(exception) {
+            if let Some(&id) = mechanism.exception_id.value() {
+                if indexes.contains_key(&id) {
+                    remove_exception_id(&mut mechanism.exception_id, "duplicate exception_id");
+                } else {
+                    indexes.insert(id, index);
+                }
+            }
+        }
+    }
+
+    let mut parents = vec![None; exceptions.len()];
+    for (index, exception) in exceptions.iter_mut().enumerate() {
+        if let Some(mechanism) = get_mechanism_mut(exception) {
+            if let Some(parent_id) = mechanism.parent_id.value() {
+                match indexes.get(parent_id) {
+                    Some(&parent) => parents[index] = Some(parent),
+                    None => remove_exception_id(
+                        &mut mechanism.parent_id,
+                        "parent exception does not exist",
+                    ),
+                }
+            }
+        }
+    }
+
+    // Walk up the parents of every exception. Exceptions on the current walk are marked with
+    // `Some(false)`, exceptions that were fully checked with `Some(true)`.
+    let mut done = vec![None; exceptions.len()];
+    for start in 0..exceptions.len() {
+        let mut path = Vec::new();
+        let mut current = Some(start);
+
+        while let Some(index) = current {
+            match done[index] {
+                Some(true) => break,
+                Some(false) => {
+                    let closing = path[path.len() - 1];
+                    if let Some(mechanism) = get_mechanism_mut(&mut exceptions[closing]) {
+                        remove_exception_id(
+                            &mut mechanism.parent_id,
+                            "exception is part of a cycle",
+                        );
+                    }
+                    break;
+                }
+                None => {
+                    done[index] = Some(false);
+                    path.push(index);
+                    current = parents[index];
+                }
+            }
+        }
+
+        for index in path {
+            done[index] = Some(true);
+        }
+    }
+}
+
 #[test]
 fn test_normalize_missing() {
     let mut mechanism = Mechanism {
@@ -1028,3 +1292,174 @@ fn test_normalize_http_url() {
     }
     "###);
 }
+
+#[test]
+fn test_normalize_windows_exception() {
+    let mut mechanism = Mechanism {
+        ty: Annotated::new("minidump".to_string()),
+        meta: Annotated::new(MechanismMeta {
+            windows_exception: Annotated::new(WindowsException {
+                code: Annotated::new(0xC000_0005),
+                ..Default::default()
+            }),
+            ..Default::default()
+        }),
+        ..Default::default()
+    };
+
+    normalize_mechanism(&mut mechanism, Some(OsHint::Windows)).unwrap();
+
+    let windows_exception = mechanism
+        .meta
+        .value()
+        .unwrap()
+        .windows_exception
+        .value()
+        .unwrap();
+    assert_eq!(
+        windows_exception,
+        &WindowsException {
+            code: Annotated::new(0xC000_0005),
+            name: Annotated::new("EXCEPTION_ACCESS_VIOLATION".to_string()),
+        }
+    );
+}
+
+#[test]
+fn test_normalize_windows_exception_other_os() {
+    let mut mechanism = Mechanism {
+        ty: Annotated::new("minidump".to_string()),
+        meta: Annotated::new(MechanismMeta {
+            windows_exception: Annotated::new(WindowsException {
+                code: Annotated::new(0xC000_0005),
+                ..Default::default()
+            }),
+            ..Default::default()
+        }),
+        ..Default::default()
+    };
+
+    normalize_mechanism(&mut mechanism, Some(OsHint::Linux)).unwrap();
+
+    let windows_exception = mechanism
+        .meta
+        .value()
+        .unwrap()
+        .windows_exception
+        .value()
+        .unwrap();
+    assert_eq!(
+        windows_exception,
+        &WindowsException {
+            code: Annotated::new(0xC000_0005),
+            ..Default::default()
+        }
+    );
+}
+
+#[test]
+fn test_normalize_hresult() {
+    let mut mechanism = Mechanism {
+        ty: Annotated::new("generic".to_string()),
+        meta: Annotated::new(MechanismMeta {
+            hresult: Annotated::new(HResult {
+                // COR_E_INVALIDOPERATION as reported by .NET
+                code: Annotated::new(-2_146_233_079),
+                ..Default::default()
+            }),
+            ..Default::default()
+        }),
+        ..Default::default()
+    };
+
+    normalize_mechanism(&mut mechanism, None).unwrap();
+
+    let hresult = mechanism.meta.value().unwrap().hresult.value().unwrap();
+    assert_eq!(
+        hresult,
+        &HResult {
+            code: Annotated::new(-2_146_233_079),
+            name: Annotated::new("COR_E_INVALIDOPERATION".to_string()),
+            facility: Annotated::new(19),
+            facility_name: Annotated::new("FACILITY_URT".to_string()),
+            error_code: Annotated::new(0x1509),
+        }
+    );
+}
+
+#[test]
+fn test_normalize_hresult_invalid() {
+    let mut mechanism = Mechanism {
+        ty: Annotated::new("generic".to_string()),
+        meta: Annotated::new(MechanismMeta {
+            hresult: Annotated::new(HResult {
+                code: Annotated::new(1 << 40),
+                ..Default::default()
+            }),
+            ..Default::default()
+        }),
+        ..Default::default()
+    };
+
+    normalize_mechanism(&mut mechanism, None).unwrap();
+
+    let hresult = mechanism.meta.value().unwrap().hresult.value().unwrap();
+    assert_eq!(
+        hresult,
+        &HResult {
+            code: Annotated::new(1 << 40),
+            ..Default::default()
+        }
+    );
+}
+
+#[cfg(test)]
+fn exception_with_ids(exception_id: u64, parent_id: Option<u64>) -> Annotated<Exception> {
+    Annotated::new(Exception {
+        mechanism: Annotated::new(Mechanism {
+            ty: Annotated::new("chained".to_string()),
+            exception_id: Annotated::new(exception_id),
+            parent_id: Annotated::from(parent_id),
+            ..Default::default()
+        }),
+        ..Default::default()
+    })
+}
+
+#[test]
+fn test_normalize_exception_tree() {
+    let mut exceptions = vec![
+        exception_with_ids(0, None),
+        exception_with_ids(1, Some(0)),
+        exception_with_ids(1, Some(0)),
+        exception_with_ids(2, Some(7)),
+        exception_with_ids(3, Some(4)),
+        exception_with_ids(4, Some(3)),
+    ];
+
+    normalize_exception_tree(&mut exceptions);
+
+    let mechanism = |index: usize| {
+        exceptions[index]
+            .value()
+            .and_then(|exception| exception.mechanism.value())
+            .unwrap()
+    };
+
+    assert_eq_dbg!(mechanism(1).exception_id.value(), Some(&1));
+    assert_eq_dbg!(mechanism(1).parent_id.value(), Some(&0));
+
+    // duplicate id
+    assert_eq_dbg!(mechanism(2).exception_id.value(), None);
+    assert!(mechanism(2).exception_id.meta().has_errors());
+    assert_eq_dbg!(mechanism(2).parent_id.value(), Some(&0));
+
+    // dangling parent
+    assert_eq_dbg!(mechanism(3).parent_id.value(), None);
+    assert!(mechanism(3).parent_id.meta().has_errors());
+
+    // cycle, broken at the exception closing it
+    assert_eq_dbg!(mechanism(4).parent_id.value(), Some(&4));
+    assert_eq_dbg!(mechanism(5).parent_id.value(), None);
+    assert!(mechanism(5).parent_id.meta().has_errors());
+}
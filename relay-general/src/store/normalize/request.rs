@@ -76,7 +76,7 @@ fn normalize_url(request: &mut Request) {
     };
 }
 
-fn normalize_method(method: &mut String, meta: &mut Meta) -> ProcessingResult {
+pub fn normalize_method(method: &mut String, meta: &mut Meta) -> ProcessingResult {
     method.make_ascii_uppercase();
 
     if !meta.has_errors() && !METHOD_RE.is_match(&method) {
@@ -1,10 +1,363 @@
 use std::mem;
 
+use lazy_static::lazy_static;
+use regex::{Captures, Regex};
+#[cfg(feature = "demangle")]
+use symbolic::{
+    common::Name,
+    demangle::{Demangle, DemangleFormat, DemangleOptions},
+};
 use url::Url;
 
-use crate::protocol::{Frame, RawStacktrace};
+use crate::protocol::{Exception, Frame, JsonLenientString, RawStacktrace, Stacktrace};
 use crate::types::{Annotated, Empty, Meta, ProcessingResult};
 
+lazy_static! {
+    /// First line of every traceback printed by CPython.
+    static ref PYTHON_TRACEBACK_RE: Regex =
+        Regex::new(r#"^Traceback \(most recent call last\):\s*$"#).unwrap();
+
+    /// `File "app/foo.py", line 42, in handler`
+    static ref PYTHON_FRAME_RE: Regex =
+        Regex::new(r#"^\s+File "(?P<filename>[^"]+)", line (?P<lineno>\d+)(?:, in (?P<function>.+?))?\s*$"#).unwrap();
+
+    /// `at com.example.Foo.bar(Foo.java:42)`, optionally prefixed with a class loader or module.
+    static ref JAVA_FRAME_RE: Regex =
+        Regex::new(r#"^at (?:[^\s/()]+/+)?(?P<module>[\w$.]+)\.(?P<function>[\w$<>\-]+)\((?P<location>[^)]*)\)$"#).unwrap();
+
+    /// Location part of a Java frame: `Foo.java:42`, `Foo.java`, `Native Method`, `Unknown Source`.
+    static ref JAVA_LOCATION_RE: Regex =
+        Regex::new(r#"^(?:Native Method|Unknown Source|(?P<filename>[^\s:]+\.\w+)(?::(?P<lineno>\d+))?)$"#).unwrap();
+
+    /// `at Namespace.Type.Method(String arg) in C:\path\File.cs:line 42`
+    static ref DOTNET_FRAME_RE: Regex =
+        Regex::new(r#"^at (?P<module>[^\s(]+)\.(?P<function>\.?[^\s.(]+)\([^)]*\)(?: in (?P<filename>.+):line (?P<lineno>\d+))?$"#).unwrap();
+
+    /// V8 frames: `at handler (https://example.com/app.js:10:5)` or `at https://example.com/app.js:10:5`
+    static ref V8_FRAME_RE: Regex =
+        Regex::new(r#"^at (?:(?P<function>.+?) \()?(?P<abs_path>[^\s()]+?):(?P<lineno>\d+):(?P<colno>\d+)\)?$"#).unwrap();
+
+    /// SpiderMonkey and JavaScriptCore frames: `handler@https://example.com/app.js:10:5`
+    static ref GECKO_FRAME_RE: Regex =
+        Regex::new(r#"^(?P<function>[^@\s]*)@(?P<abs_path>\S+?):(?P<lineno>\d+):(?P<colno>\d+)$"#).unwrap();
+
+    /// Exception header line: `ValueError: invalid literal`, `java.lang.NullPointerException`.
+    static ref EXCEPTION_HEADER_RE: Regex =
+        Regex::new(r#"^(?P<type>[A-Za-z_$][\w$`]*(?:[.+$][\w$`]+)*)(?::\s*(?P<value>.*))?$"#).unwrap();
+}
+
+/// Lines separating inner exceptions in .NET's `Exception.ToString()`.
+const DOTNET_INNER_EXCEPTION_END: &str = "--- End of inner exception stack trace ---";
+
+/// An exception recovered from a textual stack trace.
+///
+/// Frames are stored in the order they appear in the text.
+#[derive(Debug, Default)]
+struct ParsedException {
+    ty: String,
+    value: Option<String>,
+    frames: Vec<Frame>,
+}
+
+impl ParsedException {
+    fn from_header(header: &str) -> Option<Self> {
+        let captures = EXCEPTION_HEADER_RE.captures(header.trim())?;
+        Some(ParsedException {
+            ty: captures["type"].to_owned(),
+            value: captures
+                .name("value")
+                .map(|value| value.as_str().trim().to_owned())
+                .filter(|value| !value.is_empty()),
+            frames: Vec::new(),
+        })
+    }
+
+    /// Appends a continuation line of a multi-line exception message.
+    fn append_value(&mut self, line: &str) {
+        match self.value {
+            Some(ref mut value) => {
+                value.push('\n');
+                value.push_str(line);
+            }
+            None => self.value = Some(line.to_owned()),
+        }
+    }
+
+    /// Converts into a protocol exception.
+    ///
+    /// If `split_module` is set, a qualified type such as `java.lang.IllegalStateException`
+    /// is split into `module` and `type`. `reverse` flips frames into the oldest-first order
+    /// that Sentry expects.
+    fn into_exception(self, split_module: bool, reverse: bool) -> Exception {
+        let (module, ty) = match self.ty.rfind('.') {
+            Some(index) if split_module => (
+                Annotated::new(self.ty[..index].to_owned()),
+                self.ty[index + 1..].to_owned(),
+            ),
+            _ => (Annotated::empty(), self.ty),
+        };
+
+        let mut frames = self.frames;
+        if reverse {
+            frames.reverse();
+        }
+
+        let stacktrace = RawStacktrace {
+            frames: Annotated::new(frames.into_iter().map(Annotated::new).collect()),
+            ..RawStacktrace::default()
+        };
+
+        Exception {
+            ty: Annotated::new(ty),
+            value: Annotated::from(self.value.map(JsonLenientString::from)),
+            module,
+            stacktrace: Annotated::new(Stacktrace::from(stacktrace)),
+            ..Exception::default()
+        }
+    }
+}
+
+fn capture_str(captures: &Captures<'_>, name: &str) -> Annotated<String> {
+    Annotated::from(captures.name(name).map(|m| m.as_str().to_owned()))
+}
+
+fn capture_u64(captures: &Captures<'_>, name: &str) -> Annotated<u64> {
+    Annotated::from(captures.name(name).and_then(|m| m.as_str().parse().ok()))
+}
+
+/// Parses a CPython traceback, including chained exceptions.
+///
+/// Chained tracebacks are printed cause first, which already is the order of exception values.
+fn parse_python(text: &str) -> Option<Vec<Exception>> {
+    let mut exceptions = Vec::new();
+    let mut frames: Option<Vec<Frame>> = None;
+
+    for line in text.lines() {
+        if PYTHON_TRACEBACK_RE.is_match(line) {
+            frames = Some(Vec::new());
+            continue;
+        }
+
+        let current = match frames {
+            Some(ref mut current) => current,
+            None => continue,
+        };
+
+        if let Some(captures) = PYTHON_FRAME_RE.captures(line) {
+            current.push(Frame {
+                function: capture_str(&captures, "function"),
+                filename: capture_str(&captures, "filename").map_value(Into::into),
+                lineno: capture_u64(&captures, "lineno"),
+                platform: Annotated::new("python".to_owned()),
+                ..Frame::default()
+            });
+        } else if line.starts_with(char::is_whitespace) {
+            // The source line following a frame, or markers pointing into it.
+            if let Some(frame) = current.last_mut() {
+                if frame.context_line.value().is_none() && !line.trim().is_empty() {
+                    frame.context_line = Annotated::new(line.trim().to_owned());
+                }
+            }
+        } else if let Some(mut exception) = ParsedException::from_header(line) {
+            exception.frames = frames.take().unwrap_or_default();
+            exceptions.push(exception.into_exception(true, false));
+        }
+    }
+
+    Some(exceptions).filter(|exceptions| !exceptions.is_empty())
+}
+
+/// Parses a Java stack trace as printed by `Throwable.printStackTrace`.
+///
+/// `Caused by:` sections are listed after their wrapping exception and are returned first.
+/// Suppressed exceptions are skipped.
+fn parse_java(text: &str) -> Option<Vec<Exception>> {
+    let mut exceptions: Vec<ParsedException> = Vec::new();
+    let mut suppressed = false;
+
+    for line in text.lines() {
+        let line = line.trim();
+
+        if line.starts_with("at ") {
+            let current = match exceptions.last_mut() {
+                Some(current) if !suppressed => current,
+                _ => continue,
+            };
+
+            let captures = JAVA_FRAME_RE.captures(line)?;
+            let location = JAVA_LOCATION_RE.captures(&captures["location"])?;
+            current.frames.push(Frame {
+                function: capture_str(&captures, "function"),
+                module: capture_str(&captures, "module"),
+                filename: capture_str(&location, "filename").map_value(Into::into),
+                lineno: capture_u64(&location, "lineno"),
+                platform: Annotated::new("java".to_owned()),
+                ..Frame::default()
+            });
+        } else if line.starts_with("...") && line.ends_with("more") {
+            continue;
+        } else if line.starts_with("Suppressed: ") {
+            suppressed = true;
+        } else if line.starts_with("Caused by: ") {
+            if exceptions.is_empty() {
+                return None;
+            }
+            suppressed = false;
+            exceptions.push(ParsedException::from_header(&line["Caused by: ".len()..])?);
+        } else if suppressed {
+            continue;
+        } else if let Some(current) = exceptions.last_mut().filter(|last| last.frames.is_empty()) {
+            // Exception messages may span multiple lines up to the first frame.
+            current.append_value(line);
+        } else if line.is_empty() {
+            continue;
+        } else {
+            let header = match line.find("\" ") {
+                Some(index) if line.starts_with("Exception in thread \"") => &line[index + 2..],
+                _ => line,
+            };
+
+            let exception = ParsedException::from_header(header)?;
+            // A second top-level stack trace; only the first one is parsed.
+            if !exceptions.is_empty() {
+                break;
+            }
+            exceptions.push(exception);
+        }
+    }
+
+    if exceptions.first()?.frames.is_empty() {
+        return None;
+    }
+
+    Some(
+        exceptions
+            .into_iter()
+            .rev()
+            .map(|exception| exception.into_exception(true, true))
+            .collect(),
+    )
+}
+
+/// Parses the output of .NET's `Exception.ToString()`.
+///
+/// Inner exceptions are listed in the header line separated by ` ---> `. Their frames come
+/// first, each section terminated by an "End of inner exception stack trace" marker.
+fn parse_dotnet(text: &str) -> Option<Vec<Exception>> {
+    let mut chain: Vec<ParsedException> = Vec::new();
+    let mut sections: Vec<Vec<Frame>> = vec![Vec::new()];
+
+    for line in text.lines() {
+        let line = line.trim();
+
+        if line.starts_with("at ") {
+            if chain.is_empty() {
+                continue;
+            }
+
+            let captures = DOTNET_FRAME_RE.captures(line)?;
+            sections.last_mut()?.push(Frame {
+                function: capture_str(&captures, "function"),
+                module: capture_str(&captures, "module"),
+                filename: capture_str(&captures, "filename").map_value(Into::into),
+                lineno: capture_u64(&captures, "lineno"),
+                platform: Annotated::new("csharp".to_owned()),
+                ..Frame::default()
+            });
+        } else if line == DOTNET_INNER_EXCEPTION_END {
+            sections.push(Vec::new());
+        } else if line.starts_with("---") {
+            // e.g. "--- End of stack trace from previous location ---"
+            continue;
+        } else if chain.is_empty() || sections.iter().all(Vec::is_empty) {
+            let line = line
+                .trim_start_matches("Unhandled exception. ")
+                .trim_start_matches("Unhandled Exception: ");
+
+            let parsed: Option<Vec<_>> = line
+                .split(" ---> ")
+                .map(ParsedException::from_header)
+                .collect();
+
+            if let Some(parsed) = parsed {
+                chain = parsed;
+            }
+        } else {
+            break;
+        }
+    }
+
+    if sections.iter().all(Vec::is_empty) {
+        return None;
+    }
+
+    // The innermost exception is printed last in the header but its frames come first.
+    Some(
+        chain
+            .into_iter()
+            .rev()
+            .zip(sections.into_iter().chain(std::iter::repeat_with(Vec::new)))
+            .map(|(mut exception, frames)| {
+                exception.frames = frames;
+                exception.into_exception(false, true)
+            })
+            .collect(),
+    )
+}
+
+/// Parses a JavaScript `Error.stack` string from V8, SpiderMonkey or JavaScriptCore.
+fn parse_javascript(text: &str) -> Option<Vec<Exception>> {
+    let mut exception: Option<ParsedException> = None;
+
+    for line in text.lines() {
+        let line = line.trim();
+
+        let captures = if line.starts_with("at ") {
+            V8_FRAME_RE.captures(line)
+        } else {
+            GECKO_FRAME_RE.captures(line)
+        };
+
+        if let Some(captures) = captures {
+            if let Some(ref mut current) = exception {
+                current.frames.push(Frame {
+                    function: capture_str(&captures, "function").and_then(|function| {
+                        let function = function.trim_start_matches("async ");
+                        Some(function.to_owned()).filter(|function| !function.is_empty())
+                    }),
+                    abs_path: capture_str(&captures, "abs_path").map_value(Into::into),
+                    lineno: capture_u64(&captures, "lineno"),
+                    colno: capture_u64(&captures, "colno"),
+                    platform: Annotated::new("javascript".to_owned()),
+                    ..Frame::default()
+                });
+            }
+        } else if line.starts_with("at ") {
+            // Frames without a location, such as `at new Promise (<anonymous>)`.
+            continue;
+        } else if exception.as_ref().map_or(true, |e| e.frames.is_empty()) {
+            exception = ParsedException::from_header(line.trim_start_matches("Uncaught "));
+        } else {
+            break;
+        }
+    }
+
+    let exception = exception.filter(|exception| !exception.frames.is_empty())?;
+    Some(vec![exception.into_exception(false, true)])
+}
+
+/// Detects a textual stack trace and converts it into exceptions.
+///
+/// Supported are Python tracebacks, Java stack traces, .NET `Exception.ToString()` output and
+/// JavaScript `Error.stack`. Exceptions are returned in the order of exception values, with
+/// the outermost exception last. Returns `None` if no format matched or no frames were found.
+pub fn parse_stacktrace_string(text: &str) -> Option<Vec<Exception>> {
+    parse_python(text)
+        .or_else(|| parse_java(text))
+        .or_else(|| parse_dotnet(text))
+        .or_else(|| parse_javascript(text))
+}
+
 fn is_url(filename: &str) -> bool {
     filename.starts_with("file:")
         || filename.starts_with("http:")
@@ -47,6 +400,53 @@ pub fn process_non_raw_frame(frame: &mut Frame, _meta: &mut Meta) -> ProcessingR
     Ok(())
 }
 
+/// Platforms of frames with mangled native function names.
+#[cfg(feature = "demangle")]
+const NATIVE_PLATFORMS: &[&str] = &["c", "cocoa", "native", "objc"];
+
+/// Demangles function names of native frames in the stack trace.
+///
+/// Frames without a platform inherit the platform of the event.
+#[cfg(feature = "demangle")]
+pub fn demangle_stacktrace(stacktrace: &mut RawStacktrace, event_platform: Option<&str>) {
+    if let Some(frames) = stacktrace.frames.value_mut() {
+        for frame in frames.iter_mut().filter_map(Annotated::value_mut) {
+            let platform = frame.platform.as_str().or(event_platform);
+            if platform.map_or(false, |platform| NATIVE_PLATFORMS.contains(&platform)) {
+                demangle_frame(frame);
+            }
+        }
+    }
+}
+
+/// Replaces a mangled `function` with its readable name.
+///
+/// The mangled name is retained in `symbol`. If there is no function name, the demangled
+/// `symbol` is used instead.
+#[cfg(feature = "demangle")]
+fn demangle_frame(frame: &mut Frame) {
+    let mangled = match frame.function.as_str().or_else(|| frame.symbol.as_str()) {
+        Some(mangled) => mangled.to_owned(),
+        None => return,
+    };
+
+    let options = DemangleOptions {
+        format: DemangleFormat::Short,
+        with_arguments: true,
+    };
+
+    let demangled = match Name::new(mangled.as_str()).demangle(options) {
+        Some(demangled) if demangled != mangled => demangled,
+        _ => return,
+    };
+
+    if frame.symbol.value().is_none() {
+        frame.symbol.set_value(Some(mangled));
+    }
+
+    frame.function.set_value(Some(demangled));
+}
+
 #[test]
 fn test_coerces_url_filenames() {
     let mut frame = Annotated::new(Frame {
@@ -152,3 +552,284 @@ fn test_is_url() {
     assert!(!is_url("data:,"));
     assert!(!is_url("blob:\x00"));
 }
+
+#[cfg(test)]
+fn frame_summary(exception: &Exception) -> Vec<(Option<&str>, Option<&str>, Option<u64>)> {
+    exception
+        .stacktrace
+        .value()
+        .and_then(|stacktrace| stacktrace.frames.value())
+        .into_iter()
+        .flatten()
+        .filter_map(Annotated::value)
+        .map(|frame| {
+            let path = frame.filename.value().or_else(|| frame.abs_path.value());
+            (
+                frame.function.as_str(),
+                path.map(|path| path.as_str()),
+                frame.lineno.value().copied(),
+            )
+        })
+        .collect()
+}
+
+#[test]
+fn test_parse_python_traceback() {
+    let text = r#"Failed to handle request
+Traceback (most recent call last):
+  File "app/db.py", line 12, in connect
+    return socket.connect(addr)
+ConnectionRefusedError: [Errno 111] Connection refused
+
+During handling of the above exception, another exception occurred:
+
+Traceback (most recent call last):
+  File "app/main.py", line 4, in <module>
+    main()
+  File "app/db.py", line 14, in connect
+    raise errors.DatabaseError("unavailable")
+app.errors.DatabaseError: unavailable"#;
+
+    let exceptions = parse_stacktrace_string(text).unwrap();
+    assert_eq!(exceptions.len(), 2);
+
+    assert_eq!(exceptions[0].ty.as_str(), Some("ConnectionRefusedError"));
+    assert_eq!(
+        exceptions[0].value.as_str(),
+        Some("[Errno 111] Connection refused")
+    );
+    assert_eq!(
+        frame_summary(&exceptions[0]),
+        vec![(Some("connect"), Some("app/db.py"), Some(12))]
+    );
+
+    let exception = &exceptions[1];
+    assert_eq!(exception.ty.as_str(), Some("DatabaseError"));
+    assert_eq!(exception.module.as_str(), Some("app.errors"));
+    assert_eq!(
+        frame_summary(exception),
+        vec![
+            (Some("<module>"), Some("app/main.py"), Some(4)),
+            (Some("connect"), Some("app/db.py"), Some(14)),
+        ]
+    );
+
+    let frames = exception
+        .stacktrace
+        .value()
+        .unwrap()
+        .frames
+        .value()
+        .unwrap();
+    let frame = frames[0].value().unwrap();
+    assert_eq!(frame.context_line.as_str(), Some("main()"));
+    assert_eq!(frame.platform.as_str(), Some("python"));
+}
+
+#[test]
+fn test_parse_java_stacktrace() {
+    let text = "Exception in thread \"main\" java.lang.IllegalStateException: Could not start\n\
+                \tat com.example.App.start(App.java:21)\n\
+                \tat com.example.App.main(App.java:8)\n\
+                Caused by: java.io.FileNotFoundException: config.yml\n\
+                \tat java.base/java.io.FileInputStream.open0(Native Method)\n\
+                \tat com.example.Config.load(Config.java:40)\n\
+                \t... 2 more";
+
+    let exceptions = parse_stacktrace_string(text).unwrap();
+    assert_eq!(exceptions.len(), 2);
+
+    assert_eq!(exceptions[0].ty.as_str(), Some("FileNotFoundException"));
+    assert_eq!(exceptions[0].module.as_str(), Some("java.io"));
+    assert_eq!(
+        frame_summary(&exceptions[0]),
+        vec![
+            (Some("load"), Some("Config.java"), Some(40)),
+            (Some("open0"), None, None),
+        ]
+    );
+
+    assert_eq!(exceptions[1].ty.as_str(), Some("IllegalStateException"));
+    assert_eq!(exceptions[1].value.as_str(), Some("Could not start"));
+    assert_eq!(
+        frame_summary(&exceptions[1]),
+        vec![
+            (Some("main"), Some("App.java"), Some(8)),
+            (Some("start"), Some("App.java"), Some(21)),
+        ]
+    );
+}
+
+#[test]
+fn test_parse_java_multiline_message() {
+    let text = "java.lang.IllegalArgumentException: Invalid config\n\
+                key: timeout\n\
+                \tat com.example.Config.load(Config.java:40)";
+
+    let exceptions = parse_stacktrace_string(text).unwrap();
+    assert_eq!(exceptions.len(), 1);
+    assert_eq!(
+        exceptions[0].value.as_str(),
+        Some("Invalid config\nkey: timeout")
+    );
+    assert_eq!(
+        frame_summary(&exceptions[0]),
+        vec![(Some("load"), Some("Config.java"), Some(40))]
+    );
+}
+
+#[test]
+fn test_parse_java_unknown_line() {
+    let text = "java.lang.IllegalStateException: Could not start\n\
+                \tat com.example.App.start(App.java:21)\n\
+                \tat <generated frame>";
+
+    assert!(parse_stacktrace_string(text).is_none());
+}
+
+#[test]
+fn test_parse_dotnet_stacktrace() {
+    let text = "System.InvalidOperationException: Checkout failed ---> System.ArgumentNullException: Value cannot be null.\n   \
+                at Shop.Cart.Add(Item item) in C:\\src\\Shop\\Cart.cs:line 17\n   \
+                --- End of inner exception stack trace ---\n   \
+                at Shop.Checkout..ctor(Cart cart) in C:\\src\\Shop\\Checkout.cs:line 30\n   \
+                at Shop.Program.Main()";
+
+    let exceptions = parse_stacktrace_string(text).unwrap();
+    assert_eq!(exceptions.len(), 2);
+
+    assert_eq!(
+        exceptions[0].ty.as_str(),
+        Some("System.ArgumentNullException")
+    );
+    assert_eq!(
+        frame_summary(&exceptions[0]),
+        vec![(Some("Add"), Some("C:\\src\\Shop\\Cart.cs"), Some(17))]
+    );
+
+    assert_eq!(
+        exceptions[1].ty.as_str(),
+        Some("System.InvalidOperationException")
+    );
+    assert_eq!(
+        frame_summary(&exceptions[1]),
+        vec![
+            (Some("Main"), None, None),
+            (Some(".ctor"), Some("C:\\src\\Shop\\Checkout.cs"), Some(30)),
+        ]
+    );
+}
+
+#[test]
+fn test_parse_javascript_stacktrace() {
+    let text = "TypeError: Cannot read property 'id' of undefined\n    \
+                at getUser (https://example.com/app.js:10:15)\n    \
+                at Array.map (<anonymous>)\n    \
+                at https://example.com/app.js:20:3";
+
+    let exceptions = parse_stacktrace_string(text).unwrap();
+    assert_eq!(exceptions.len(), 1);
+    assert_eq!(exceptions[0].ty.as_str(), Some("TypeError"));
+    assert_eq!(
+        frame_summary(&exceptions[0]),
+        vec![
+            (None, Some("https://example.com/app.js"), Some(20)),
+            (
+                Some("getUser"),
+                Some("https://example.com/app.js"),
+                Some(10)
+            ),
+        ]
+    );
+
+    let text =
+        "Error: timeout\nfetchData@https://example.com/app.js:5:9\n@https://example.com/app.js:1:1";
+    let exceptions = parse_stacktrace_string(text).unwrap();
+    assert_eq!(
+        frame_summary(&exceptions[0]),
+        vec![
+            (None, Some("https://example.com/app.js"), Some(1)),
+            (
+                Some("fetchData"),
+                Some("https://example.com/app.js"),
+                Some(5)
+            ),
+        ]
+    );
+}
+
+#[test]
+fn test_parse_stacktrace_string_no_match() {
+    assert!(parse_stacktrace_string("Order 42 could not be processed").is_none());
+    assert!(parse_stacktrace_string("ValueError: missing traceback").is_none());
+}
+
+#[test]
+#[cfg(feature = "demangle")]
+fn test_demangle_function() {
+    let mut frame = Frame {
+        function: Annotated::new("_ZN3foo3barEv".to_owned()),
+        ..Frame::default()
+    };
+
+    demangle_frame(&mut frame);
+    assert_eq!(frame.function.as_str(), Some("foo::bar()"));
+    assert_eq!(frame.symbol.as_str(), Some("_ZN3foo3barEv"));
+}
+
+#[test]
+#[cfg(feature = "demangle")]
+fn test_demangle_symbol_only() {
+    let mut frame = Frame {
+        symbol: Annotated::new("_ZN3foo3barEv".to_owned()),
+        ..Frame::default()
+    };
+
+    demangle_frame(&mut frame);
+    assert_eq!(frame.function.as_str(), Some("foo::bar()"));
+    assert_eq!(frame.symbol.as_str(), Some("_ZN3foo3barEv"));
+}
+
+#[test]
+#[cfg(feature = "demangle")]
+fn test_demangle_native_frames_only() {
+    let mut stacktrace = RawStacktrace {
+        frames: Annotated::new(vec![
+            Annotated::new(Frame {
+                function: Annotated::new("_ZN3foo3barEv".to_owned()),
+                ..Frame::default()
+            }),
+            Annotated::new(Frame {
+                function: Annotated::new("_ZN3foo3barEv".to_owned()),
+                platform: Annotated::new("python".to_owned()),
+                ..Frame::default()
+            }),
+        ]),
+        ..RawStacktrace::default()
+    };
+
+    demangle_stacktrace(&mut stacktrace, Some("native"));
+
+    let frames = stacktrace.frames.value().unwrap();
+    assert_eq!(
+        frames[0].value().unwrap().function.as_str(),
+        Some("foo::bar()")
+    );
+    assert_eq!(
+        frames[1].value().unwrap().function.as_str(),
+        Some("_ZN3foo3barEv")
+    );
+}
+
+#[test]
+#[cfg(feature = "demangle")]
+fn test_demangle_ignores_readable_names() {
+    let mut frame = Frame {
+        function: Annotated::new("main".to_owned()),
+        ..Frame::default()
+    };
+
+    demangle_frame(&mut frame);
+    assert_eq!(frame.function.as_str(), Some("main"));
+    assert_eq!(frame.symbol.value(), None);
+}
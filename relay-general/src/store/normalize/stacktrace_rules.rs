@@ -0,0 +1,277 @@
+//! Project-specific rules for classifying stack trace frames.
+use std::borrow::Cow;
+use std::fmt::Debug;
+
+use serde::{Deserialize, Serialize};
+
+use relay_common::{glob_match, GlobOptions};
+
+use crate::protocol::{Frame, FrameData};
+use crate::types::{Annotated, Remark, RemarkType, ToValue};
+
+/// Glob patterns matched against the attributes of a frame.
+///
+/// All given patterns need to match. A matcher without any patterns matches no frame.
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+pub struct FrameMatcher {
+    /// Pattern for the module, such as `django.*`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub module: Option<String>,
+    /// Pattern for the package, such as `**/libsystem_*.dylib`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub package: Option<String>,
+    /// Pattern for the filename.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub filename: Option<String>,
+    /// Pattern for the absolute path, such as `**/node_modules/**`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub abs_path: Option<String>,
+    /// Pattern for the function name.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub function: Option<String>,
+}
+
+fn path_options() -> GlobOptions {
+    GlobOptions {
+        double_star: true,
+        case_insensitive: true,
+        path_normalize: true,
+        ..Default::default()
+    }
+}
+
+/// Matches an optional pattern against a frame attribute. Missing patterns always match.
+fn matches_pattern(pattern: &Option<String>, value: Option<&str>, options: GlobOptions) -> bool {
+    match (pattern, value) {
+        (None, _) => true,
+        (Some(pattern), Some(value)) => glob_match(value, pattern, options),
+        (Some(_), None) => false,
+    }
+}
+
+impl FrameMatcher {
+    fn is_empty(&self) -> bool {
+        self.module.is_none()
+            && self.package.is_none()
+            && self.filename.is_none()
+            && self.abs_path.is_none()
+            && self.function.is_none()
+    }
+
+    /// Returns `true` if all patterns of this matcher match the frame.
+    pub fn matches(&self, frame: &Frame) -> bool {
+        let options = GlobOptions::default();
+        let filename = frame.filename.value().map(|path| path.as_str());
+        let abs_path = frame.abs_path.value().map(|path| path.as_str());
+
+        !self.is_empty()
+            && matches_pattern(&self.module, frame.module.as_str(), options)
+            && matches_pattern(&self.package, frame.package.as_str(), path_options())
+            && matches_pattern(&self.filename, filename, path_options())
+            && matches_pattern(&self.abs_path, abs_path, path_options())
+            && matches_pattern(&self.function, frame.function.as_str(), options)
+    }
+}
+
+/// A rule that classifies frames matching its matcher.
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+pub struct StacktraceRule {
+    /// Identifier of this rule, recorded in remarks of modified frames.
+    ///
+    /// Defaults to `@stacktrace:<index>` with the position of the rule in the list.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub id: Option<String>,
+
+    /// Patterns that select the frames this rule applies to.
+    #[serde(rename = "match")]
+    pub matcher: FrameMatcher,
+
+    /// Marks matching frames as in-app or system frames.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub in_app: Option<bool>,
+
+    /// Sets the category of matching frames, such as `framework`, `telemetry` or `ui`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub category: Option<String>,
+}
+
+/// Sets the value of a frame attribute and records a remark if the value changed.
+///
+/// If a value sent by the client is overwritten, it is retained as original value.
+fn set_with_remark<T>(annotated: &mut Annotated<T>, value: T, rule_id: &str)
+where
+    T: Clone + Debug + PartialEq + ToValue,
+{
+    let remark_type = match annotated.value() {
+        Some(previous) if *previous == value => return,
+        Some(_) => RemarkType::Substituted,
+        None => RemarkType::Annotated,
+    };
+
+    let previous = annotated.value_mut().replace(value);
+    let meta = annotated.meta_mut();
+    if previous.is_some() && meta.original_value().is_none() {
+        meta.set_original_value(previous);
+    }
+    meta.add_remark(Remark::new(remark_type, rule_id));
+}
+
+/// Records the in-app flag of a frame in `orig_in_app` before a rule changes it.
+///
+/// Sentry reads the flag from there to display and group by the original classification. If the
+/// flag has been changed before, the recorded value is retained.
+fn record_orig_in_app(frame: &mut Frame, in_app: bool) {
+    let orig_in_app = match frame.in_app.value() {
+        Some(&previous) if previous == in_app => return,
+        Some(&previous) => i64::from(previous),
+        None => -1,
+    };
+
+    let data = frame.data.get_or_insert_with(FrameData::default);
+    if data.orig_in_app.value().is_none() {
+        data.orig_in_app.set_value(Some(orig_in_app));
+    }
+}
+
+/// Ordered rules for in-app classification and categorization of frames.
+///
+/// All matching rules are applied in order, so later rules take precedence over earlier ones.
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct StacktraceRules(pub Vec<StacktraceRule>);
+
+impl StacktraceRules {
+    /// Returns `true` if there are no rules.
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    /// Applies all matching rules to a frame.
+    pub fn apply(&self, frame: &mut Frame) {
+        for (index, rule) in self.0.iter().enumerate() {
+            if !rule.matcher.matches(frame) {
+                continue;
+            }
+
+            let rule_id = match rule.id {
+                Some(ref id) => Cow::Borrowed(id.as_str()),
+                None => Cow::Owned(format!("@stacktrace:{}", index)),
+            };
+
+            if let Some(in_app) = rule.in_app {
+                record_orig_in_app(frame, in_app);
+                set_with_remark(&mut frame.in_app, in_app, &rule_id);
+            }
+
+            if let Some(ref category) = rule.category {
+                let data = frame.data.get_or_insert_with(FrameData::default);
+                set_with_remark(&mut data.category, category.clone(), &rule_id);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rules() -> StacktraceRules {
+        serde_json::from_str(
+            r#"[
+                {"match": {"module": "django.*"}, "inApp": false, "category": "framework"},
+                {"match": {"absPath": "**/node_modules/**"}, "inApp": false},
+                {"id": "sentry-sdk", "match": {"module": "sentry_sdk.*"}, "category": "telemetry"},
+                {"match": {"module": "django.views.*", "function": "render*"}, "category": "ui"}
+            ]"#,
+        )
+        .unwrap()
+    }
+
+    fn module_frame(module: &str, function: &str) -> Frame {
+        Frame {
+            module: Annotated::new(module.to_owned()),
+            function: Annotated::new(function.to_owned()),
+            ..Frame::default()
+        }
+    }
+
+    fn category(frame: &Frame) -> Option<&str> {
+        frame.data.value()?.category.as_str()
+    }
+
+    #[test]
+    fn test_frame_matcher() {
+        let matcher = FrameMatcher {
+            abs_path: Some("**/node_modules/**".to_owned()),
+            ..FrameMatcher::default()
+        };
+
+        let mut frame = Frame::default();
+        assert!(!matcher.matches(&frame));
+
+        frame.abs_path = Annotated::new("C:\\App\\Node_Modules\\react\\index.js".into());
+        assert!(matcher.matches(&frame));
+
+        assert!(!FrameMatcher::default().matches(&frame));
+    }
+
+    #[test]
+    fn test_apply_rules() {
+        let rules = rules();
+
+        let mut frame = module_frame("django.views.generic", "render_to_response");
+        rules.apply(&mut frame);
+        assert_eq_dbg!(frame.in_app.value(), Some(&false));
+        assert_eq_dbg!(category(&frame), Some("ui"));
+        assert_eq_dbg!(frame.data.value().unwrap().orig_in_app.value(), Some(&-1));
+
+        let mut frame = module_frame("sentry_sdk.hub", "capture_event");
+        rules.apply(&mut frame);
+        assert_eq_dbg!(frame.in_app.value(), None);
+        assert_eq_dbg!(category(&frame), Some("telemetry"));
+
+        let mut frame = module_frame("app.views", "index");
+        rules.apply(&mut frame);
+        assert_eq_dbg!(frame.in_app.value(), None);
+        assert!(frame.data.value().is_none());
+    }
+
+    #[test]
+    fn test_remarks() {
+        let rules = rules();
+
+        let mut frame = module_frame("django.views.generic", "render_to_response");
+        frame.in_app = Annotated::new(true);
+        rules.apply(&mut frame);
+
+        let meta = frame.in_app.meta();
+        let remarks: Vec<_> = meta.iter_remarks().collect();
+        assert_eq_dbg!(
+            remarks,
+            vec![&Remark::new(RemarkType::Substituted, "@stacktrace:0")]
+        );
+        assert!(meta.original_value().is_some());
+        assert_eq_dbg!(frame.data.value().unwrap().orig_in_app.value(), Some(&1));
+
+        // The category is set by the first rule and overwritten by the last rule.
+        let meta = frame.data.value().unwrap().category.meta();
+        let remarks: Vec<_> = meta.iter_remarks().collect();
+        assert_eq_dbg!(
+            remarks,
+            vec![
+                &Remark::new(RemarkType::Annotated, "@stacktrace:0"),
+                &Remark::new(RemarkType::Substituted, "@stacktrace:3"),
+            ]
+        );
+
+        let mut frame = module_frame("sentry_sdk.hub", "capture_event");
+        rules.apply(&mut frame);
+        let meta = frame.data.value().unwrap().category.meta();
+        let remarks: Vec<_> = meta.iter_remarks().collect();
+        assert_eq_dbg!(
+            remarks,
+            vec![&Remark::new(RemarkType::Annotated, "sentry-sdk")]
+        );
+    }
+}
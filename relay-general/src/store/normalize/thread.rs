@@ -0,0 +1,99 @@
+use crate::protocol::Thread;
+
+/// Maps a runtime-specific thread state to the corresponding JVM thread state.
+///
+/// State names are compared case-insensitively, ignoring separators, so that `TIMED_WAITING`,
+/// `TimedWaiting` and `timed-waiting` are all recognized. This covers JVM and Android ART thread
+/// dumps as well as goroutine states.
+fn canonical_thread_state(state: &str) -> Option<&'static str> {
+    let key: String = state
+        .chars()
+        .filter(|c| c.is_ascii_alphanumeric())
+        .map(|c| c.to_ascii_lowercase())
+        .collect();
+
+    Some(match key.as_str() {
+        "runnable" | "running" | "native" | "syscall" => "RUNNABLE",
+        "blocked" | "monitor" => "BLOCKED",
+        "waiting" | "wait" | "suspended" | "parked" | "chanreceive" | "chansend" | "select"
+        | "semacquire" => "WAITING",
+        "timedwaiting" | "sleeping" | "sleep" => "TIMED_WAITING",
+        "new" | "starting" | "initializing" => "NEW",
+        "terminated" | "zombie" | "dead" => "TERMINATED",
+        _ => return None,
+    })
+}
+
+/// Normalizes the state of a thread to one of the JVM thread states.
+///
+/// Unknown states are retained as sent by the client. The original value of a normalized state is
+/// retained in meta.
+pub fn normalize_thread(thread: &mut Thread) {
+    let state = match thread.state.as_str().and_then(canonical_thread_state) {
+        Some(state) => state,
+        None => return,
+    };
+
+    if thread.state.as_str() != Some(state) {
+        let original = thread.state.value().cloned();
+        thread.state.set_value(Some(state.to_string()));
+        thread.state.meta_mut().set_original_value(original);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::types::Annotated;
+
+    fn normalized(state: &str) -> Option<String> {
+        let mut thread = Thread {
+            state: Annotated::new(state.to_string()),
+            ..Thread::default()
+        };
+
+        normalize_thread(&mut thread);
+        thread.state.value().cloned()
+    }
+
+    #[test]
+    fn test_normalize_jvm_states() {
+        assert_eq_dbg!(normalized("RUNNABLE").as_deref(), Some("RUNNABLE"));
+        assert_eq_dbg!(
+            normalized("TIMED_WAITING").as_deref(),
+            Some("TIMED_WAITING")
+        );
+    }
+
+    #[test]
+    fn test_normalize_android_states() {
+        assert_eq_dbg!(normalized("Native").as_deref(), Some("RUNNABLE"));
+        assert_eq_dbg!(normalized("Monitor").as_deref(), Some("BLOCKED"));
+        assert_eq_dbg!(normalized("Sleeping").as_deref(), Some("TIMED_WAITING"));
+        assert_eq_dbg!(normalized("TimedWaiting").as_deref(), Some("TIMED_WAITING"));
+    }
+
+    #[test]
+    fn test_normalize_goroutine_states() {
+        assert_eq_dbg!(normalized("chan receive").as_deref(), Some("WAITING"));
+        assert_eq_dbg!(normalized("syscall").as_deref(), Some("RUNNABLE"));
+    }
+
+    #[test]
+    fn test_original_state_retained() {
+        let mut thread = Thread {
+            state: Annotated::new("Monitor".to_string()),
+            ..Thread::default()
+        };
+
+        normalize_thread(&mut thread);
+        assert_eq_dbg!(thread.state.as_str(), Some("BLOCKED"));
+        assert!(thread.state.meta().original_value().is_some());
+    }
+
+    #[test]
+    fn test_unknown_state_retained() {
+        assert_eq_dbg!(normalized("Hibernating").as_deref(), Some("Hibernating"));
+    }
+}
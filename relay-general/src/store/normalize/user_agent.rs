@@ -1,52 +1,33 @@
 //! Contains the user agent normalization code
 //!
-//! This module is responsible for taking the user agent string parsing it and filling in
-//! the browser, os and device information in the event.
+//! This module is responsible for taking the user agent string and client hints, parsing them
+//! and filling in the browser, os and device information in the event. Client hints are
+//! preferred over the user agent string, since Chromium no longer updates versions there.
 //!
 
 use std::fmt::Write;
 
 use crate::protocol::{BrowserContext, Context, Contexts, DeviceContext, Event, OsContext};
 use crate::types::Annotated;
-use crate::user_agent::{get_user_agent, parse_device, parse_os, parse_user_agent};
+use crate::user_agent::{
+    get_client_hints, get_user_agent, parse_device, parse_os, parse_user_agent, ClientHints,
+};
 
 pub fn normalize_user_agent(event: &mut Event) {
-    let user_agent = match get_user_agent(event) {
-        Some(ua) => ua,
-        None => return,
-    };
+    let client_hints = get_client_hints(event);
+    let user_agent = get_user_agent(event);
 
-    let device = parse_device(user_agent);
-    let os = parse_os(user_agent);
-    let ua = parse_user_agent(user_agent);
+    let browser = browser_from_client_hints(&client_hints)
+        .or_else(|| user_agent.and_then(browser_from_user_agent));
+    let device = device_from_client_hints(&client_hints)
+        .or_else(|| user_agent.and_then(device_from_user_agent));
+    let os =
+        os_from_client_hints(&client_hints).or_else(|| user_agent.and_then(os_from_user_agent));
 
-    if !is_known(ua.family.as_str())
-        && !is_known(device.family.as_str())
-        && !is_known(os.family.as_str())
-    {
+    if browser.is_none() && device.is_none() && os.is_none() {
         return; // no useful information in the user agent
     }
 
-    let contexts = event.contexts.get_or_insert_with(|| Contexts::new());
-
-    if is_known(ua.family.as_str()) && !contexts.contains_key(BrowserContext::default_key()) {
-        let version = get_version(&ua.major, &ua.minor, &ua.patch);
-        contexts.add(Context::Browser(Box::new(BrowserContext {
-            name: Annotated::from(ua.family),
-            version: Annotated::from(version),
-            ..BrowserContext::default()
-        })));
-    }
-
-    if is_known(device.family.as_str()) && !contexts.contains_key(DeviceContext::default_key()) {
-        contexts.add(Context::Device(Box::new(DeviceContext {
-            family: Annotated::from(device.family),
-            model: Annotated::from(device.model),
-            brand: Annotated::from(device.brand),
-            ..DeviceContext::default()
-        })));
-    }
-
     // avoid conflicts with OS-context sent by a serverside SDK by using `contexts.client_os`
     // instead of `contexts.os`. This is then preferred by the UI to show alongside device and
     // browser context.
@@ -58,19 +39,141 @@ pub fn normalize_user_agent(event: &mut Event) {
         _ => "client_os",
     };
 
-    if is_known(os.family.as_str()) && !contexts.contains_key(os_context_key) {
-        let version = get_version(&os.major, &os.minor, &os.patch);
-        contexts.insert(
-            os_context_key.to_owned(),
-            Annotated::new(
-                Context::Os(Box::new(OsContext {
-                    name: Annotated::from(os.family),
-                    version: Annotated::from(version),
-                    ..OsContext::default()
-                }))
-                .into(),
-            ),
-        );
+    let contexts = event.contexts.get_or_insert_with(|| Contexts::new());
+
+    if let Some(browser) = browser {
+        if !contexts.contains_key(BrowserContext::default_key()) {
+            contexts.add(Context::Browser(Box::new(browser)));
+        }
+    }
+
+    if let Some(device) = device {
+        if !contexts.contains_key(DeviceContext::default_key()) {
+            contexts.add(Context::Device(Box::new(device)));
+        }
+    }
+
+    if let Some(os) = os {
+        if !contexts.contains_key(os_context_key) {
+            contexts.insert(
+                os_context_key.to_owned(),
+                Annotated::new(Context::Os(Box::new(os)).into()),
+            );
+        }
+    }
+}
+
+fn browser_from_user_agent(user_agent: &str) -> Option<BrowserContext> {
+    let ua = parse_user_agent(user_agent);
+    if !is_known(ua.family.as_str()) {
+        return None;
+    }
+
+    let version = get_version(&ua.major, &ua.minor, &ua.patch);
+    Some(BrowserContext {
+        name: Annotated::from(ua.family),
+        version: Annotated::from(version),
+        ..BrowserContext::default()
+    })
+}
+
+fn device_from_user_agent(user_agent: &str) -> Option<DeviceContext> {
+    let device = parse_device(user_agent);
+    if !is_known(device.family.as_str()) {
+        return None;
+    }
+
+    Some(DeviceContext {
+        family: Annotated::from(device.family),
+        model: Annotated::from(device.model),
+        brand: Annotated::from(device.brand),
+        ..DeviceContext::default()
+    })
+}
+
+fn os_from_user_agent(user_agent: &str) -> Option<OsContext> {
+    let os = parse_os(user_agent);
+    if !is_known(os.family.as_str()) {
+        return None;
+    }
+
+    let version = get_version(&os.major, &os.minor, &os.patch);
+    Some(OsContext {
+        name: Annotated::from(os.family),
+        version: Annotated::from(version),
+        ..OsContext::default()
+    })
+}
+
+fn browser_from_client_hints(client_hints: &ClientHints<'_>) -> Option<BrowserContext> {
+    let (brand, version) = client_hints.browser()?;
+
+    // Use the same names as the user agent parser.
+    let name = match brand {
+        "Google Chrome" => "Chrome",
+        "Microsoft Edge" => "Edge",
+        brand => brand,
+    };
+
+    Some(BrowserContext {
+        name: Annotated::new(name.to_owned()),
+        version: Annotated::from(Some(version.to_owned()).filter(|v| !v.is_empty())),
+        ..BrowserContext::default()
+    })
+}
+
+fn device_from_client_hints(client_hints: &ClientHints<'_>) -> Option<DeviceContext> {
+    // Desktop browsers send an empty model.
+    if client_hints.mobile() == Some(false) {
+        return None;
+    }
+
+    let model = client_hints.model()?;
+    Some(DeviceContext {
+        family: Annotated::new(model.to_owned()),
+        model: Annotated::new(model.to_owned()),
+        ..DeviceContext::default()
+    })
+}
+
+fn os_from_client_hints(client_hints: &ClientHints<'_>) -> Option<OsContext> {
+    let platform = client_hints.platform()?;
+
+    // Use the same names as the user agent parser.
+    let name = match platform {
+        "macOS" => "Mac OS X",
+        platform => platform,
+    };
+
+    let version = client_hints.platform_version().map(|version| match name {
+        "Windows" => get_windows_version(version),
+        _ => version.to_owned(),
+    });
+
+    Some(OsContext {
+        name: Annotated::new(name.to_owned()),
+        version: Annotated::from(version),
+        ..OsContext::default()
+    })
+}
+
+/// Maps the Windows platform version from client hints to the marketing version.
+///
+/// See <https://learn.microsoft.com/en-us/microsoft-edge/web-platform/how-to-detect-win11>.
+fn get_windows_version(platform_version: &str) -> String {
+    let mut parts = platform_version
+        .split('.')
+        .map(|part| part.parse::<u32>().ok());
+    let major = parts.next().flatten();
+    let minor = parts.next().flatten();
+
+    match (major, minor) {
+        (Some(major), _) if major >= 13 => "11".to_owned(),
+        (Some(major), _) if major >= 1 => "10".to_owned(),
+        (Some(0), Some(1)) => "7".to_owned(),
+        (Some(0), Some(2)) => "8".to_owned(),
+        (Some(0), Some(3)) => "8.1".to_owned(),
+        _ => platform_version.to_owned(),
     }
 }
 
@@ -330,4 +433,97 @@ mod tests {
         }
         "###);
     }
+
+    fn get_event_with_client_hints(user_agent: &str, client_hints: &[(&str, &str)]) -> Event {
+        let mut event = testutils::get_event_with_user_agent(user_agent);
+        let request = event.request.value_mut().as_mut().unwrap();
+        let headers = request.headers.value_mut().as_mut().unwrap();
+
+        for (name, value) in client_hints {
+            headers.push(Annotated::new((
+                Annotated::new(name.to_string().into()),
+                Annotated::new(value.to_string().into()),
+            )));
+        }
+
+        event
+    }
+
+    #[test]
+    fn test_windows_version() {
+        assert_eq!(get_windows_version("15.0.0"), "11");
+        assert_eq!(get_windows_version("10.0.0"), "10");
+        assert_eq!(get_windows_version("0.3.0"), "8.1");
+        assert_eq!(get_windows_version("0.1.0"), "7");
+        assert_eq!(get_windows_version("unknown"), "unknown");
+    }
+
+    #[test]
+    fn test_client_hints_preferred() {
+        let ua = "Mozilla/5.0 (Windows NT 10.0; Win64; x64) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/110.0.0.0 Safari/537.36";
+        let mut event = get_event_with_client_hints(
+            ua,
+            &[
+                (
+                    "Sec-CH-UA",
+                    r#""Chromium";v="110", "Not A(Brand";v="24", "Google Chrome";v="110""#,
+                ),
+                ("Sec-CH-UA-Platform", r#""Windows""#),
+                ("Sec-CH-UA-Platform-Version", r#""15.0.0""#),
+                ("Sec-CH-UA-Mobile", "?0"),
+                ("Sec-CH-UA-Model", r#""""#),
+            ],
+        );
+
+        normalize_user_agent(&mut event);
+        assert_annotated_snapshot!(event.contexts, @r###"
+        {
+          "browser": {
+            "name": "Chrome",
+            "version": "110",
+            "type": "browser"
+          },
+          "client_os": {
+            "name": "Windows",
+            "version": "11",
+            "type": "os"
+          }
+        }
+        "###);
+    }
+
+    #[test]
+    fn test_client_hints_without_user_agent() {
+        let mut event = get_event_with_client_hints(
+            "a dont no",
+            &[
+                ("sec-ch-ua", r#""Not_A Brand";v="8", "Chromium";v="120""#),
+                ("sec-ch-ua-platform", r#""Android""#),
+                ("sec-ch-ua-platform-version", r#""13.0.0""#),
+                ("sec-ch-ua-mobile", "?1"),
+                ("sec-ch-ua-model", r#""Pixel 7""#),
+            ],
+        );
+
+        normalize_user_agent(&mut event);
+        assert_annotated_snapshot!(event.contexts, @r###"
+        {
+          "browser": {
+            "name": "Chromium",
+            "version": "120",
+            "type": "browser"
+          },
+          "client_os": {
+            "name": "Android",
+            "version": "13.0.0",
+            "type": "os"
+          },
+          "device": {
+            "family": "Pixel 7",
+            "model": "Pixel 7",
+            "type": "device"
+          }
+        }
+        "###);
+    }
 }
@@ -1,30 +1,474 @@
+use std::borrow::Cow;
+use std::collections::{BTreeMap, HashMap, VecDeque};
+
 use chrono::{DateTime, Duration, Utc};
+use lazy_static::lazy_static;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+
+use relay_common::{glob_match, GlobOptions};
 
 use crate::processor::{ProcessValue, ProcessingState, Processor};
-use crate::protocol::{Context, ContextInner, Event, EventType, Span};
-use crate::types::{Annotated, Meta, ProcessingAction, ProcessingResult, Timestamp};
+use crate::protocol::{Context, ContextInner, Event, EventType, Span, SpanId, TraceId};
+use crate::types::{
+    Annotated, Error, Meta, ProcessingAction, ProcessingResult, Remark, RemarkType, Timestamp,
+};
+
+/// Placeholder for segments of transaction names that have been replaced.
+const NAME_PLACEHOLDER: &str = "*";
+
+/// Rule id recorded in meta when identifiers are replaced in a transaction name.
+const IDENTIFIER_RULE_ID: &str = "@identifier";
+
+lazy_static! {
+    /// Matches the scheme and host of absolute URLs.
+    static ref URL_PREFIX_RE: Regex = Regex::new(r"^[a-zA-Z][a-zA-Z0-9+.-]*://[^/?#]*").unwrap();
+
+    /// Matches path segments that are integers, UUIDs or dates.
+    static ref IDENTIFIER_RE: Regex = Regex::new(
+        r#"(?x)
+        ^(?:
+            \d+ |
+            [0-9a-fA-F]{8}-?[0-9a-fA-F]{4}-?[0-9a-fA-F]{4}-?[0-9a-fA-F]{4}-?[0-9a-fA-F]{12} |
+            \d{4}-\d{2}-\d{2}(?:[T\ ][0-9:.]+(?:Z|[+-]\d{2}:?\d{2})?)?
+        )$
+    "#
+    )
+    .unwrap();
+
+    /// Matches path segments that are hexadecimal hashes.
+    static ref HASH_RE: Regex = Regex::new(r"^[0-9a-fA-F]{8,}$").unwrap();
+}
+
+/// A user-defined rule for transaction names.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct TransactionNameRule {
+    /// Glob pattern matched against the path of transaction names, such as `/orgs/*/projects/**`.
+    ///
+    /// `*` matches a single path segment and `**` any number of segments. Segments matched by a
+    /// single `*` are replaced with a placeholder.
+    pub pattern: String,
+}
+
+impl TransactionNameRule {
+    fn matches(&self, path: &str) -> bool {
+        let options = GlobOptions {
+            double_star: true,
+            ..Default::default()
+        };
+
+        glob_match(path, &self.pattern, options)
+    }
+
+    /// Replaces the path segments matched by a single `*` with a placeholder.
+    ///
+    /// Since `**` matches any number of segments, pattern segments before the first `**` are
+    /// aligned with the start of the path and segments after the last `**` with its end.
+    /// Segments between two `**` cannot be aligned and are left as-is.
+    fn replace_placeholders(&self, segments: &mut [Cow<'_, str>]) {
+        let patterns: Vec<&str> = self.pattern.split('/').collect();
+        let head_len = patterns
+            .iter()
+            .position(|pattern| *pattern == "**")
+            .unwrap_or_else(|| patterns.len());
+        let tail: &[&str] = match patterns.iter().rposition(|pattern| *pattern == "**") {
+            Some(index) => &patterns[index + 1..],
+            None => &[],
+        };
+
+        let tail_start = segments.len().saturating_sub(tail.len());
+        let aligned = patterns[..head_len]
+            .iter()
+            .zip(0..)
+            .chain(tail.iter().zip(tail_start..));
+
+        for (pattern, index) in aligned {
+            if *pattern == "*" {
+                if let Some(segment) = segments.get_mut(index) {
+                    *segment = Cow::Borrowed(NAME_PLACEHOLDER);
+                }
+            }
+        }
+    }
+}
+
+/// Configuration for normalizing URL-like transaction names.
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+pub struct TransactionNameConfig {
+    /// Replace path segments that look like identifiers, such as integers, UUIDs, hexadecimal
+    /// hashes and dates.
+    pub scrub_identifiers: bool,
+
+    /// Rules applied to transaction names in order. Only the first matching rule is applied.
+    pub rules: Vec<TransactionNameRule>,
+}
+
+fn is_identifier(segment: &str) -> bool {
+    IDENTIFIER_RE.is_match(segment)
+        || (HASH_RE.is_match(segment) && segment.bytes().any(|b| b.is_ascii_digit()))
+}
+
+/// Splits a URL-like transaction name into its scheme and host, and the remainder.
+///
+/// Returns `None` if the name is neither an absolute URL nor an absolute path.
+fn split_url(name: &str) -> Option<(&str, &str)> {
+    if name.starts_with('/') {
+        return Some(("", name));
+    }
+
+    let prefix = URL_PREFIX_RE.find(name)?;
+    Some(name.split_at(prefix.end()))
+}
+
+impl TransactionNameConfig {
+    /// Returns `true` if this config does not modify transaction names.
+    pub fn is_empty(&self) -> bool {
+        !self.scrub_identifiers && self.rules.is_empty()
+    }
+
+    /// Normalizes a URL-like transaction name.
+    ///
+    /// Returns the new name along with the ids of all applied rules, or `None` if the name remains
+    /// unchanged. Query strings and fragments are retained as-is.
+    fn normalize(&self, name: &str) -> Option<(String, Vec<&str>)> {
+        let (prefix, rest) = split_url(name)?;
+        let path_end = rest
+            .find(|c| c == '?' || c == '#')
+            .unwrap_or_else(|| rest.len());
+        let (path, suffix) = rest.split_at(path_end);
+
+        let mut segments: Vec<Cow<'_, str>> = path.split('/').map(Cow::Borrowed).collect();
+        let mut rule_ids = Vec::new();
+
+        if let Some(rule) = self.rules.iter().find(|rule| rule.matches(path)) {
+            rule.replace_placeholders(&mut segments);
+            rule_ids.push(rule.pattern.as_str());
+        }
+
+        if self.scrub_identifiers {
+            let mut scrubbed = false;
+
+            for segment in &mut segments {
+                if is_identifier(segment) {
+                    *segment = Cow::Borrowed(NAME_PLACEHOLDER);
+                    scrubbed = true;
+                }
+            }
+
+            if scrubbed {
+                rule_ids.push(IDENTIFIER_RULE_ID);
+            }
+        }
+
+        let normalized = format!("{}{}{}", prefix, segments.join("/"), suffix);
+        if normalized == name {
+            return None;
+        }
+
+        Some((normalized, rule_ids))
+    }
+}
+
+/// A problem found when validating the span tree of a transaction.
+#[derive(Clone, Copy, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub enum SpanTreeIssue {
+    /// The `parent_span_id` of a span does not reference a span in the transaction.
+    MissingParent,
+    /// Parent references of spans form a cycle.
+    Cycle,
+    /// The `trace_id` of a span differs from the trace context of the transaction.
+    TraceIdMismatch,
+    /// A span starts before or ends after its parent.
+    OutOfBounds,
+}
+
+impl SpanTreeIssue {
+    /// Returns the name of this issue used in metrics.
+    pub fn as_str(self) -> &'static str {
+        match self {
+            SpanTreeIssue::MissingParent => "missing_parent",
+            SpanTreeIssue::Cycle => "cycle",
+            SpanTreeIssue::TraceIdMismatch => "trace_id_mismatch",
+            SpanTreeIssue::OutOfBounds => "out_of_bounds",
+        }
+    }
+}
+
+/// The parent of a span in the span tree.
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Parent {
+    /// The root span of the transaction, described by the trace context.
+    Root,
+    /// Another span, given by its index.
+    Span(usize),
+    /// The span is not connected to the root span.
+    Detached,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum VisitState {
+    New,
+    Visiting,
+    Done,
+}
 
-pub struct TransactionsProcessor {
+/// Sets the parent of a span to the root span, retaining the original parent in meta.
+fn reparent_to_root(span: &mut Span, root_span_id: &SpanId) {
+    let original = span.parent_span_id.value().cloned();
+    span.parent_span_id.set_value(Some(root_span_id.clone()));
+    span.parent_span_id.meta_mut().set_original_value(original);
+}
+
+pub struct TransactionsProcessor<'a> {
     /// Timestamp when the client thinks it sent the event. None means that we default to
     /// event.timestamp.
     sent_at: Option<DateTime<Utc>>,
     client_clock_drift: Option<Duration>,
 
+    /// Re-parent orphaned spans to the root span and clamp spans into their parents.
+    repair_span_tree: bool,
+    span_tree_issues: BTreeMap<SpanTreeIssue, usize>,
+
+    /// Rules for normalizing URL-like transaction names.
+    name_config: Option<&'a TransactionNameConfig>,
+
     // This is an attribute so we can mock it in testing.
     now: DateTime<Utc>,
 }
 
-impl TransactionsProcessor {
+impl<'a> TransactionsProcessor<'a> {
     pub fn new(sent_at: Option<DateTime<Utc>>) -> Self {
         TransactionsProcessor {
             sent_at,
             client_clock_drift: None,
+            repair_span_tree: false,
+            span_tree_issues: BTreeMap::new(),
+            name_config: None,
             now: Utc::now(),
         }
     }
+
+    /// Sets the configuration for normalizing URL-like transaction names.
+    pub fn transaction_name_config(mut self, config: Option<&'a TransactionNameConfig>) -> Self {
+        self.name_config = config;
+        self
+    }
+
+    /// Enables repairing of the span tree.
+    ///
+    /// Spans with missing parents or in cycles are re-parented to the root span, and timestamps of
+    /// spans exceeding their parents are clamped. Original values are retained in meta.
+    pub fn repair_span_tree(mut self, repair: bool) -> Self {
+        self.repair_span_tree = repair;
+        self
+    }
+
+    /// Returns the span tree issues found in the processed event, along with their number.
+    pub fn into_span_tree_issues(self) -> BTreeMap<SpanTreeIssue, usize> {
+        self.span_tree_issues
+    }
+
+    /// Replaces identifiers in URL-like transaction names, retaining the original name in meta.
+    fn normalize_transaction_name(&self, event: &mut Event) {
+        let config = match self.name_config {
+            Some(config) => config,
+            None => return,
+        };
+
+        let (normalized, rule_ids) =
+            match event.transaction.as_str().and_then(|n| config.normalize(n)) {
+                Some(result) => result,
+                None => return,
+            };
+
+        let original = event.transaction.value_mut().replace(normalized);
+        let meta = event.transaction.meta_mut();
+        meta.set_original_value(original);
+        for rule_id in rule_ids {
+            meta.add_remark(Remark::new(RemarkType::Substituted, rule_id));
+        }
+    }
+
+    fn record_issue(&mut self, issue: SpanTreeIssue) {
+        *self.span_tree_issues.entry(issue).or_insert(0) += 1;
+    }
+
+    /// Checks that a span lies within the bounds of its parent.
+    ///
+    /// Returns the bounds of the span after repairing.
+    fn validate_span_bounds(
+        &mut self,
+        span: &mut Span,
+        (parent_start, parent_end): (Timestamp, Timestamp),
+    ) -> (Timestamp, Timestamp) {
+        let (start, end) = match (span.start_timestamp.value(), span.timestamp.value()) {
+            (Some(start), Some(end)) => (*start, *end),
+            _ => return (parent_start, parent_end),
+        };
+
+        if start >= parent_start && end <= parent_end {
+            return (start, end);
+        }
+
+        self.record_issue(SpanTreeIssue::OutOfBounds);
+
+        let new_start = start.max(parent_start).min(parent_end);
+        let new_end = end.min(parent_end).max(new_start);
+
+        if new_start != start {
+            let meta = span.start_timestamp.meta_mut();
+            meta.add_error(Error::invalid("span starts outside of its parent"));
+            if self.repair_span_tree {
+                meta.set_original_value(Some(start));
+                span.start_timestamp.set_value(Some(new_start));
+            }
+        }
+
+        if new_end != end {
+            let meta = span.timestamp.meta_mut();
+            meta.add_error(Error::invalid("span ends outside of its parent"));
+            if self.repair_span_tree {
+                meta.set_original_value(Some(end));
+                span.timestamp.set_value(Some(new_end));
+            }
+        }
+
+        if self.repair_span_tree {
+            (new_start, new_end)
+        } else {
+            (start, end)
+        }
+    }
+
+    /// Validates parent references, trace ids and timestamps of all spans.
+    ///
+    /// Problems are recorded as errors in meta and counted in the span tree issues.
+    fn validate_span_tree(&mut self, event: &mut Event, trace_id: &TraceId, root_span_id: &SpanId) {
+        let root_bounds = match (event.start_timestamp.value(), event.timestamp.value()) {
+            (Some(start), Some(end)) => (*start, *end),
+            _ => return,
+        };
+
+        let spans = match event.spans.value_mut() {
+            Some(spans) => spans,
+            None => return,
+        };
+
+        let indexes: HashMap<String, usize> = spans
+            .iter()
+            .enumerate()
+            .filter_map(|(index, span)| Some((span.value()?.span_id.value()?.0.clone(), index)))
+            .collect();
+
+        let mut parents = vec![Parent::Detached; spans.len()];
+
+        for (index, span) in spans.iter_mut().enumerate() {
+            let span = match span.value_mut() {
+                Some(span) => span,
+                None => continue,
+            };
+
+            if span.trace_id.value() != Some(trace_id) {
+                span.trace_id
+                    .meta_mut()
+                    .add_error(Error::invalid("trace_id does not match the transaction"));
+                self.record_issue(SpanTreeIssue::TraceIdMismatch);
+            }
+
+            parents[index] = match span.parent_span_id.value() {
+                None => Parent::Root,
+                Some(parent_id) if parent_id == root_span_id => Parent::Root,
+                Some(parent_id) => match indexes.get(&parent_id.0) {
+                    Some(&parent) => Parent::Span(parent),
+                    None => {
+                        self.record_issue(SpanTreeIssue::MissingParent);
+                        span.parent_span_id
+                            .meta_mut()
+                            .add_error(Error::invalid("parent span does not exist"));
+
+                        if self.repair_span_tree {
+                            reparent_to_root(span, root_span_id);
+                            Parent::Root
+                        } else {
+                            Parent::Detached
+                        }
+                    }
+                },
+            };
+        }
+
+        // Detect cycles by walking up the parents of every span. A cycle is broken at the span
+        // whose parent reference closes it.
+        let mut states = vec![VisitState::New; spans.len()];
+        for start in 0..spans.len() {
+            let mut path = Vec::new();
+            let mut current = start;
+
+            loop {
+                match states[current] {
+                    VisitState::Done => break,
+                    VisitState::Visiting => {
+                        let closing = path[path.len() - 1];
+                        self.record_issue(SpanTreeIssue::Cycle);
+
+                        if let Some(span) = spans[closing].value_mut() {
+                            span.parent_span_id
+                                .meta_mut()
+                                .add_error(Error::invalid("span is part of a cycle"));
+
+                            parents[closing] = if self.repair_span_tree {
+                                reparent_to_root(span, root_span_id);
+                                Parent::Root
+                            } else {
+                                Parent::Detached
+                            };
+                        }
+
+                        break;
+                    }
+                    VisitState::New => {
+                        states[current] = VisitState::Visiting;
+                        path.push(current);
+
+                        match parents[current] {
+                            Parent::Span(parent) => current = parent,
+                            Parent::Root | Parent::Detached => break,
+                        }
+                    }
+                }
+            }
+
+            for index in path {
+                states[index] = VisitState::Done;
+            }
+        }
+
+        // Check bounds from the root down, so that parents are clamped before their children.
+        let mut children = vec![Vec::new(); spans.len()];
+        let mut queue = VecDeque::new();
+        for (index, parent) in parents.iter().enumerate() {
+            match *parent {
+                Parent::Root => queue.push_back((index, root_bounds)),
+                Parent::Span(parent) => children[parent].push(index),
+                Parent::Detached => (),
+            }
+        }
+
+        while let Some((index, parent_bounds)) = queue.pop_front() {
+            let bounds = match spans[index].value_mut() {
+                Some(span) => self.validate_span_bounds(span, parent_bounds),
+                None => continue,
+            };
+
+            for &child in &children[index] {
+                queue.push_back((child, bounds));
+            }
+        }
+    }
 }
 
-impl Processor for TransactionsProcessor {
+impl Processor for TransactionsProcessor<'_> {
     fn process_event(
         &mut self,
         event: &mut Event,
@@ -32,6 +476,14 @@ impl Processor for TransactionsProcessor {
         state: &ProcessingState<'_>,
     ) -> ProcessingResult {
         if event.ty.value() != Some(&EventType::Transaction) {
+            // Measurements are only meaningful for transactions and are discarded otherwise.
+            if event.measurements.value().is_some() {
+                event.measurements = Annotated::from_error(
+                    Error::invalid("measurements are only allowed on transactions"),
+                    None,
+                );
+            }
+
             return Ok(());
         }
 
@@ -46,6 +498,8 @@ impl Processor for TransactionsProcessor {
                 .set_value(Some("<unlabeled transaction>".to_owned()))
         }
 
+        self.normalize_transaction_name(event);
+
         match (event.start_timestamp.value(), event.timestamp.value_mut()) {
             (Some(start), Some(end)) => {
                 if *end < *start {
@@ -85,7 +539,7 @@ impl Processor for TransactionsProcessor {
             _ => return err_trace_context_required,
         };
 
-        match trace_context {
+        let (trace_id, root_span_id) = match trace_context {
             ContextInner(Context::Trace(trace_context)) => {
                 if trace_context.trace_id.value().is_none() {
                     return Err(ProcessingAction::InvalidTransaction(
@@ -100,13 +554,18 @@ impl Processor for TransactionsProcessor {
                 }
 
                 trace_context.op.get_or_insert_with(|| "default".to_owned());
+
+                (
+                    trace_context.trace_id.value().cloned(),
+                    trace_context.span_id.value().cloned(),
+                )
             }
             _ => {
                 return Err(ProcessingAction::InvalidTransaction(
                     "context at event.contexts.trace must be of type trace.",
                 ));
             }
-        }
+        };
 
         if let Some(spans) = event.spans.value() {
             for span in spans {
@@ -120,6 +579,10 @@ impl Processor for TransactionsProcessor {
 
         event.process_child_values(self, state)?;
 
+        if let (Some(trace_id), Some(root_span_id)) = (trace_id, root_span_id) {
+            self.validate_span_tree(event, &trace_id, &root_span_id);
+        }
+
         Ok(())
     }
 
@@ -194,8 +657,8 @@ mod tests {
     use chrono::Utc;
 
     use crate::processor::process_value;
-    use crate::protocol::{Contexts, SpanId, TraceContext, TraceId};
-    use crate::types::Object;
+    use crate::protocol::{Contexts, Measurements, SpanId, TraceContext, TraceId};
+    use crate::types::{Object, Value};
 
     fn new_test_event() -> Annotated<Event> {
         let start = Utc.ymd(2000, 1, 1).and_hms(0, 0, 0);
@@ -244,6 +707,25 @@ mod tests {
         assert!(event.value().is_some());
     }
 
+    #[test]
+    fn test_removes_measurements_from_non_transaction_events() {
+        let mut event = Annotated::new(Event {
+            measurements: Annotated::new(Measurements::default()),
+            ..Default::default()
+        });
+
+        process_value(
+            &mut event,
+            &mut TransactionsProcessor::new(None),
+            ProcessingState::root(),
+        )
+        .unwrap();
+
+        let measurements = &event.value().unwrap().measurements;
+        assert!(measurements.value().is_none());
+        assert!(measurements.meta().has_errors());
+    }
+
     #[test]
     fn test_discards_when_missing_timestamp() {
         let mut event = Annotated::new(Event {
@@ -1004,4 +1486,222 @@ mod tests {
         }
         "###);
     }
+
+    fn test_span(
+        span_id: &str,
+        parent_span_id: Option<&str>,
+        start: u32,
+        end: u32,
+    ) -> Annotated<Span> {
+        Annotated::new(Span {
+            start_timestamp: Annotated::new(Utc.ymd(2000, 1, 1).and_hms(0, 0, start)),
+            timestamp: Annotated::new(Utc.ymd(2000, 1, 1).and_hms(0, 0, end)),
+            trace_id: Annotated::new(TraceId("4c79f60c11214eb38604f4ae0781bfb2".into())),
+            span_id: Annotated::new(SpanId(span_id.into())),
+            parent_span_id: Annotated::from(parent_span_id.map(|id| SpanId(id.into()))),
+            op: Annotated::new("db.statement".to_owned()),
+            ..Default::default()
+        })
+    }
+
+    fn process_span_tree(
+        spans: Vec<Annotated<Span>>,
+        repair: bool,
+    ) -> (Annotated<Event>, Vec<(SpanTreeIssue, usize)>) {
+        let mut event = new_test_event();
+        let end = *event.value().unwrap().timestamp.value().unwrap();
+        event.value_mut().as_mut().unwrap().spans = Annotated::new(spans);
+
+        let mut processor = TransactionsProcessor::new(None).repair_span_tree(repair);
+        processor.now = end;
+        process_value(&mut event, &mut processor, ProcessingState::root()).unwrap();
+
+        let issues = processor.into_span_tree_issues().into_iter().collect();
+        (event, issues)
+    }
+
+    #[test]
+    fn test_validates_span_tree() {
+        let mut mismatch = test_span("bbbbbbbbbbbbbbbb", Some("fa90fdead5f74053"), 1, 2);
+        mismatch.value_mut().as_mut().unwrap().trace_id =
+            Annotated::new(TraceId("ffffffffffffffffffffffffffffffff".into()));
+
+        let spans = vec![
+            test_span("aaaaaaaaaaaaaaaa", Some("fa90fdead5f74053"), 1, 5),
+            mismatch,
+            test_span("cccccccccccccccc", Some("0000000000000000"), 1, 2),
+            test_span("dddddddddddddddd", Some("eeeeeeeeeeeeeeee"), 1, 2),
+            test_span("eeeeeeeeeeeeeeee", Some("dddddddddddddddd"), 1, 2),
+            test_span("1111111111111111", Some("aaaaaaaaaaaaaaaa"), 4, 9),
+        ];
+
+        let (event, issues) = process_span_tree(spans, false);
+        assert_eq_dbg!(
+            issues,
+            vec![
+                (SpanTreeIssue::MissingParent, 1),
+                (SpanTreeIssue::Cycle, 1),
+                (SpanTreeIssue::TraceIdMismatch, 1),
+                (SpanTreeIssue::OutOfBounds, 1),
+            ]
+        );
+
+        let spans = event.value().unwrap().spans.value().unwrap();
+        let orphan = spans[2].value().unwrap();
+        assert!(orphan.parent_span_id.meta().has_errors());
+        assert_eq_dbg!(orphan.parent_span_id.value().unwrap().0, "0000000000000000");
+
+        let child = spans[5].value().unwrap();
+        assert!(child.timestamp.meta().has_errors());
+        assert_eq_dbg!(
+            child.timestamp.value(),
+            Some(&Utc.ymd(2000, 1, 1).and_hms(0, 0, 9))
+        );
+    }
+
+    #[test]
+    fn test_repairs_span_tree() {
+        let spans = vec![
+            test_span("aaaaaaaaaaaaaaaa", Some("0000000000000000"), 0, 20),
+            test_span("bbbbbbbbbbbbbbbb", Some("aaaaaaaaaaaaaaaa"), 2, 30),
+            test_span("cccccccccccccccc", Some("dddddddddddddddd"), 1, 2),
+            test_span("dddddddddddddddd", Some("cccccccccccccccc"), 1, 2),
+        ];
+
+        let (event, issues) = process_span_tree(spans, true);
+        assert_eq_dbg!(
+            issues,
+            vec![
+                (SpanTreeIssue::MissingParent, 1),
+                (SpanTreeIssue::Cycle, 1),
+                (SpanTreeIssue::OutOfBounds, 2),
+            ]
+        );
+
+        let spans = event.value().unwrap().spans.value().unwrap();
+        let bounds = |index: usize| {
+            let span = spans[index].value().unwrap();
+            let start = span.start_timestamp.value().unwrap().timestamp();
+            let end = span.timestamp.value().unwrap().timestamp();
+            (start - 946_684_800, end - 946_684_800)
+        };
+        let parent = |index: usize| {
+            let span = spans[index].value().unwrap();
+            span.parent_span_id.value().unwrap().0.clone()
+        };
+
+        // The orphan is attached to the root span and clamped, which clamps its child in turn.
+        assert_eq_dbg!(parent(0), "fa90fdead5f74053");
+        assert_eq_dbg!(
+            spans[0]
+                .value()
+                .unwrap()
+                .parent_span_id
+                .meta()
+                .original_value(),
+            Some(&Value::String("0000000000000000".to_owned()))
+        );
+        assert_eq_dbg!(bounds(0), (0, 10));
+        assert_eq_dbg!(bounds(1), (2, 10));
+
+        // The cycle is broken at the span closing it.
+        assert_eq_dbg!(parent(2), "dddddddddddddddd");
+        assert_eq_dbg!(parent(3), "fa90fdead5f74053");
+    }
+
+    fn normalize_name(config: &TransactionNameConfig, name: &str) -> Option<String> {
+        config.normalize(name).map(|(name, _)| name)
+    }
+
+    #[test]
+    fn test_scrub_transaction_name_identifiers() {
+        let config = TransactionNameConfig {
+            scrub_identifiers: true,
+            ..Default::default()
+        };
+
+        assert_eq_dbg!(
+            normalize_name(&config, "/users/1234/orders/9f8e7d6c5b4a"),
+            Some("/users/*/orders/*".to_owned())
+        );
+        assert_eq_dbg!(
+            normalize_name(&config, "/items/4c79f60c-1121-4eb3-8604-f4ae0781bfb2/"),
+            Some("/items/*/".to_owned())
+        );
+        assert_eq_dbg!(
+            normalize_name(&config, "https://example.com/reports/2020-08-01?page=2"),
+            Some("https://example.com/reports/*?page=2".to_owned())
+        );
+        assert_eq_dbg!(normalize_name(&config, "/settings/deadbeef/accepted"), None);
+        assert_eq_dbg!(normalize_name(&config, "tasks.process_order"), None);
+        assert_eq_dbg!(normalize_name(&config, "/users/me"), None);
+    }
+
+    #[test]
+    fn test_transaction_name_rules() {
+        let config = TransactionNameConfig {
+            scrub_identifiers: true,
+            rules: vec![TransactionNameRule {
+                pattern: "/orgs/*/projects/*/**".to_owned(),
+            }],
+        };
+
+        assert_eq_dbg!(
+            config.normalize("/orgs/acme/projects/backend/issues/42"),
+            Some((
+                "/orgs/*/projects/*/issues/*".to_owned(),
+                vec!["/orgs/*/projects/*/**", IDENTIFIER_RULE_ID]
+            ))
+        );
+        assert_eq_dbg!(normalize_name(&config, "/teams/acme"), None);
+    }
+
+    #[test]
+    fn test_transaction_name_rules_double_star() {
+        let config = TransactionNameConfig {
+            scrub_identifiers: false,
+            rules: vec![TransactionNameRule {
+                pattern: "/orgs/*/**/files/*".to_owned(),
+            }],
+        };
+
+        assert_eq_dbg!(
+            config.normalize("/orgs/acme/projects/backend/files/readme"),
+            Some((
+                "/orgs/*/projects/backend/files/*".to_owned(),
+                vec!["/orgs/*/**/files/*"]
+            ))
+        );
+        assert_eq_dbg!(
+            config.normalize("/orgs/acme/files/readme"),
+            Some(("/orgs/*/files/*".to_owned(), vec!["/orgs/*/**/files/*"]))
+        );
+    }
+
+    #[test]
+    fn test_normalizes_transaction_name_in_event() {
+        let config = TransactionNameConfig {
+            scrub_identifiers: true,
+            ..Default::default()
+        };
+
+        let mut event = new_test_event();
+        let end = *event.value().unwrap().timestamp.value().unwrap();
+        event.value_mut().as_mut().unwrap().transaction = Annotated::new("/users/1234/".to_owned());
+
+        let mut processor = TransactionsProcessor::new(None).transaction_name_config(Some(&config));
+        processor.now = end;
+        process_value(&mut event, &mut processor, ProcessingState::root()).unwrap();
+
+        let transaction = &event.value().unwrap().transaction;
+        assert_eq_dbg!(transaction.as_str(), Some("/users/*/"));
+        assert_eq_dbg!(
+            transaction.meta().original_value(),
+            Some(&Value::String("/users/1234/".to_owned()))
+        );
+
+        let remark = transaction.meta().iter_remarks().next().unwrap();
+        assert_eq_dbg!(remark.rule_id(), IDENTIFIER_RULE_ID);
+        assert_eq_dbg!(remark.ty(), RemarkType::Substituted);
+    }
 }
@@ -0,0 +1,706 @@
+//! Declarative transformations of event payloads configured per project.
+use std::borrow::Cow;
+use std::fmt;
+use std::mem;
+use std::str::FromStr;
+
+use failure::Fail;
+use serde::{Deserialize, Serialize};
+
+use crate::pii::process_pairlist;
+use crate::processor::{
+    process_chunked_value, Chunk, InvalidSelectorError, ProcessValue, ProcessingState, Processor,
+    SelectorPathItem, SelectorSpec,
+};
+use crate::protocol::{AsPair, Event, PairList};
+use crate::types::{
+    Annotated, FromValue, Meta, Object, ProcessingAction, ProcessingResult, Remark, RemarkType,
+    ToValue, Value,
+};
+
+/// Error for invalid field paths.
+#[derive(Debug, Fail)]
+pub enum InvalidFieldPathError {
+    #[fail(display = "{}", _0)]
+    InvalidSelector(#[cause] InvalidSelectorError),
+
+    #[fail(display = "invalid field path: only keys separated by dots are allowed")]
+    UnsupportedSelector,
+}
+
+/// A path of keys that addresses a single location in an event, such as `tags.request_id`.
+///
+/// Field paths use the syntax of `SelectorSpec` paths, but do not allow types, indexes, wildcards
+/// or boolean operators. Keys of pair lists, such as tags, address the value of the pair.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct FieldPath(Vec<String>);
+
+impl FieldPath {
+    /// Returns the keys of this path.
+    pub fn keys(&self) -> &[String] {
+        &self.0
+    }
+}
+
+impl FromStr for FieldPath {
+    type Err = InvalidFieldPathError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let items = match s.parse() {
+            Ok(SelectorSpec::Path(items)) => items,
+            Ok(_) => return Err(InvalidFieldPathError::UnsupportedSelector),
+            Err(error) => return Err(InvalidFieldPathError::InvalidSelector(error)),
+        };
+
+        let keys = items
+            .into_iter()
+            .map(|item| match item {
+                SelectorPathItem::Key(key) => Ok(key),
+                _ => Err(InvalidFieldPathError::UnsupportedSelector),
+            })
+            .collect::<Result<_, _>>()?;
+
+        Ok(FieldPath(keys))
+    }
+}
+
+impl fmt::Display for FieldPath {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for (index, key) in self.0.iter().enumerate() {
+            if index > 0 {
+                write!(f, ".")?;
+            }
+            write!(f, "{}", SelectorPathItem::Key(key.clone()))?;
+        }
+        Ok(())
+    }
+}
+
+impl_str_serde!(FieldPath);
+
+/// The operation performed by a `TransformRule`.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(tag = "op", rename_all = "camelCase")]
+pub enum TransformOperation {
+    /// Sets the target to a fixed value.
+    Set {
+        target: FieldPath,
+        value: Value,
+        /// Only sets the value if the target is missing, for instance to default the environment.
+        #[serde(default, rename = "keepExisting")]
+        keep_existing: bool,
+    },
+    /// Copies the first value matching the source selector to the target.
+    Copy {
+        source: SelectorSpec,
+        target: FieldPath,
+    },
+    /// Moves the first value matching the source selector to the target.
+    ///
+    /// All other values matching the source selector are removed, too.
+    Move {
+        source: SelectorSpec,
+        target: FieldPath,
+    },
+    /// Removes all values matching the target selector.
+    Delete { target: SelectorSpec },
+    /// Converts all strings matching the target selector to lower case.
+    Lowercase { target: SelectorSpec },
+    /// Truncates all strings matching the target selector to a maximum number of characters.
+    Truncate {
+        target: SelectorSpec,
+        #[serde(rename = "maxChars")]
+        max_chars: usize,
+    },
+}
+
+/// A transformation applied to events of a project.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct TransformRule {
+    /// Identifier of this rule, recorded in remarks of modified values.
+    ///
+    /// Defaults to `@transform:<index>` with the position of the rule in the list.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub id: Option<String>,
+
+    /// The operation of this rule.
+    #[serde(flatten)]
+    pub operation: TransformOperation,
+}
+
+/// Ordered transformation rules, each of which is applied to the result of the previous rule.
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct TransformRules(pub Vec<TransformRule>);
+
+impl TransformRules {
+    /// Returns `true` if there are no rules.
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+}
+
+/// The part of a rule that is currently applied while traversing the event.
+#[derive(Clone, Copy, Debug)]
+enum Step<'a> {
+    Collect(&'a SelectorSpec),
+    Delete(&'a SelectorSpec),
+    Lowercase(&'a SelectorSpec),
+    Truncate(&'a SelectorSpec, usize),
+}
+
+/// Returns the key of a raw pair, such as a tag, if the value is a pair.
+fn pair_key(pair: &Annotated<Value>) -> Option<&str> {
+    match pair.value() {
+        Some(Value::Array(items)) if items.len() == 2 => items[0].as_str(),
+        _ => None,
+    }
+}
+
+/// Returns the slot at the given path in a raw value, inserting missing objects along the way.
+///
+/// Arrays are treated as pair lists and the value of the pair with the matching key is returned.
+/// Returns `None` if the path traverses any other value.
+fn get_or_insert_path<'a>(
+    value: &'a mut Annotated<Value>,
+    path: &[String],
+) -> Option<&'a mut Annotated<Value>> {
+    let (key, rest) = match path.split_first() {
+        Some(split) => split,
+        None => return Some(value),
+    };
+
+    let slot = match value.get_or_insert_with(|| Value::Object(Object::new())) {
+        Value::Object(object) => object.entry(key.clone()).or_insert_with(Annotated::empty),
+        Value::Array(pairs) => {
+            let index = match pairs
+                .iter()
+                .position(|pair| pair_key(pair) == Some(key.as_str()))
+            {
+                Some(index) => index,
+                None => {
+                    let key = Annotated::new(Value::String(key.clone()));
+                    pairs.push(Annotated::new(Value::Array(vec![key, Annotated::empty()])));
+                    pairs.len() - 1
+                }
+            };
+
+            match pairs[index].value_mut() {
+                Some(Value::Array(pair)) => &mut pair[1],
+                _ => return None,
+            }
+        }
+        _ => return None,
+    };
+
+    get_or_insert_path(slot, rest)
+}
+
+/// A value waiting to be written to its target while the event is traversed.
+struct PendingWrite<'a> {
+    target: &'a FieldPath,
+    value: Annotated<Value>,
+    overwrite: bool,
+}
+
+/// Writes a value into a raw value at the given path.
+///
+/// Missing objects along the path are inserted, so that the target does not need to exist.
+fn write_raw(
+    raw: &mut Annotated<Value>,
+    path: &[String],
+    value: Annotated<Value>,
+    overwrite: bool,
+    rule_id: &str,
+) {
+    let slot = match get_or_insert_path(raw, path) {
+        Some(slot) => slot,
+        None => return,
+    };
+
+    let remark_type = if slot.value().is_none() {
+        RemarkType::Annotated
+    } else if overwrite {
+        RemarkType::Substituted
+    } else {
+        return;
+    };
+
+    let previous = mem::replace(slot.value_mut(), value.0);
+    let meta = slot.meta_mut();
+    if previous.is_some() && meta.original_value().is_none() {
+        meta.set_original_value(previous);
+    }
+    meta.add_remark(Remark::new(remark_type, rule_id));
+}
+
+/// Truncates a string to the given number of characters, retaining existing remarks.
+fn truncate_string(value: &mut String, meta: &mut Meta, max_chars: usize, rule_id: &str) {
+    if bytecount::num_chars(value.as_bytes()) <= max_chars {
+        return;
+    }
+
+    process_chunked_value(value, meta, |chunks| {
+        let mut length = 0;
+        let mut new_chunks = vec![];
+
+        for chunk in chunks {
+            let chunk_chars = chunk.count();
+            if length + chunk_chars <= max_chars {
+                new_chunks.push(chunk);
+                length += chunk_chars;
+                continue;
+            }
+
+            // Redactions are dropped entirely since a partial redaction marker is meaningless.
+            if let Chunk::Text { text } = chunk {
+                let remaining = text.chars().take(max_chars - length).collect::<String>();
+                new_chunks.push(Chunk::Text {
+                    text: Cow::Owned(remaining),
+                });
+            }
+
+            new_chunks.push(Chunk::Redaction {
+                text: Cow::Borrowed(""),
+                rule_id: Cow::Owned(rule_id.to_owned()),
+                ty: RemarkType::Removed,
+            });
+            break;
+        }
+
+        new_chunks
+    });
+}
+
+/// A processor that applies transformation rules to events.
+///
+/// Rules that write a value with `set`, `copy` or `move` convert the top-level field containing
+/// the target into its raw representation and back, which is more expensive than the in-place
+/// operations.
+pub struct TransformProcessor<'a> {
+    rules: &'a TransformRules,
+    step: Option<Step<'a>>,
+    rule_id: Cow<'a, str>,
+    collected: Option<Annotated<Value>>,
+    write: Option<PendingWrite<'a>>,
+}
+
+impl<'a> TransformProcessor<'a> {
+    /// Creates a new processor for the given rules.
+    pub fn new(rules: &'a TransformRules) -> Self {
+        TransformProcessor {
+            rules,
+            step: None,
+            rule_id: Cow::Borrowed(""),
+            collected: None,
+            write: None,
+        }
+    }
+
+    /// Traverses the event and applies the given step to all matching values.
+    fn run_step(
+        &mut self,
+        step: Step<'a>,
+        event: &mut Event,
+        state: &ProcessingState<'_>,
+    ) -> ProcessingResult {
+        self.step = Some(step);
+        let result = event.process_child_values(self, state);
+        self.step = None;
+        result
+    }
+
+    /// Returns the first value matching the selector.
+    fn collect(
+        &mut self,
+        selector: &'a SelectorSpec,
+        event: &mut Event,
+        state: &ProcessingState<'_>,
+    ) -> Result<Option<Annotated<Value>>, ProcessingAction> {
+        self.run_step(Step::Collect(selector), event, state)?;
+        Ok(self.collected.take())
+    }
+
+    /// Writes a value into the event at the given path.
+    ///
+    /// Only the top-level field addressed by the path is converted to a raw value and back.
+    /// Values that do not fit the schema at the target are recorded as errors.
+    fn write_field(
+        &mut self,
+        event: &mut Event,
+        target: &'a FieldPath,
+        value: Annotated<Value>,
+        overwrite: bool,
+        state: &ProcessingState<'_>,
+    ) -> ProcessingResult {
+        let key = match target.keys().first() {
+            Some(key) => key,
+            None => return Ok(()),
+        };
+
+        self.write = Some(PendingWrite {
+            target,
+            value,
+            overwrite,
+        });
+        let result = event.process_child_values(self, state);
+
+        // Fields that are neither known nor present in the event are added to `other`.
+        if let Some(write) = self.write.take() {
+            let slot = event
+                .other
+                .entry(key.clone())
+                .or_insert_with(Annotated::empty);
+            let rest = &target.keys()[1..];
+            write_raw(slot, rest, write.value, write.overwrite, &self.rule_id);
+        }
+
+        result
+    }
+}
+
+impl<'a> Processor for TransformProcessor<'a> {
+    fn process_annotated<T: ProcessValue>(
+        &mut self,
+        annotated: &mut Annotated<T>,
+        state: &ProcessingState<'_>,
+    ) -> ProcessingResult {
+        // Writes replace the top-level field of the event that contains the target.
+        if state.depth() != 1 {
+            return Ok(());
+        }
+
+        let is_target = match self.write {
+            Some(ref write) => {
+                state.path().key() == write.target.keys().first().map(String::as_str)
+            }
+            None => false,
+        };
+
+        if !is_target {
+            return Ok(());
+        }
+
+        if let Some(write) = self.write.take() {
+            let Annotated(value, meta) = mem::take(annotated);
+            let mut raw = Annotated(value.map(ToValue::to_value), meta);
+            let rest = &write.target.keys()[1..];
+            write_raw(&mut raw, rest, write.value, write.overwrite, &self.rule_id);
+            *annotated = FromValue::from_value(raw);
+        }
+
+        Ok(())
+    }
+
+    fn before_process<T: ProcessValue>(
+        &mut self,
+        value: Option<&T>,
+        meta: &mut Meta,
+        state: &ProcessingState<'_>,
+    ) -> ProcessingResult {
+        let value = match value {
+            Some(value) => value,
+            None => return Ok(()),
+        };
+
+        match self.step {
+            Some(Step::Collect(selector)) => {
+                if self.collected.is_none() && state.path().matches_selector(selector) {
+                    self.collected = Some(Annotated::new(value.clone().to_value()));
+                }
+            }
+            Some(Step::Delete(selector)) => {
+                if state.path().matches_selector(selector) {
+                    meta.add_remark(Remark::new(RemarkType::Removed, &*self.rule_id));
+                    return Err(ProcessingAction::DeleteValueHard);
+                }
+            }
+            _ => (),
+        }
+
+        Ok(())
+    }
+
+    fn process_string(
+        &mut self,
+        value: &mut String,
+        meta: &mut Meta,
+        state: &ProcessingState<'_>,
+    ) -> ProcessingResult {
+        // Strings are matched including their value, so that selectors with predicates apply.
+        let step = match self.step {
+            Some(step) => step,
+            None => return Ok(()),
+        };
+
+        let path = state.path();
+        match step {
+            Step::Collect(selector) => {
+                if self.collected.is_none()
+                    && path.matches_selector_value(selector, Some(value.as_str()))
+                {
+                    self.collected = Some(Annotated::new(Value::String(value.clone())));
+                }
+            }
+            Step::Delete(selector) => {
+                if path.matches_selector_value(selector, Some(value.as_str())) {
+                    meta.add_remark(Remark::new(RemarkType::Removed, &*self.rule_id));
+                    return Err(ProcessingAction::DeleteValueHard);
+                }
+            }
+            Step::Lowercase(selector) => {
+                if path.matches_selector_value(selector, Some(value.as_str())) {
+                    let lowercase = value.to_lowercase();
+                    if lowercase != *value {
+                        meta.set_original_value(Some(mem::replace(value, lowercase)));
+                        meta.add_remark(Remark::new(RemarkType::Substituted, &*self.rule_id));
+                    }
+                }
+            }
+            Step::Truncate(selector, max_chars) => {
+                if path.matches_selector_value(selector, Some(value.as_str())) {
+                    truncate_string(value, meta, max_chars, &self.rule_id);
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    fn process_pairlist<T: ProcessValue + AsPair>(
+        &mut self,
+        value: &mut PairList<T>,
+        _meta: &mut Meta,
+        state: &ProcessingState,
+    ) -> ProcessingResult {
+        // Address pairs by their key, such as `tags.environment`, like in PII selectors.
+        process_pairlist(self, value, state)
+    }
+
+    fn process_event(
+        &mut self,
+        event: &mut Event,
+        _meta: &mut Meta,
+        state: &ProcessingState<'_>,
+    ) -> ProcessingResult {
+        let rules = self.rules;
+        for (index, rule) in rules.0.iter().enumerate() {
+            self.rule_id = match rule.id {
+                Some(ref id) => Cow::Borrowed(id.as_str()),
+                None => Cow::Owned(format!("@transform:{}", index)),
+            };
+
+            match rule.operation {
+                TransformOperation::Set {
+                    ref target,
+                    ref value,
+                    keep_existing,
+                } => {
+                    let value = Annotated::new(value.clone());
+                    self.write_field(event, target, value, !keep_existing, state)?;
+                }
+                TransformOperation::Copy {
+                    ref source,
+                    ref target,
+                } => {
+                    if let Some(value) = self.collect(source, event, state)? {
+                        self.write_field(event, target, value, true, state)?;
+                    }
+                }
+                TransformOperation::Move {
+                    ref source,
+                    ref target,
+                } => {
+                    if let Some(value) = self.collect(source, event, state)? {
+                        self.run_step(Step::Delete(source), event, state)?;
+                        self.write_field(event, target, value, true, state)?;
+                    }
+                }
+                TransformOperation::Delete { ref target } => {
+                    self.run_step(Step::Delete(target), event, state)?;
+                }
+                TransformOperation::Lowercase { ref target } => {
+                    self.run_step(Step::Lowercase(target), event, state)?;
+                }
+                TransformOperation::Truncate {
+                    ref target,
+                    max_chars,
+                } => {
+                    self.run_step(Step::Truncate(target, max_chars), event, state)?;
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::processor::process_value;
+
+    fn transform(rules: &str, event: &str) -> Annotated<Event> {
+        let rules: TransformRules = serde_json::from_str(rules).unwrap();
+        let mut event = Annotated::<Event>::from_json(event).unwrap();
+        let mut processor = TransformProcessor::new(&rules);
+        process_value(&mut event, &mut processor, ProcessingState::root()).unwrap();
+        event
+    }
+
+    fn tag<'a>(event: &'a Event, key: &str) -> Option<&'a str> {
+        event
+            .tags
+            .value()?
+            .iter()
+            .filter_map(Annotated::value)
+            .find(|entry| entry.0.as_str() == Some(key))
+            .and_then(|entry| entry.1.as_str())
+    }
+
+    #[test]
+    fn test_field_path() {
+        let path: FieldPath = "tags.'request.id'".parse().unwrap();
+        assert_eq_dbg!(
+            path.keys(),
+            &["tags".to_owned(), "request.id".to_owned()][..]
+        );
+        assert_eq_str!(path.to_string(), "tags.'request.id'");
+
+        assert!("extra.*".parse::<FieldPath>().is_err());
+        assert!("$string".parse::<FieldPath>().is_err());
+        assert!("extra.foo || tags.foo".parse::<FieldPath>().is_err());
+    }
+
+    #[test]
+    fn test_set() {
+        let rules = r#"[
+            {"op": "set", "target": "environment", "value": "production", "keepExisting": true},
+            {"op": "set", "target": "contexts.deployment.region", "value": "eu"}
+        ]"#;
+
+        let event = transform(rules, r#"{"environment": "staging"}"#);
+        let event = event.value().unwrap();
+        assert_eq_dbg!(event.environment.as_str(), Some("staging"));
+        assert!(event.contexts.value().unwrap().contains_key("deployment"));
+
+        let event = transform(rules, "{}");
+        let environment = &event.value().unwrap().environment;
+        assert_eq_dbg!(environment.as_str(), Some("production"));
+        assert_eq_dbg!(
+            environment.meta().iter_remarks().collect::<Vec<_>>(),
+            vec![&Remark::new(RemarkType::Annotated, "@transform:0")]
+        );
+    }
+
+    #[test]
+    fn test_set_unknown_field() {
+        let rules = r#"[{"op": "set", "target": "custom.flag", "value": true}]"#;
+
+        let event = transform(rules, "{}");
+        let custom = &event.value().unwrap().other["custom"];
+        let mut expected = Object::new();
+        expected.insert("flag".to_owned(), Annotated::new(Value::Bool(true)));
+        assert_eq_dbg!(custom.value(), Some(&Value::Object(expected)));
+
+        let event = transform(rules, r#"{"custom": {"flag": false}}"#);
+        let custom = event.value().unwrap().other["custom"].value();
+        let flag = match custom {
+            Some(Value::Object(custom)) => &custom["flag"],
+            _ => panic!("expected an object"),
+        };
+        assert_eq_dbg!(flag.value(), Some(&Value::Bool(true)));
+        assert_eq_dbg!(flag.meta().original_value(), Some(&Value::Bool(false)));
+    }
+
+    #[test]
+    fn test_move_to_tag() {
+        let rules = r#"[{"id": "request-id", "op": "move", "source": "extra.request_id", "target": "tags.request_id"}]"#;
+        let event = transform(
+            rules,
+            r#"{"extra": {"request_id": 42, "other": true}, "tags": {"level": "info"}}"#,
+        );
+
+        let event = event.value().unwrap();
+        assert_eq_dbg!(tag(event, "request_id"), Some("42"));
+        assert_eq_dbg!(tag(event, "level"), Some("info"));
+
+        let extra = event.extra.value().unwrap();
+        assert!(extra["request_id"].value().is_none());
+        assert_eq_dbg!(
+            extra["request_id"]
+                .meta()
+                .iter_remarks()
+                .collect::<Vec<_>>(),
+            vec![&Remark::new(RemarkType::Removed, "request-id")]
+        );
+        assert!(extra["other"].value().is_some());
+    }
+
+    #[test]
+    fn test_copy_tag() {
+        let rules = r#"[{"op": "copy", "source": "tags.server_name", "target": "server_name"}]"#;
+        let event = transform(rules, r#"{"tags": [["server_name", "web-1"]]}"#);
+
+        let event = event.value().unwrap();
+        assert_eq_dbg!(event.server_name.as_str(), Some("web-1"));
+        assert_eq_dbg!(tag(event, "server_name"), Some("web-1"));
+    }
+
+    #[test]
+    fn test_copy_missing_source() {
+        let rules = r#"[{"op": "copy", "source": "extra.missing", "target": "tags.missing"}]"#;
+        let event = transform(rules, "{}");
+        assert!(event.value().unwrap().tags.value().is_none());
+    }
+
+    #[test]
+    fn test_delete() {
+        let rules = r#"[
+            {"op": "delete", "target": "modules"},
+            {"op": "delete", "target": "extra.*[?(@ ~ '^tok_')]"}
+        ]"#;
+        let event = transform(
+            rules,
+            r#"{"modules": {"rust": "1.0"}, "extra": {"a": "tok_123", "b": "value"}}"#,
+        );
+
+        let event = event.value().unwrap();
+        assert!(event.modules.value().is_none());
+
+        let extra = event.extra.value().unwrap();
+        assert!(extra["a"].value().is_none());
+        assert!(extra["b"].value().is_some());
+    }
+
+    #[test]
+    fn test_lowercase_and_truncate() {
+        let rules = r#"[
+            {"op": "lowercase", "target": "tags.env"},
+            {"op": "truncate", "target": "transaction", "maxChars": 5}
+        ]"#;
+        let event = transform(
+            rules,
+            r#"{"tags": {"env": "PROD"}, "transaction": "/api/users"}"#,
+        );
+
+        let event = event.value().unwrap();
+        assert_eq_dbg!(tag(event, "env"), Some("prod"));
+
+        let transaction = &event.transaction;
+        assert_eq_dbg!(transaction.as_str(), Some("/api/"));
+        assert_eq_dbg!(transaction.meta().original_length(), Some(10));
+    }
+
+    #[test]
+    fn test_rules_apply_in_order() {
+        let rules = r#"[
+            {"op": "copy", "source": "extra.tenant", "target": "tags.tenant"},
+            {"op": "lowercase", "target": "tags.tenant"}
+        ]"#;
+        let event = transform(rules, r#"{"extra": {"tenant": "ACME"}}"#);
+
+        let event = event.value().unwrap();
+        assert_eq_dbg!(tag(event, "tenant"), Some("acme"));
+    }
+}
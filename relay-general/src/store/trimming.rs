@@ -2,11 +2,16 @@ use std::borrow::Cow;
 
 use crate::processor::{estimate_size_flat, process_chunked_value, BagSize, Chunk, MaxChars};
 use crate::processor::{process_value, ProcessValue, ProcessingState, Processor, ValueType};
-use crate::protocol::{Frame, RawStacktrace};
+use crate::protocol::{Event, Frame, RawStacktrace, Thread};
 use crate::types::{
     Annotated, Array, Empty, Meta, Object, ProcessingAction, ProcessingResult, RemarkType, Value,
 };
 
+/// The maximum number of threads retained in an event.
+///
+/// When trimming, crashed and main threads are retained first.
+const MAX_THREADS: usize = 100;
+
 #[derive(Clone, Debug)]
 struct BagSizeState {
     bag_size: BagSize,
@@ -53,6 +58,23 @@ impl TrimmingProcessor {
 }
 
 impl Processor for TrimmingProcessor {
+    fn process_event(
+        &mut self,
+        event: &mut Event,
+        _meta: &mut Meta,
+        state: &ProcessingState<'_>,
+    ) -> ProcessingResult {
+        if let Some(threads) = event.threads.value_mut() {
+            threads
+                .values
+                .apply(|values, meta| enforce_thread_hard_limit(values, meta, MAX_THREADS))?;
+        }
+
+        event.process_child_values(self, state)?;
+
+        Ok(())
+    }
+
     fn before_process<T: ProcessValue>(
         &mut self,
         _: Option<&T>,
@@ -344,6 +366,34 @@ fn enforce_frame_hard_limit(
     Ok(())
 }
 
+/// Trims down the list of threads to a hard limit.
+///
+/// Large thread dumps are reordered so that the crashed thread comes first, followed by the main
+/// thread and the remaining threads in their original order. Threads beyond the limit are removed.
+fn enforce_thread_hard_limit(
+    threads: &mut Array<Thread>,
+    meta: &mut Meta,
+    limit: usize,
+) -> ProcessingResult {
+    let original_length = threads.len();
+    if original_length <= limit {
+        return Ok(());
+    }
+
+    let priority = |thread: &Annotated<Thread>| match thread.value() {
+        Some(thread) if thread.crashed.value() == Some(&true) => 0,
+        Some(thread) if thread.main.value() == Some(&true) => 1,
+        _ => 2,
+    };
+
+    // The sort is stable, so threads of equal priority retain their order.
+    threads.sort_by_key(priority);
+    threads.truncate(limit);
+    meta.set_original_length(Some(original_length));
+
+    Ok(())
+}
+
 /// Remove excess metadata for middle frames which go beyond `frame_allowance`.
 ///
 /// This is supposed to be equivalent to `slim_frame_data` in Sentry.
@@ -900,3 +950,31 @@ fn test_slim_frame_data_over_max() {
 
     assert_eq_dbg!(frames, expected);
 }
+
+#[test]
+fn test_thread_hard_limit_keeps_crashed_and_main() {
+    let thread = |name: &str, crashed: bool, main: bool| {
+        Annotated::new(Thread {
+            name: Annotated::new(name.to_string()),
+            crashed: Annotated::new(crashed),
+            main: Annotated::new(main),
+            ..Default::default()
+        })
+    };
+
+    let mut threads: Vec<_> = (0..10)
+        .map(|index| thread(&format!("worker-{}", index), false, false))
+        .collect();
+    threads.push(thread("main", false, true));
+    threads.push(thread("crashed", true, false));
+
+    let mut meta = Meta::default();
+    enforce_thread_hard_limit(&mut threads, &mut meta, 4).unwrap();
+
+    let names: Vec<_> = threads
+        .iter()
+        .map(|thread| thread.value().unwrap().name.as_str().unwrap())
+        .collect();
+    assert_eq_dbg!(names, vec!["crashed", "main", "worker-0", "worker-1"]);
+    assert_eq_dbg!(meta.original_length(), Some(12));
+}
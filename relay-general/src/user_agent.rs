@@ -1,5 +1,6 @@
 //! Utility functions for working with the event user-agent.
 use lazy_static::lazy_static;
+use regex::Regex;
 pub use uaparser::{Device, UserAgent, OS};
 use uaparser::{Parser, UserAgentParser};
 
@@ -15,15 +16,19 @@ lazy_static! {
              You are probably using a bad build of 'relay-common'. ",
         )
     };
+
+    /// A brand in the `Sec-CH-UA` structured header list: `"Google Chrome";v="110"`.
+    static ref SEC_CH_UA_BRAND_REGEX: Regex =
+        Regex::new(r#""(?P<brand>[^"]*)"\s*;\s*v\s*=\s*"(?P<version>[^"]*)""#).unwrap();
 }
 
 use crate::protocol::{Event, Headers};
 
-fn get_user_agent_from_headers(headers: &Headers) -> Option<&str> {
+fn get_header_ignore_case<'a>(headers: &'a Headers, name: &str) -> Option<&'a str> {
     for item in headers.iter() {
         if let Some((ref o_k, ref v)) = item.value() {
             if let Some(ref k) = o_k.as_str() {
-                if k.to_lowercase() == "user-agent" {
+                if k.eq_ignore_ascii_case(name) {
                     return v.as_str();
                 }
             }
@@ -33,6 +38,119 @@ fn get_user_agent_from_headers(headers: &Headers) -> Option<&str> {
     None
 }
 
+fn get_user_agent_from_headers(headers: &Headers) -> Option<&str> {
+    get_header_ignore_case(headers, "user-agent")
+}
+
+/// Removes the quotes around a structured header string, such as `"Windows"`.
+fn unquote(value: &str) -> &str {
+    let value = value.trim();
+    if value.len() >= 2 && value.starts_with('"') && value.ends_with('"') {
+        &value[1..value.len() - 1]
+    } else {
+        value
+    }
+}
+
+/// Returns `true` for the randomized placeholder brands browsers insert into `Sec-CH-UA`.
+fn is_grease_brand(brand: &str) -> bool {
+    brand.contains("Not") && brand.contains("Brand")
+}
+
+/// User-Agent Client Hints sent by Chromium based browsers.
+///
+/// These headers carry browser and platform versions that are no longer contained in the
+/// reduced `User-Agent` string. See <https://wicg.github.io/ua-client-hints/>.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct ClientHints<'a> {
+    /// `Sec-CH-UA`: Browser brands and their major versions.
+    pub sec_ch_ua: Option<&'a str>,
+    /// `Sec-CH-UA-Platform`: Name of the operating system.
+    pub sec_ch_ua_platform: Option<&'a str>,
+    /// `Sec-CH-UA-Platform-Version`: Version of the operating system.
+    pub sec_ch_ua_platform_version: Option<&'a str>,
+    /// `Sec-CH-UA-Mobile`: `?1` if the browser runs on a mobile device.
+    pub sec_ch_ua_mobile: Option<&'a str>,
+    /// `Sec-CH-UA-Model`: Model of the mobile device.
+    pub sec_ch_ua_model: Option<&'a str>,
+}
+
+impl<'a> ClientHints<'a> {
+    /// Reads client hints from request headers, ignoring the case of header names.
+    pub fn from_headers(headers: &'a Headers) -> Self {
+        ClientHints {
+            sec_ch_ua: get_header_ignore_case(headers, "sec-ch-ua"),
+            sec_ch_ua_platform: get_header_ignore_case(headers, "sec-ch-ua-platform"),
+            sec_ch_ua_platform_version: get_header_ignore_case(
+                headers,
+                "sec-ch-ua-platform-version",
+            ),
+            sec_ch_ua_mobile: get_header_ignore_case(headers, "sec-ch-ua-mobile"),
+            sec_ch_ua_model: get_header_ignore_case(headers, "sec-ch-ua-model"),
+        }
+    }
+
+    /// Returns the name and major version of the browser.
+    ///
+    /// Placeholder brands are skipped, and specific brands such as `Google Chrome` are preferred
+    /// over the generic `Chromium` brand.
+    pub fn browser(&self) -> Option<(&'a str, &'a str)> {
+        let brands = SEC_CH_UA_BRAND_REGEX
+            .captures_iter(self.sec_ch_ua?)
+            .filter_map(|captures| {
+                let brand = captures.name("brand")?.as_str().trim();
+                let version = captures.name("version")?.as_str().trim();
+                Some((brand, version))
+            })
+            .filter(|(brand, _)| !brand.is_empty() && !is_grease_brand(brand));
+
+        let mut chromium = None;
+        for (brand, version) in brands {
+            if brand == "Chromium" {
+                chromium = Some((brand, version));
+            } else {
+                return Some((brand, version));
+            }
+        }
+
+        chromium
+    }
+
+    /// Returns the name of the operating system.
+    pub fn platform(&self) -> Option<&'a str> {
+        Some(unquote(self.sec_ch_ua_platform?)).filter(|p| !p.is_empty() && *p != "Unknown")
+    }
+
+    /// Returns the version of the operating system.
+    pub fn platform_version(&self) -> Option<&'a str> {
+        Some(unquote(self.sec_ch_ua_platform_version?)).filter(|v| !v.is_empty())
+    }
+
+    /// Returns whether the browser runs on a mobile device.
+    pub fn mobile(&self) -> Option<bool> {
+        match self.sec_ch_ua_mobile?.trim() {
+            "?1" => Some(true),
+            "?0" => Some(false),
+            _ => None,
+        }
+    }
+
+    /// Returns the model of the mobile device.
+    pub fn model(&self) -> Option<&'a str> {
+        Some(unquote(self.sec_ch_ua_model?)).filter(|m| !m.is_empty())
+    }
+}
+
+/// Returns the client hints from an event's request headers.
+pub fn get_client_hints(event: &Event) -> ClientHints<'_> {
+    event
+        .request
+        .value()
+        .and_then(|request| request.headers.value())
+        .map(ClientHints::from_headers)
+        .unwrap_or_default()
+}
+
 /// Returns the user agent from an event (if available) or None (if not present).
 pub fn get_user_agent(event: &Event) -> Option<&str> {
     let request = event.request.value()?;
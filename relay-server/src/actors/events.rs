@@ -12,13 +12,13 @@ use serde_json::Value as SerdeValue;
 
 use relay_common::{clone, metric, LogError};
 use relay_config::{Config, RelayMode};
-use relay_general::pii::PiiProcessor;
+use relay_general::pii::{PiiProcessor, PiiReport};
 use relay_general::processor::{process_value, ProcessingState};
 use relay_general::protocol::{
     Breadcrumb, Csp, Event, EventId, EventType, ExpectCt, ExpectStaple, Hpkp, LenientString,
-    Metrics, SecurityReportType, Values,
+    Metrics, SecurityReportType, SessionUpdate, Values,
 };
-use relay_general::types::{Annotated, Array, Object, ProcessingAction, Value};
+use relay_general::types::{Annotated, Array, Object, ProcessingAction, Remark, RemarkType, Value};
 use relay_quotas::RateLimits;
 use relay_redis::RedisPool;
 
@@ -38,10 +38,11 @@ use crate::utils::{self, FormDataIter, FutureExt};
 use {
     crate::actors::store::{StoreEnvelope, StoreError, StoreForwarder},
     crate::service::ServerErrorKind,
+    crate::utils::ReloadingGeoIpLookup,
     failure::ResultExt,
     relay_filter::FilterStatKey,
     relay_general::protocol::IpAddr,
-    relay_general::store::{GeoIpLookup, StoreConfig, StoreProcessor},
+    relay_general::store::{StoreConfig, StoreProcessor, TransformProcessor},
     relay_quotas::{DataCategory, RateLimiter, RateLimitingError},
 };
 
@@ -123,7 +124,7 @@ struct EventProcessor {
     #[cfg(feature = "processing")]
     rate_limiter: Option<RateLimiter>,
     #[cfg(feature = "processing")]
-    geoip_lookup: Option<Arc<GeoIpLookup>>,
+    geoip_lookup: Option<Arc<ReloadingGeoIpLookup>>,
 }
 
 impl EventProcessor {
@@ -131,7 +132,7 @@ impl EventProcessor {
     pub fn new(
         config: Arc<Config>,
         rate_limiter: Option<RateLimiter>,
-        geoip_lookup: Option<Arc<GeoIpLookup>>,
+        geoip_lookup: Option<Arc<ReloadingGeoIpLookup>>,
     ) -> Self {
         Self {
             config,
@@ -495,7 +496,7 @@ impl EventProcessor {
         envelope: &Envelope,
         project_state: &ProjectState,
     ) -> Result<(), ProcessingError> {
-        let geoip_lookup = self.geoip_lookup.as_deref();
+        let geoip_lookup = self.geoip_lookup.as_ref().map(|lookup| lookup.get());
         let key_id = project_state
             .get_public_key_config(&envelope.meta().public_key())
             .and_then(|k| Some(k.numeric_id?.to_string()));
@@ -518,15 +519,41 @@ impl EventProcessor {
             is_renormalize: Some(false),
             remove_other: Some(true),
             normalize_user_agent: Some(true),
+            demangle_symbols: Some(true),
+            parse_raw_stacktraces: Some(project_state.config.parse_raw_stacktraces),
+            breakdowns: project_state.config.breakdowns.clone(),
+            repair_span_tree: Some(project_state.config.repair_span_tree),
+            transaction_names: project_state.config.transaction_names.clone(),
+            split_breadcrumb_urls: Some(
+                project_state.config.pii_config.is_some()
+                    || project_state.config.datascrubbing_settings.scrub_data,
+            ),
+            stacktrace_rules: Some(project_state.config.stacktrace_rules.clone()),
             sent_at: envelope.sent_at(),
         };
 
-        let mut store_processor = StoreProcessor::new(store_config, geoip_lookup);
+        let mut store_processor = StoreProcessor::new(store_config, geoip_lookup.as_deref());
         metric!(timer(RelayTimers::EventProcessingProcess), {
             process_value(event, &mut store_processor, ProcessingState::root())
                 .map_err(|_| ProcessingError::InvalidTransaction)?;
         });
 
+        for (issue, count) in store_processor.span_tree_issues() {
+            metric!(
+                counter(RelayCounters::SpanTreeIssues) += count as i64,
+                issue = issue.as_str()
+            );
+        }
+
+        // Fingerprinting rules are matched against the normalized event and override the
+        // fingerprint sent by the SDK.
+        let fingerprinting_rules = &project_state.config.fingerprinting_rules;
+        if let Some(event) = event.value_mut() {
+            if !fingerprinting_rules.is_empty() {
+                fingerprinting_rules.apply(event);
+            }
+        }
+
         // Event filters assume a normalized event. Unfortunately, this requires us to run
         // expensive normalization first.
         if let Some(event) = event.value_mut() {
@@ -551,6 +578,183 @@ impl EventProcessor {
         Ok(())
     }
 
+    /// Emits metrics and a log entry for the matches of a PII dry run.
+    fn report_pii_dry_run(&self, envelope: &Envelope, report: &PiiReport) {
+        if report.is_empty() {
+            return;
+        }
+
+        for (&rule_type, &count) in report.type_counts() {
+            metric!(
+                counter(RelayCounters::PiiDryRunMatches) += count as i64,
+                rule_type = rule_type
+            );
+        }
+
+        if let Ok(json) = serde_json::to_string(report) {
+            log::debug!(
+                "pii dry run for project {}: {}",
+                envelope.meta().project_id(),
+                json
+            );
+        }
+    }
+
+    /// Applies PII rules to the user identifiers of all session items in the envelope.
+    ///
+    /// Sessions are scrubbed with the rules of the project's PII config, so that pseudonymized user
+    /// ids in sessions and events still match. Data scrubbing settings do not apply to sessions.
+    fn process_sessions(
+        &self,
+        envelope: &mut Envelope,
+        project_state: &ProjectState,
+    ) -> Result<(), ProcessingError> {
+        // In dry runs, the PII config must not modify sessions, either.
+        if project_state.config.pii_dry_run {
+            return Ok(());
+        }
+
+        let config = match project_state.config.pii_config {
+            Some(ref config) => config,
+            None => return Ok(()),
+        };
+
+        let keys = self.config.pseudonymization_keys();
+        let compiled = config.compiled();
+
+        for item in envelope.items_mut() {
+            if item.ty() != ItemType::Session {
+                continue;
+            }
+
+            let mut session = match SessionUpdate::parse(&item.payload()) {
+                Ok(session) => session,
+                // Invalid sessions are skipped gracefully when they are stored.
+                Err(_) => continue,
+            };
+
+            let mut processor = PiiProcessor::new(&compiled).with_pseudonymization_keys(keys);
+            processor
+                .process_session(&mut session)
+                .map_err(ProcessingError::ProcessingFailed)?;
+
+            let payload = session
+                .serialize()
+                .map_err(ProcessingError::SerializeFailed)?;
+            item.set_payload(ContentType::Json, payload);
+        }
+
+        Ok(())
+    }
+
+    /// Applies PII rules to text attachments and minidumps selected by the PII config.
+    ///
+    /// See `scrub_attachment` for how attachments are modified.
+    fn process_attachments(&self, envelope: &mut Envelope, project_state: &ProjectState) {
+        let config = match project_state.config.pii_config {
+            Some(ref config) => config,
+            None => return,
+        };
+
+        let dry_run = project_state.config.pii_dry_run;
+        let compiled = config.compiled();
+        let mut processor = PiiProcessor::new(&compiled)
+            .with_pseudonymization_keys(self.config.pseudonymization_keys());
+        if dry_run {
+            processor = processor.with_report();
+        }
+
+        for item in envelope.items_mut() {
+            self.scrub_attachment(&mut processor, item, dry_run);
+        }
+
+        if let Some(report) = processor.take_report() {
+            self.report_pii_dry_run(envelope, &report);
+        }
+    }
+
+    /// Applies PII rules to a text attachment or minidump.
+    ///
+    /// Text attachments that are not valid UTF-8 are skipped. Text attachments exceeding the size
+    /// limit are skipped as well, which is recorded in their `pii_remarks` item header. Otherwise,
+    /// this header lists the rules that modified the attachment. Memory in minidumps is scrubbed
+    /// in place, so that the minidump remains valid for processing. Minidumps that cannot be
+    /// parsed are kept, but the rules are applied to the entire file since their memory cannot be
+    /// located. This is recorded with an `!invalid` remark.
+    fn scrub_attachment(&self, processor: &mut PiiProcessor<'_>, item: &mut Item, dry_run: bool) {
+        if item.ty() != ItemType::Attachment {
+            return;
+        }
+
+        let attachment_type = item.attachment_type().unwrap_or_default();
+        let filename = item.filename().unwrap_or_default().to_owned();
+
+        if attachment_type == AttachmentType::Minidump {
+            if !processor.selects_minidump(&filename) {
+                return;
+            }
+
+            let mut data = item.payload().to_vec();
+            let remarks = match processor.scrub_minidump(&filename, &mut data) {
+                Ok(remarks) => remarks,
+                Err(error) => {
+                    metric!(counter(RelayCounters::MinidumpScrubbingFailed) += 1);
+                    log::debug!("failed to scrub minidump: {}", LogError(&error));
+
+                    let mut remarks = processor.scrub_unparseable_minidump(&filename, &mut data);
+                    remarks.push(Remark::new(RemarkType::Annotated, "!invalid"));
+                    remarks
+                }
+            };
+
+            if !remarks.is_empty() && !dry_run {
+                let content_type = item
+                    .content_type()
+                    .cloned()
+                    .unwrap_or(ContentType::Minidump);
+                item.set_payload(content_type, data);
+                add_pii_remarks(item, &remarks);
+            }
+
+            return;
+        }
+
+        // Other attachment types are binary or consumed by processing.
+        match attachment_type {
+            AttachmentType::Attachment | AttachmentType::UnrealLogs => (),
+            _ => return,
+        }
+
+        if !processor.selects_attachment(attachment_type.as_str(), &filename) {
+            return;
+        }
+
+        if item.len() > self.config.max_scrubbed_attachment_size() {
+            if !dry_run {
+                let remark = Remark::new(RemarkType::Annotated, "!limit");
+                add_pii_remarks(item, &[remark]);
+            }
+            return;
+        }
+
+        // Scrub the text directly from the payload, so that only the output is allocated.
+        let payload = item.payload();
+        let text = match std::str::from_utf8(&payload) {
+            Ok(text) => text,
+            Err(_) => return,
+        };
+
+        let scrubbed = processor.scrub_attachment_text(attachment_type.as_str(), &filename, text);
+        let (text, remarks) = match scrubbed {
+            Some(scrubbed) if !dry_run => scrubbed,
+            _ => return,
+        };
+
+        let content_type = item.content_type().cloned().unwrap_or(ContentType::Text);
+        item.set_payload(content_type, text);
+        add_pii_remarks(item, &remarks);
+    }
+
     /// Checks for duplicate items in an envelope.
     ///
     /// An item is considered duplicate if it was not removed by sanitation in `process_event` and
@@ -608,6 +812,14 @@ impl EventProcessor {
             }
         }
 
+        // Session updates are not part of the event. Scrub them before processing of
+        // event-less envelopes stops below.
+        self.process_sessions(&mut envelope, &message.project_state)?;
+
+        // Attachments are forwarded even without an event, so they are scrubbed here, too. This
+        // runs after expanding Unreal reports to include their logs.
+        self.process_attachments(&mut envelope, &message.project_state);
+
         // Carry metrics on event sizes through the entire normalization process. Without
         // processing, this value is unused and will be optimized away. Note how we need to extract
         // sizes at different stages of processing and apply them after `store_process_event`.
@@ -666,6 +878,14 @@ impl EventProcessor {
             // id was ingested, this will already be the case. Otherwise, this will insert a new
             // event id. To be defensive, we always overwrite to ensure consistency.
             event.id = Annotated::new(event_id);
+
+            // Enrichment runs before normalization, so that the added tags are subject to the same
+            // limits as tags sent by the SDK. Project-level values take precedence.
+            let enrichment_configs = [
+                self.config.enrichment(),
+                &message.project_state.config.enrichment,
+            ];
+            utils::enrich_event(event, envelope.meta(), &enrichment_configs);
         } else {
             // If we have an envelope without event at this point, we are done with processing. This
             // envelope only contains attachments or user reports. We should not run filters or
@@ -684,15 +904,39 @@ impl EventProcessor {
                 // during processing is overwritten at last.
                 event._metrics = Annotated::new(_metrics);
             }
+
+            // Transformation rules run after normalization and before PII stripping, so that PII
+            // rules also apply to transformed data.
+            let transform_rules = &message.project_state.config.transform_rules;
+            if !transform_rules.is_empty() {
+                let mut processor = TransformProcessor::new(transform_rules);
+                process_value(&mut event, &mut processor, ProcessingState::root())
+                    .map_err(ProcessingError::ProcessingFailed)?;
+            }
         }
 
         // Run PII stripping last since normalization can add PII (e.g. IP addresses).
         metric!(timer(RelayTimers::EventProcessingPii), {
+            let keys = self.config.pseudonymization_keys();
+
             if let Some(ref config) = message.project_state.config.pii_config {
                 let compiled = config.compiled();
-                let mut processor = PiiProcessor::new(&compiled);
-                process_value(&mut event, &mut processor, ProcessingState::root())
-                    .map_err(ProcessingError::ProcessingFailed)?;
+                let mut processor = PiiProcessor::new(&compiled).with_pseudonymization_keys(keys);
+
+                if message.project_state.config.pii_dry_run {
+                    // Process a copy to record matches and leave the original event untouched.
+                    let mut processor = processor.with_report();
+                    let mut event = event.clone();
+                    process_value(&mut event, &mut processor, ProcessingState::root())
+                        .map_err(ProcessingError::ProcessingFailed)?;
+
+                    if let Some(report) = processor.take_report() {
+                        self.report_pii_dry_run(&envelope, &report);
+                    }
+                } else {
+                    process_value(&mut event, &mut processor, ProcessingState::root())
+                        .map_err(ProcessingError::ProcessingFailed)?;
+                }
             }
 
             let config = message
@@ -704,7 +948,7 @@ impl EventProcessor {
             if let Some(ref config) = *config {
                 let compiled = config.compiled();
 
-                let mut processor = PiiProcessor::new(&compiled);
+                let mut processor = PiiProcessor::new(&compiled).with_pseudonymization_keys(keys);
                 process_value(&mut event, &mut processor, ProcessingState::root())
                     .map_err(ProcessingError::ProcessingFailed)?;
             }
@@ -730,6 +974,23 @@ impl EventProcessor {
     }
 }
 
+/// Records PII remarks of an attachment in its item headers.
+///
+/// Remarks of previous PII configs applied to the same attachment are retained.
+fn add_pii_remarks(item: &mut Item, remarks: &[Remark]) {
+    let mut merged = match item.get_header("pii_remarks") {
+        Some(Value::Array(previous)) => previous.clone(),
+        _ => Array::new(),
+    };
+
+    let json = serde_json::to_value(remarks).unwrap_or_default();
+    if let Some(Value::Array(remarks)) = Annotated::<Value>::from(json).0 {
+        merged.extend(remarks);
+    }
+
+    item.set_header("pii_remarks", Value::Array(merged));
+}
+
 impl Actor for EventProcessor {
     type Context = SyncContext<Self>;
 }
@@ -791,7 +1052,8 @@ impl EventManager {
         let processor = {
             let geoip_lookup = match config.geoip_path() {
                 Some(p) => Some(Arc::new(
-                    GeoIpLookup::open(p).context(ServerErrorKind::GeoIpError)?,
+                    ReloadingGeoIpLookup::open(p, config.geoip_asn_path())
+                        .context(ServerErrorKind::GeoIpError)?,
                 )),
                 None => None,
             };
@@ -935,6 +1197,7 @@ impl Handler<HandleEnvelope> for EventManager {
         let outcome_producer = self.outcome_producer.clone();
         let captured_events = self.captured_events.clone();
         let capture = self.config.relay_mode() == RelayMode::Capture;
+        let config = self.config.clone();
 
         #[cfg(feature = "processing")]
         let store_forwarder = self.store_forwarder.clone();
@@ -1039,6 +1302,20 @@ impl Handler<HandleEnvelope> for EventManager {
                             builder.header("User-Agent", user_agent);
                         }
 
+                        // Captured headers are signed, so that the upstream can tell them apart
+                        // from headers set by clients. Without credentials, they cannot be
+                        // forwarded.
+                        if let Some(credentials) = config.credentials() {
+                            let signed = meta
+                                .sign_headers(&credentials.secret_key)
+                                .map_err(failure::Error::from)?;
+                            if let Some((json, signature)) = signed {
+                                builder
+                                    .header("X-Sentry-Relay-Headers", json)
+                                    .header("X-Sentry-Relay-Headers-Signature", signature);
+                            }
+                        }
+
                         builder
                             .header("X-Sentry-Auth", meta.auth_header())
                             .header("X-Forwarded-For", meta.forwarded_for())
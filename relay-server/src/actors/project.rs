@@ -9,9 +9,12 @@ use url::Url;
 
 use relay_auth::PublicKey;
 use relay_common::{metric, ProjectId, Uuid};
-use relay_config::{Config, RelayMode};
+use relay_config::{Config, EnrichmentConfig, RelayMode};
 use relay_filter::{matches_any_origin, FiltersConfig};
 use relay_general::pii::{DataScrubbingConfig, PiiConfig};
+use relay_general::store::{
+    BreakdownsConfig, FingerprintingRules, StacktraceRules, TransactionNameConfig, TransformRules,
+};
 use relay_quotas::{DataCategory, Quota, RateLimits, Scoping};
 
 use crate::actors::outcome::DiscardReason;
@@ -51,6 +54,10 @@ pub enum PublicKeyStatus {
     Enabled,
 }
 
+fn is_false(value: &bool) -> bool {
+    !*value
+}
+
 /// These are config values that the user can modify in the UI.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(default, rename_all = "camelCase")]
@@ -61,6 +68,11 @@ pub struct ProjectConfig {
     pub trusted_relays: Vec<PublicKey>,
     /// Configuration for PII stripping.
     pub pii_config: Option<PiiConfig>,
+    /// Only record what `pii_config` would redact without modifying events.
+    ///
+    /// Matches are reported as metrics and logs. Data scrubbing settings are still enforced.
+    #[serde(default, skip_serializing_if = "is_false")]
+    pub pii_dry_run: bool,
     /// The grouping configuration.
     #[serde(skip_serializing_if = "Option::is_none")]
     pub grouping_config: Option<Value>,
@@ -75,6 +87,30 @@ pub struct ProjectConfig {
     pub event_retention: Option<u16>,
     #[serde(skip_serializing_if = "Vec::is_empty")]
     pub quotas: Vec<Quota>,
+    /// Parse stack traces pasted into messages of events without exceptions.
+    #[serde(default, skip_serializing_if = "is_false")]
+    pub parse_raw_stacktraces: bool,
+    /// Breakdowns of the transaction duration computed from spans.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub breakdowns: Option<BreakdownsConfig>,
+    /// Repair span trees of transactions with orphaned, cyclic or out-of-bounds spans.
+    #[serde(default, skip_serializing_if = "is_false")]
+    pub repair_span_tree: bool,
+    /// Rules for replacing identifiers in URL-like transaction names.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub transaction_names: Option<TransactionNameConfig>,
+    /// Rules that override the fingerprint of matching events.
+    #[serde(skip_serializing_if = "FingerprintingRules::is_empty")]
+    pub fingerprinting_rules: FingerprintingRules,
+    /// Rules that mark stack trace frames as in-app and assign frame categories.
+    #[serde(skip_serializing_if = "StacktraceRules::is_empty")]
+    pub stacktrace_rules: StacktraceRules,
+    /// Rules that set, copy, move, delete or rewrite fields of events before PII scrubbing.
+    #[serde(skip_serializing_if = "TransformRules::is_empty")]
+    pub transform_rules: TransformRules,
+    /// Tags and context fields added to events from request metadata and static values.
+    #[serde(skip_serializing_if = "EnrichmentConfig::is_empty")]
+    pub enrichment: EnrichmentConfig,
 }
 
 impl Default for ProjectConfig {
@@ -83,11 +119,20 @@ impl Default for ProjectConfig {
             allowed_domains: vec!["*".to_string()],
             trusted_relays: vec![],
             pii_config: None,
+            pii_dry_run: false,
             grouping_config: None,
             filter_settings: FiltersConfig::default(),
             datascrubbing_settings: DataScrubbingConfig::default(),
             event_retention: None,
             quotas: Vec::new(),
+            parse_raw_stacktraces: false,
+            breakdowns: None,
+            repair_span_tree: false,
+            transaction_names: None,
+            fingerprinting_rules: FingerprintingRules::default(),
+            stacktrace_rules: StacktraceRules::default(),
+            transform_rules: TransformRules::default(),
+            enrichment: EnrichmentConfig::default(),
         }
     }
 }
@@ -99,6 +144,7 @@ pub struct LimitedProjectConfig {
     pub allowed_domains: Vec<String>,
     pub trusted_relays: Vec<PublicKey>,
     pub pii_config: Option<PiiConfig>,
+    pub pii_dry_run: bool,
     pub datascrubbing_settings: DataScrubbingConfig,
 }
 
@@ -287,6 +287,21 @@ pub enum AttachmentType {
     UnrealLogs,
 }
 
+impl AttachmentType {
+    /// Returns the name of this attachment type as used in item headers.
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Attachment => "event.attachment",
+            Self::Minidump => "event.minidump",
+            Self::AppleCrashReport => "event.applecrashreport",
+            Self::EventPayload => "event.payload",
+            Self::Breadcrumbs => "event.breadcrumbs",
+            Self::UnrealContext => "unreal.context",
+            Self::UnrealLogs => "unreal.logs",
+        }
+    }
+}
+
 impl Default for AttachmentType {
     fn default() -> Self {
         Self::Attachment
@@ -361,7 +376,6 @@ impl Item {
     }
 
     /// Returns the content type of this item's payload.
-    #[cfg_attr(not(feature = "processing"), allow(dead_code))]
     pub fn content_type(&self) -> Option<&ContentType> {
         self.headers.content_type.as_ref()
     }
@@ -401,7 +415,6 @@ impl Item {
     }
 
     /// Returns the file name of this item, if it is an attachment.
-    #[cfg_attr(not(feature = "processing"), allow(dead_code))]
     pub fn filename(&self) -> Option<&str> {
         self.headers.filename.as_deref()
     }
@@ -478,6 +491,7 @@ impl Item {
 
 pub type Items = SmallVec<[Item; 3]>;
 pub type ItemIter<'a> = std::slice::Iter<'a, Item>;
+pub type ItemIterMut<'a> = std::slice::IterMut<'a, Item>;
 
 #[derive(Clone, Debug, Deserialize, Serialize)]
 pub struct EnvelopeHeaders<M = RequestMeta> {
@@ -670,6 +684,11 @@ impl Envelope {
         self.items.iter()
     }
 
+    /// Returns a mutable iterator over items in this envelope.
+    pub fn items_mut(&mut self) -> ItemIterMut<'_> {
+        self.items.iter_mut()
+    }
+
     /// Returns the an option with a reference to the first item that matches
     /// the predicate, or None if the predicate is not matched by any item.
     pub fn get_item_by<F>(&self, mut pred: F) -> Option<&Item>
@@ -1070,6 +1089,18 @@ mod tests {
         assert_eq!(meta.dsn(), request_meta().dsn());
     }
 
+    #[test]
+    fn test_parse_request_ignores_headers() {
+        let bytes = Bytes::from("{\"event_id\":\"9ec79c33ec9942ab8353589fcb2e04dc\",\"headers\":{\"x-region\":\"spoofed\"}}");
+        let request_meta = request_meta().with_header("X-Tenant", "acme");
+        let envelope = Envelope::parse_request(bytes, request_meta).unwrap();
+        let meta = envelope.meta();
+
+        // Headers in the envelope are set by the client and must never be used for enrichment.
+        assert_eq!(meta.header("x-region"), None);
+        assert_eq!(meta.header("x-tenant"), Some("acme"));
+    }
+
     #[test]
     fn test_parse_request_no_origin() {
         let bytes = Bytes::from("{\"event_id\":\"9ec79c33ec9942ab8353589fcb2e04dc\",\"dsn\":\"https://e12d836b15bb49d7bbf99e64295d995b:@sentry.io/42\"}");
@@ -1144,4 +1175,22 @@ mod tests {
 
         "###);
     }
+
+    #[test]
+    fn test_attachment_type_as_str() {
+        let types = [
+            AttachmentType::Attachment,
+            AttachmentType::Minidump,
+            AttachmentType::AppleCrashReport,
+            AttachmentType::EventPayload,
+            AttachmentType::Breadcrumbs,
+            AttachmentType::UnrealContext,
+            AttachmentType::UnrealLogs,
+        ];
+
+        for ty in &types {
+            let serialized = serde_json::to_string(ty).unwrap();
+            assert_eq!(serialized, format!("\"{}\"", ty.as_str()));
+        }
+    }
 }
@@ -1,24 +1,44 @@
+use std::collections::BTreeMap;
 use std::net::IpAddr;
 
 use actix::ResponseFuture;
 use actix_web::dev::AsyncResult;
 use actix_web::http::header;
 use actix_web::{FromRequest, HttpMessage, HttpRequest, HttpResponse, ResponseError};
+use chrono::Duration;
 use failure::Fail;
 use futures::{future, Future};
 use serde::{Deserialize, Serialize};
 use url::Url;
 
+use relay_auth::{RelayId, SecretKey};
 use relay_common::{
     tryf, Auth, Dsn, ParseAuthError, ParseDsnError, ParseProjectIdError, ProjectId,
 };
 use relay_quotas::Scoping;
 
 use crate::actors::project_keys::GetProjectId;
+use crate::actors::relays::GetRelay;
 use crate::extractors::ForwardedFor;
 use crate::service::ServiceState;
 use crate::utils::ApiErrorResponse;
 
+/// Maximum age of signed headers forwarded by a downstream Relay.
+///
+/// Headers are signed right before the request is sent, so this only needs to account for retries
+/// and clock drift between Relays.
+const FORWARDED_HEADERS_MAX_AGE_MINUTES: i64 = 15;
+
+/// Payload of the `X-Sentry-Relay-Headers` request header.
+///
+/// The captured headers are signed together with the public key of the request, so that they
+/// cannot be replayed onto requests for other projects.
+#[derive(Debug, Deserialize, Serialize)]
+struct ForwardedHeaders<H> {
+    public_key: String,
+    headers: H,
+}
+
 #[derive(Debug, Fail)]
 pub enum BadEventMeta {
     #[fail(display = "missing authorization information")]
@@ -87,6 +107,13 @@ pub struct RequestMeta<D = Dsn> {
     /// The user agent that sent this event.
     #[serde(default, skip_serializing_if = "Option::is_none")]
     user_agent: Option<String>,
+
+    /// Request headers captured for enrichment, keyed by their lowercase name.
+    ///
+    /// These are never read from or written to envelope headers, since clients could set them
+    /// freely. Downstream Relays forward them in signed request headers instead.
+    #[serde(skip)]
+    headers: BTreeMap<String, String>,
 }
 
 impl<D> RequestMeta<D> {
@@ -132,6 +159,19 @@ impl<D> RequestMeta<D> {
     pub fn user_agent(&self) -> Option<&str> {
         self.user_agent.as_deref()
     }
+
+    /// Returns the value of a request header captured for enrichment.
+    ///
+    /// Only headers listed in the enrichment config of the Relay that received the request are
+    /// available. Header names are case-insensitive.
+    pub fn header(&self, name: &str) -> Option<&str> {
+        self.headers.get(&name.to_lowercase()).map(String::as_str)
+    }
+
+    /// Returns all request headers captured for enrichment.
+    pub fn headers(&self) -> &BTreeMap<String, String> {
+        &self.headers
+    }
 }
 
 impl RequestMeta {
@@ -145,9 +185,16 @@ impl RequestMeta {
             remote_addr: Some("192.168.0.1".parse().unwrap()),
             forwarded_for: String::new(),
             user_agent: Some("sentry/agent".to_string()),
+            headers: BTreeMap::new(),
         }
     }
 
+    #[cfg(test)]
+    pub fn with_header(mut self, name: &str, value: &str) -> Self {
+        self.headers.insert(name.to_lowercase(), value.to_owned());
+        self
+    }
+
     /// Returns a reference to the DSN.
     ///
     /// The DSN declares the project and auth information and upstream address. When RequestMeta is
@@ -184,6 +231,28 @@ impl RequestMeta {
         auth
     }
 
+    /// Serializes and signs the captured headers for forwarding them to the upstream.
+    ///
+    /// Returns the payload of the `X-Sentry-Relay-Headers` header and its signature, or `None` if
+    /// no headers were captured. The signature is only valid for the public key of this request and
+    /// expires after a while.
+    pub fn sign_headers(
+        &self,
+        secret_key: &SecretKey,
+    ) -> Result<Option<(String, String)>, serde_json::Error> {
+        if self.headers.is_empty() {
+            return Ok(None);
+        }
+
+        let json = serde_json::to_string(&ForwardedHeaders {
+            public_key: self.public_key().to_owned(),
+            headers: &self.headers,
+        })?;
+
+        let signature = secret_key.sign(json.as_bytes());
+        Ok(Some((json, signature)))
+    }
+
     /// Returns scoping information from the request.
     ///
     /// The scoping returned from this function is not complete since it lacks info from the Project
@@ -211,7 +280,8 @@ impl PartialMeta {
 
     /// Completes missing information with complete `RequestMeta`.
     ///
-    /// All fields that set in this instance will remain.
+    /// All fields that set in this instance will remain. Captured headers are never taken from the
+    /// partial meta, since it is supplied by the client.
     pub fn copy_to(self, mut complete: RequestMeta) -> RequestMeta {
         // DSN needs to be validated by the caller and will not be copied over.
 
@@ -283,6 +353,72 @@ fn parse_header_url<T>(req: &HttpRequest<T>, header: header::HeaderName) -> Opti
         })
 }
 
+/// Extracts enrichment headers forwarded by a downstream Relay.
+///
+/// Downstream Relays send the headers they captured in `X-Sentry-Relay-Headers`, signed with their
+/// credentials. They are only accepted from Relays known to the upstream with a valid and recent
+/// signature, if they were signed for the same public key as the request, and only for header
+/// names listed in this Relay's enrichment config. In all other cases, the forwarded headers are
+/// ignored.
+fn extract_forwarded_headers(
+    request: &HttpRequest<ServiceState>,
+    public_key: &str,
+) -> ResponseFuture<BTreeMap<String, String>, BadEventMeta> {
+    let get_header = |name| request.headers().get(name).and_then(|h| h.to_str().ok());
+
+    let relay_id = get_header("X-Sentry-Relay-Id").and_then(|id| id.parse::<RelayId>().ok());
+    let json = get_header("X-Sentry-Relay-Headers");
+    let signature = get_header("X-Sentry-Relay-Headers-Signature");
+
+    let (relay_id, json, signature) = match (relay_id, json, signature) {
+        (Some(relay_id), Some(json), Some(signature)) => {
+            (relay_id, json.to_owned(), signature.to_owned())
+        }
+        _ => return Box::new(future::ok(BTreeMap::new())),
+    };
+
+    let allowed_headers = request.state().config().enrichment_headers();
+    if allowed_headers.is_empty() {
+        return Box::new(future::ok(BTreeMap::new()));
+    }
+
+    let public_key = public_key.to_owned();
+
+    let future = request
+        .state()
+        .relay_cache()
+        .send(GetRelay { relay_id })
+        .map_err(|_| BadEventMeta::ScheduleFailed)
+        .map(move |result| {
+            let relay = match result.ok().and_then(|result| result.relay) {
+                Some(relay) => relay,
+                None => return BTreeMap::new(),
+            };
+
+            let max_age = Some(Duration::minutes(FORWARDED_HEADERS_MAX_AGE_MINUTES));
+            let unpacked = relay
+                .public_key
+                .unpack(json.as_bytes(), &signature, max_age);
+
+            // Headers signed for another project key are replayed and must be ignored.
+            let headers: BTreeMap<String, String> = match unpacked {
+                Ok(ForwardedHeaders {
+                    public_key: key,
+                    headers,
+                }) if key == public_key => headers,
+                _ => return BTreeMap::new(),
+            };
+
+            headers
+                .into_iter()
+                .map(|(name, value)| (name.to_lowercase(), value))
+                .filter(|(name, _)| allowed_headers.contains(name))
+                .collect()
+        });
+
+    Box::new(future)
+}
+
 fn extract_event_meta(
     request: &HttpRequest<ServiceState>,
 ) -> ResponseFuture<RequestMeta, BadEventMeta> {
@@ -303,6 +439,17 @@ fn extract_event_meta(
     let state = request.state();
     let config = state.config();
 
+    let mut headers: BTreeMap<_, _> = config
+        .enrichment_headers()
+        .into_iter()
+        .filter_map(|name| {
+            let value = request.headers().get(name.as_str())?.to_str().ok()?;
+            Some((name, value.to_owned()))
+        })
+        .collect();
+
+    let forwarded_future = extract_forwarded_headers(request, auth.public_key());
+
     let project_future = match request.match_info().get("project") {
         Some(s) => {
             // The project_id was declared in the URL. Use it directly.
@@ -324,7 +471,13 @@ fn extract_event_meta(
         }
     };
 
-    Box::new(project_future.and_then(move |project_id| {
+    let future = project_future.join(forwarded_future);
+    Box::new(future.and_then(move |(project_id, forwarded_headers)| {
+        // Headers captured from this request take precedence over forwarded ones.
+        for (name, value) in forwarded_headers {
+            headers.entry(name).or_insert(value);
+        }
+
         let upstream = config.upstream_descriptor();
 
         let dsn_string = format!(
@@ -343,6 +496,7 @@ fn extract_event_meta(
             remote_addr,
             forwarded_for,
             user_agent,
+            headers,
         })
     }))
 }
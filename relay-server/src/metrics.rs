@@ -219,6 +219,18 @@ pub enum RelayCounters {
     /// We are scanning our in-memory project cache for stale entries. This counter is incremented
     /// before doing the expensive operation.
     EvictingStaleProjectCaches,
+    /// Counts the values that a PII config in dry run mode would have redacted. The counter has a
+    /// `rule_type` tag with the type of the matching rule, such as `ip` or `pattern`. Matches per
+    /// rule id are only logged.
+    PiiDryRunMatches,
+    /// Counts minidumps that could not be parsed for PII scrubbing. PII rules are applied to the
+    /// entire file of these minidumps instead.
+    MinidumpScrubbingFailed,
+    /// Counts problems found in the span trees of transactions during store normalization. The
+    /// counter has an `issue` tag, which is one of `missing_parent`, `cycle`, `trace_id_mismatch`
+    /// or `out_of_bounds`.
+    #[cfg(feature = "processing")]
+    SpanTreeIssues,
 }
 
 impl CounterMetric for RelayCounters {
@@ -240,6 +252,10 @@ impl CounterMetric for RelayCounters {
             RelayCounters::Requests => "requests",
             RelayCounters::ResponsesStatusCodes => "responses.status_codes",
             RelayCounters::EvictingStaleProjectCaches => "project_cache.eviction",
+            RelayCounters::PiiDryRunMatches => "event.pii.dry_run_matches",
+            RelayCounters::MinidumpScrubbingFailed => "event.pii.minidump_scrubbing_failed",
+            #[cfg(feature = "processing")]
+            RelayCounters::SpanTreeIssues => "event.transaction.span_tree_issues",
         }
     }
 }
@@ -0,0 +1,239 @@
+use std::collections::BTreeMap;
+
+use relay_config::{EnrichmentConfig, EnrichmentValue, RequestField};
+use relay_general::protocol::{Context, Contexts, Event, Tags};
+use relay_general::types::{Annotated, FromValue, Object, Value};
+
+use crate::extractors::RequestMeta;
+
+/// A resolved value along with the precedence over values sent by the SDK.
+type Resolved<'a> = (&'a str, bool);
+
+fn resolve<'a>(value: &'a EnrichmentValue, meta: &'a RequestMeta) -> Option<&'a str> {
+    let value = match value {
+        EnrichmentValue::Static(value) => value.as_str(),
+        EnrichmentValue::Header { header } => meta.header(header)?,
+        EnrichmentValue::Request { request } => match request {
+            RequestField::Client => meta.client()?,
+            RequestField::UserAgent => meta.user_agent()?,
+            RequestField::Origin => meta.origin()?.as_str(),
+        },
+    };
+
+    Some(value.trim()).filter(|value| !value.is_empty())
+}
+
+/// Returns `true` if contexts under the given key are parsed into a structured context.
+///
+/// This mirrors how `Contexts` infers the context type from its key.
+fn is_structured_context(name: &str) -> bool {
+    let mut object = Object::new();
+    let ty = Annotated::new(Value::String(name.to_owned()));
+    object.insert("type".to_owned(), ty);
+
+    match Context::from_value(Annotated::new(Value::Object(object))).value() {
+        Some(Context::Other(_)) | None => false,
+        Some(_) => true,
+    }
+}
+
+/// Adds tags and context fields from request metadata and static values to the event.
+///
+/// Configs are applied in order, so that values of later configs replace values of earlier configs
+/// for the same tag or context field. Values sent by the SDK are only replaced if the config that
+/// provides the final value sets `overwrite`. Context fields can only be added to custom contexts.
+///
+/// Added tags are not validated here. Store normalization applies the same limits to them as to
+/// tags sent by the SDK.
+pub fn enrich_event(event: &mut Event, meta: &RequestMeta, configs: &[&EnrichmentConfig]) {
+    let mut tags = BTreeMap::<&str, Resolved<'_>>::new();
+    let mut contexts = BTreeMap::<&str, BTreeMap<&str, Resolved<'_>>>::new();
+
+    for config in configs {
+        for (key, value) in &config.tags {
+            if let Some(value) = resolve(value, meta) {
+                tags.insert(key, (value, config.overwrite));
+            }
+        }
+
+        for (name, fields) in &config.contexts {
+            for (field, value) in fields {
+                if let Some(value) = resolve(value, meta) {
+                    let context = contexts.entry(name.as_str()).or_default();
+                    context.insert(field, (value, config.overwrite));
+                }
+            }
+        }
+    }
+
+    for (key, (value, overwrite)) in tags {
+        let tags = &mut event.tags.get_or_insert_with(Tags::default).0;
+        if overwrite || !tags.contains(key) {
+            tags.insert(key.to_owned(), Annotated::new(value.to_owned()));
+        }
+    }
+
+    for (name, fields) in contexts {
+        let exists = event
+            .contexts
+            .value()
+            .and_then(|contexts| contexts.get(name))
+            .map_or(false, |context| context.value().is_some());
+
+        // Known context keys must not be created as custom contexts, since they would no longer
+        // parse into the structured context.
+        if !exists && is_structured_context(name) {
+            log::debug!("skipping enrichment of structured context {}", name);
+            continue;
+        }
+
+        let contexts = event.contexts.get_or_insert_with(Contexts::new);
+        let context = contexts.get_or_insert_with(name, || Context::Other(Object::new()));
+
+        let object = match context {
+            Context::Other(object) => object,
+            _ => {
+                log::debug!("skipping enrichment of structured context {}", name);
+                continue;
+            }
+        };
+
+        for (field, (value, overwrite)) in fields {
+            if overwrite || !object.contains_key(field) {
+                let value = Annotated::new(Value::String(value.to_owned()));
+                object.insert(field.to_owned(), value);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn request_meta() -> RequestMeta {
+        let dsn = "https://e12d836b15bb49d7bbf99e64295d995b:@sentry.io/42"
+            .parse()
+            .unwrap();
+
+        RequestMeta::new(dsn)
+            .with_header("X-Region", "eu-west-1")
+            .with_header("X-Tenant", "acme")
+    }
+
+    fn config(json: &str) -> EnrichmentConfig {
+        serde_json::from_str(json).unwrap()
+    }
+
+    fn tag<'a>(event: &'a Event, key: &str) -> Option<&'a str> {
+        event
+            .tags
+            .value()?
+            .iter()
+            .filter_map(Annotated::value)
+            .find(|entry| entry.0.as_str() == Some(key))
+            .and_then(|entry| entry.1.as_str())
+    }
+
+    #[test]
+    fn test_enrich_tags() {
+        let relay = config(
+            r#"{"tags": {
+                "region": {"header": "X-Region"},
+                "datacenter": "fra1",
+                "agent": {"request": "user_agent"},
+                "missing": {"header": "X-Missing"}
+            }}"#,
+        );
+
+        let mut event = Event::default();
+        enrich_event(&mut event, &request_meta(), &[&relay]);
+
+        assert_eq!(tag(&event, "region"), Some("eu-west-1"));
+        assert_eq!(tag(&event, "datacenter"), Some("fra1"));
+        assert_eq!(tag(&event, "agent"), Some("sentry/agent"));
+        assert_eq!(tag(&event, "missing"), None);
+    }
+
+    #[test]
+    fn test_precedence() {
+        let relay =
+            config(r#"{"tags": {"region": "relay", "tenant": "relay"}, "overwrite": true}"#);
+        let project = config(r#"{"tags": {"tenant": {"header": "x-tenant"}}}"#);
+
+        let mut event = Annotated::<Event>::from_json(
+            r#"{"tags": {"region": "sdk", "tenant": "sdk", "team": "sdk"}}"#,
+        )
+        .unwrap()
+        .into_value()
+        .unwrap();
+
+        enrich_event(&mut event, &request_meta(), &[&relay, &project]);
+
+        // The relay config overwrites SDK tags, but the project config takes precedence over the
+        // relay config and does not overwrite.
+        assert_eq!(tag(&event, "region"), Some("relay"));
+        assert_eq!(tag(&event, "tenant"), Some("sdk"));
+        assert_eq!(tag(&event, "team"), Some("sdk"));
+    }
+
+    #[test]
+    fn test_empty_tags() {
+        let relay = config(
+            r#"{"tags": {
+                "empty": "",
+                "blank": "  ",
+                "missing": {"header": "X-Missing"}
+            }}"#,
+        );
+
+        let mut event = Event::default();
+        enrich_event(&mut event, &request_meta(), &[&relay]);
+        assert!(event.tags.value().is_none());
+    }
+
+    #[test]
+    fn test_enrich_contexts() {
+        let relay = config(
+            r#"{"contexts": {
+                "relay": {"region": {"header": "x-region"}, "datacenter": "fra1"},
+                "os": {"region": "ignored"}
+            }}"#,
+        );
+
+        let mut event = Annotated::<Event>::from_json(r#"{"contexts": {"os": {"name": "Linux"}}}"#)
+            .unwrap()
+            .into_value()
+            .unwrap();
+
+        enrich_event(&mut event, &request_meta(), &[&relay]);
+
+        let contexts = event.contexts.value().unwrap();
+        match contexts["relay"].value().map(|context| &context.0) {
+            Some(Context::Other(object)) => {
+                assert_eq!(object["region"].as_str(), Some("eu-west-1"));
+                assert_eq!(object["datacenter"].as_str(), Some("fra1"));
+            }
+            other => panic!("unexpected context: {:?}", other),
+        }
+
+        match contexts["os"].value().map(|context| &context.0) {
+            Some(Context::Os(os)) => assert!(os.other.get("region").is_none()),
+            other => panic!("unexpected context: {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_skip_missing_structured_contexts() {
+        let relay = config(
+            r#"{"contexts": {
+                "os": {"region": {"header": "x-region"}},
+                "browser": {"region": "ignored"}
+            }}"#,
+        );
+
+        let mut event = Event::default();
+        enrich_event(&mut event, &request_meta(), &[&relay]);
+        assert!(event.contexts.value().is_none());
+    }
+}
@@ -0,0 +1,100 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::{Duration, Instant, SystemTime};
+
+use parking_lot::{Mutex, RwLock};
+
+use relay_common::LogError;
+use relay_general::store::{GeoIpError, GeoIpLookup};
+
+/// Minimum interval between two checks for modified database files.
+const CHECK_INTERVAL: Duration = Duration::from_secs(10);
+
+/// Modification times of the city and ASN database files.
+type ModificationTimes = (Option<SystemTime>, Option<SystemTime>);
+
+fn modified(path: &Path) -> Option<SystemTime> {
+    fs::metadata(path).and_then(|m| m.modified()).ok()
+}
+
+fn modification_times(city_path: &Path, asn_path: Option<&Path>) -> ModificationTimes {
+    (modified(city_path), asn_path.and_then(modified))
+}
+
+fn open_lookup(city_path: &Path, asn_path: Option<&Path>) -> Result<GeoIpLookup, GeoIpError> {
+    let lookup = GeoIpLookup::open(city_path)?;
+    match asn_path {
+        Some(asn_path) => lookup.with_asn_database(asn_path),
+        None => Ok(lookup),
+    }
+}
+
+#[derive(Debug)]
+struct ReloadState {
+    checked: Instant,
+    modified: ModificationTimes,
+}
+
+/// A GeoIP lookup that reopens its databases when the files change on disk.
+///
+/// Modification times are checked at most once every ten seconds. If reopening fails, for instance
+/// because a file is only partially written, the previous databases remain in use and the reload
+/// is retried on the next check.
+pub struct ReloadingGeoIpLookup {
+    city_path: PathBuf,
+    asn_path: Option<PathBuf>,
+    lookup: RwLock<Arc<GeoIpLookup>>,
+    state: Mutex<ReloadState>,
+}
+
+impl ReloadingGeoIpLookup {
+    /// Opens the city database and optionally the ASN database at the given paths.
+    pub fn open(city_path: &Path, asn_path: Option<&Path>) -> Result<Self, GeoIpError> {
+        let modified = modification_times(city_path, asn_path);
+        let lookup = open_lookup(city_path, asn_path)?;
+
+        Ok(Self {
+            city_path: city_path.to_owned(),
+            asn_path: asn_path.map(Path::to_owned),
+            lookup: RwLock::new(Arc::new(lookup)),
+            state: Mutex::new(ReloadState {
+                checked: Instant::now(),
+                modified,
+            }),
+        })
+    }
+
+    fn check_reload(&self) {
+        // If another thread is already checking, continue with the current databases.
+        let mut state = match self.state.try_lock() {
+            Some(state) => state,
+            None => return,
+        };
+
+        if state.checked.elapsed() < CHECK_INTERVAL {
+            return;
+        }
+
+        state.checked = Instant::now();
+        let modified = modification_times(&self.city_path, self.asn_path.as_deref());
+        if modified == state.modified {
+            return;
+        }
+
+        match open_lookup(&self.city_path, self.asn_path.as_deref()) {
+            Ok(lookup) => {
+                *self.lookup.write() = Arc::new(lookup);
+                state.modified = modified;
+                log::info!("reloaded GeoIP databases");
+            }
+            Err(error) => log::error!("failed to reload GeoIP databases: {}", LogError(&error)),
+        }
+    }
+
+    /// Returns the current lookup, reloading the databases if they changed on disk.
+    pub fn get(&self) -> Arc<GeoIpLookup> {
+        self.check_reload();
+        self.lookup.read().clone()
+    }
+}
@@ -1,5 +1,6 @@
 mod actix;
 mod api;
+mod enrichment;
 mod error_boundary;
 mod multipart;
 mod param_parser;
@@ -8,11 +9,14 @@ mod request;
 mod shutdown;
 mod timer;
 
+#[cfg(feature = "processing")]
+mod geoip;
 #[cfg(feature = "processing")]
 mod unreal;
 
 pub use self::actix::*;
 pub use self::api::*;
+pub use self::enrichment::*;
 pub use self::error_boundary::*;
 pub use self::multipart::*;
 pub use self::param_parser::*;
@@ -21,5 +25,7 @@ pub use self::request::*;
 pub use self::shutdown::*;
 pub use self::timer::*;
 
+#[cfg(feature = "processing")]
+pub use self::geoip::*;
 #[cfg(feature = "processing")]
 pub use self::unreal::*;
@@ -9,10 +9,10 @@ use failure::{err_msg, Error};
 
 use relay_common::{LogError, Uuid};
 use relay_config::{Config, Credentials, MinimalConfig, OverridableConfig, RelayMode};
-use relay_general::pii::{PiiConfig, PiiProcessor};
+use relay_general::pii::{PiiConfig, PiiProcessor, PseudonymizationKeys};
 use relay_general::processor::{process_value, ProcessingState};
 use relay_general::protocol::Event;
-use relay_general::store::{StoreConfig, StoreProcessor};
+use relay_general::store::{FingerprintingRules, StoreConfig, StoreProcessor};
 use relay_general::types::Annotated;
 
 use crate::cliapp::make_app;
@@ -362,21 +362,59 @@ pub fn process_event<'a>(matches: &ArgMatches<'a>) -> Result<(), Error> {
         None
     };
 
+    let pseudonymization_keys = if let Some(path) = matches.value_of("pseudonymization_keys") {
+        let json_keys = fs::read_to_string(&path)?;
+        serde_json::from_str(&json_keys)?
+    } else {
+        PseudonymizationKeys::new()
+    };
+
+    let fingerprinting_rules = if let Some(path) = matches.value_of("fingerprinting_rules") {
+        let json_rules = fs::read_to_string(&path)?;
+        Some(serde_json::from_str::<FingerprintingRules>(&json_rules)?)
+    } else {
+        None
+    };
+
     let mut event_json = Vec::new();
     let stdin = io::stdin();
     stdin.lock().read_to_end(&mut event_json)?;
     let mut event = EventV8::from_json_bytes(&event_json[..])?;
-    if let Some(ref pii_config) = pii_config {
-        let compiled = pii_config.compiled();
-        let mut processor = PiiProcessor::new(&compiled);
-        process_value(&mut event, &mut processor, ProcessingState::root())?;
-    };
 
+    // Process in the same order as processing Relays: store normalization, fingerprinting and
+    // PII scrubbing last.
     if matches.is_present("store") {
         let mut processor = StoreProcessor::new(StoreConfig::default(), None);
         process_value(&mut event, &mut processor, ProcessingState::root())?;
     }
 
+    if let Some(ref rules) = fingerprinting_rules {
+        match event
+            .value_mut()
+            .as_mut()
+            .and_then(|event| rules.apply(event))
+        {
+            Some(index) => eprintln!("matched fingerprinting rule {}", index),
+            None => eprintln!("no fingerprinting rule matched"),
+        }
+    }
+
+    if let Some(ref pii_config) = pii_config {
+        let compiled = pii_config.compiled();
+        let mut processor =
+            PiiProcessor::new(&compiled).with_pseudonymization_keys(&pseudonymization_keys);
+
+        if matches.is_present("pii_dry_run") {
+            // Only report what would be redacted and leave the event untouched.
+            let mut processor = processor.with_report();
+            process_value(&mut event.clone(), &mut processor, ProcessingState::root())?;
+            let report = processor.take_report().unwrap_or_default();
+            eprintln!("{}", serde_json::to_string_pretty(&report)?);
+        } else {
+            process_value(&mut event, &mut processor, ProcessingState::root())?;
+        }
+    };
+
     if matches.is_present("debug") {
         println!("{:#?}", event);
     } else if matches.is_present("pretty") {
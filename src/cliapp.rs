@@ -263,10 +263,36 @@ pub fn make_app() -> App<'static, 'static> {
                         .value_name("PATH")
                         .help("The path to a PII processing config"),
                 )
+                .arg(
+                    Arg::with_name("pseudonymization_keys")
+                        .long("pseudonymization-keys")
+                        .value_name("PATH")
+                        .requires("pii_config")
+                        .help("The path to a JSON file with keys for pseudonymization"),
+                )
+                .arg(
+                    Arg::with_name("pii_dry_run")
+                        .long("pii-dry-run")
+                        .requires("pii_config")
+                        .help(
+                            "Do not modify the event, but print a report of what the PII \
+                             config would redact to stderr",
+                        ),
+                )
                 .arg(
                     Arg::with_name("store")
                         .long("store")
                         .help("Run through store normalization"),
+                )
+                .arg(
+                    Arg::with_name("fingerprinting_rules")
+                        .long("fingerprinting-rules")
+                        .value_name("PATH")
+                        .help(
+                            "The path to a JSON file with fingerprinting rules. Rules are \
+                             applied before PII scrubbing and store normalization. The index \
+                             of the matching rule is printed to stderr",
+                        ),
                 ),
         )
         .subcommand(